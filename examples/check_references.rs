@@ -0,0 +1,39 @@
+// Check that all nodes of ways and all members of relations are part of the PBF file given as
+// the first command line argument. Exits with status 1 if references are missing.
+
+use osmpbf::IndexedReader;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let arg = std::env::args_os()
+        .nth(1)
+        .ok_or("need a *.osm.pbf file as argument")?;
+    let mut reader = IndexedReader::from_path(&arg)?;
+
+    let mut missing = 0_u64;
+    reader.for_each_missing_reference(|reference| {
+        missing += 1;
+        match reference.role {
+            Some(role) => println!(
+                "{:?} {} is missing, referenced by {:?} {} with role {role:?}",
+                reference.element_type,
+                reference.id,
+                reference.referrer_type,
+                reference.referrer_id
+            ),
+            None => println!(
+                "{:?} {} is missing, referenced by {:?} {}",
+                reference.element_type,
+                reference.id,
+                reference.referrer_type,
+                reference.referrer_id
+            ),
+        }
+    })?;
+
+    println!("missing references: {missing}");
+    if missing > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,7 +1,7 @@
 // Count the number of buildings and their nodes from a PBF file
 // given as the first command line argument.
 
-use osmpbf::{Element, IndexedReader};
+use osmpbf::{Element, IndexedReader, TagFilter};
 use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -15,21 +15,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut ways = 0;
     let mut nodes = 0;
 
-    reader.read_ways_and_deps(
-        |way| {
-            // Filter ways. Return true if tags contain "building": "yes".
-            way.tags().any(|key_value| key_value == ("building", "yes"))
-        },
-        |element| {
-            // Increment counter for ways and nodes
-            match element {
-                Element::Way(_way) => ways += 1,
-                Element::Node(_node) => nodes += 1,
-                Element::DenseNode(_dense_node) => nodes += 1,
-                Element::Relation(_) => {} // should not occur
-            }
-        },
-    )?;
+    // Filter ways with the tag "building": "yes".
+    let mut filter = TagFilter::any();
+    filter.key_value("building", "yes");
+
+    reader.read_ways_and_deps_filtered(&filter, |element| {
+        // Increment counter for ways and nodes
+        match element {
+            Element::Way(_way) => ways += 1,
+            Element::Node(_node) => nodes += 1,
+            Element::DenseNode(_dense_node) => nodes += 1,
+            Element::Relation(_) => {} // should not occur
+        }
+    })?;
 
     // Print result
     println!("ways:  {ways}\nnodes: {nodes}");
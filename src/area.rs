@@ -0,0 +1,537 @@
+//! Assemble areas from closed ways and multipolygon relations
+//!
+//! An area is created for
+//! * each closed way (the first and the last node are the same) that has tags, unless it is
+//!   tagged `area=no`. Untagged closed ways are usually members of multipolygons.
+//! * each relation tagged `type=multipolygon` or `type=boundary`.
+//!
+//! The member ways of a relation are stitched together into rings by their node IDs, regardless
+//! of their roles. Then the nesting of the rings decides which rings are outer rings and which
+//! are inner rings (holes), just like in `osmium`. Member roles other than `outer`, `inner` or an
+//! empty role are ignored, so that e.g. `subarea` members of boundaries are not part of the area.
+//!
+//! See [`IndexedReader::for_each_area`](crate::indexed::IndexedReader::for_each_area).
+
+use crate::elements::WayNodeLocation;
+use crate::extract::ring_contains;
+use std::collections::{HashMap, HashSet};
+
+/// The element that an [`Area`] was assembled from.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AreaSource {
+    /// A closed way with the given ID.
+    Way(i64),
+    /// A multipolygon or boundary relation with the given ID.
+    Relation(i64),
+}
+
+/// A polygon with one outer ring and any number of inner rings (holes).
+///
+/// Rings are closed, so the first and the last location are the same. Outer rings are oriented
+/// counterclockwise and inner rings clockwise (with longitude as x and latitude as y), like in
+/// GeoJSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AreaPolygon {
+    /// The outer ring.
+    pub outer: Vec<WayNodeLocation>,
+    /// The inner rings.
+    pub inners: Vec<Vec<WayNodeLocation>>,
+}
+
+/// A problem that was found while assembling an [`Area`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum AreaProblem {
+    /// A member way of the relation is missing in the file.
+    MissingWay { way_id: i64 },
+    /// The location of a node is unknown.
+    MissingNodeLocation { way_id: i64, node_id: i64 },
+    /// The member ways could not be joined into a closed ring. The ring starts and ends at the
+    /// given nodes.
+    OpenRing {
+        start_node_id: i64,
+        end_node_id: i64,
+    },
+    /// A ring has fewer than three distinct nodes.
+    DegenerateRing { way_ids: Vec<i64> },
+    /// The role of a member way does not match the nesting of its ring. `expected` is either
+    /// "outer" or "inner".
+    RoleMismatch {
+        way_id: i64,
+        role: String,
+        expected: &'static str,
+    },
+    /// The relation has no member ways with the roles `outer`, `inner` or an empty role.
+    NoMemberWays,
+    /// A ring crosses another ring, so that it is neither inside nor outside of it. The ring is
+    /// not part of the polygons.
+    CrossingRing { way_ids: Vec<i64> },
+}
+
+/// An area that was assembled from a closed way or a relation.
+///
+/// If problems were found, [`polygons`](Area::polygons) is empty for broken geometries (missing
+/// ways or locations, rings that are not closed) but still contains the polygons if only the
+/// roles of members are wrong. Rings that cross other rings are left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Area {
+    /// The element this area was assembled from.
+    pub source: AreaSource,
+    /// The tags of the way or relation.
+    pub tags: Vec<(String, String)>,
+    /// The polygons of the area.
+    pub polygons: Vec<AreaPolygon>,
+    /// The problems that were found, empty for valid areas.
+    pub problems: Vec<AreaProblem>,
+}
+
+impl Area {
+    /// Returns true if no problems were found while assembling the area.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Returns true if a way with the given refs and tags is an area.
+pub(crate) fn is_closed_way_area(refs: &[i64], tags: &[(String, String)]) -> bool {
+    refs.len() >= 2
+        && refs.first() == refs.last()
+        && !tags.is_empty()
+        && !tags.iter().any(|(k, v)| k == "area" && v == "no")
+}
+
+/// Returns true if a relation with the given tags is an area.
+pub(crate) fn is_area_relation<'a, I>(mut tags: I) -> bool
+where
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
+    tags.any(|tag| tag == ("type", "multipolygon") || tag == ("type", "boundary"))
+}
+
+/// Returns true if a member way with this role is part of the area.
+pub(crate) fn is_area_role(role: &str) -> bool {
+    matches!(role, "outer" | "inner" | "")
+}
+
+/// A member way of an area with resolved node locations.
+#[derive(Clone, Debug)]
+pub(crate) struct AreaWay {
+    pub(crate) id: i64,
+    pub(crate) refs: Vec<i64>,
+    pub(crate) locations: Vec<Option<WayNodeLocation>>,
+}
+
+/// A ring with the IDs and locations of its nodes and the IDs of the ways it consists of.
+struct Ring {
+    nodes: Vec<(i64, WayNodeLocation)>,
+    way_ids: Vec<i64>,
+}
+
+impl Ring {
+    fn coordinates(&self) -> Vec<(f64, f64)> {
+        self.nodes
+            .iter()
+            .map(|(_, location)| (location.lon(), location.lat()))
+            .collect()
+    }
+
+    /// Twice the signed area, positive for counterclockwise rings.
+    fn signed_area(&self) -> f64 {
+        self.nodes
+            .windows(2)
+            .map(|w| {
+                let (a, b) = (w[0].1, w[1].1);
+                a.lon() * b.lat() - b.lon() * a.lat()
+            })
+            .sum()
+    }
+
+    /// Returns true if this ring is inside the other ring. Both rings may share nodes.
+    fn is_inside(&self, other: &Ring, other_coordinates: &[(f64, f64)]) -> bool {
+        let other_ids: HashSet<i64> = other.nodes.iter().map(|&(id, _)| id).collect();
+        let point = match self.nodes.iter().find(|(id, _)| !other_ids.contains(id)) {
+            Some(&(_, location)) => (location.lon(), location.lat()),
+            None => {
+                // All nodes are shared, test the midpoint of the first segment.
+                let (a, b) = (self.nodes[0].1, self.nodes[1].1);
+                ((a.lon() + b.lon()) / 2.0, (a.lat() + b.lat()) / 2.0)
+            }
+        };
+        ring_contains(other_coordinates, point.0, point.1)
+    }
+
+    fn into_locations(self, counterclockwise: bool) -> Vec<WayNodeLocation> {
+        let is_counterclockwise = self.signed_area() > 0.0;
+        let mut locations: Vec<WayNodeLocation> = self.nodes.into_iter().map(|(_, l)| l).collect();
+        if is_counterclockwise != counterclockwise {
+            locations.reverse();
+        }
+        locations
+    }
+}
+
+/// Returns the nodes of the way with their locations or reports a missing location.
+fn way_nodes(
+    way: &AreaWay,
+    problems: &mut Vec<AreaProblem>,
+) -> Option<Vec<(i64, WayNodeLocation)>> {
+    let mut nodes = Vec::with_capacity(way.refs.len());
+    for (&node_id, location) in way.refs.iter().zip(&way.locations) {
+        match location {
+            Some(location) => nodes.push((node_id, *location)),
+            None => {
+                problems.push(AreaProblem::MissingNodeLocation {
+                    way_id: way.id,
+                    node_id,
+                });
+                return None;
+            }
+        }
+    }
+    Some(nodes)
+}
+
+/// Assembles the area of a closed way.
+pub(crate) fn assemble_way(way: &AreaWay, tags: Vec<(String, String)>) -> Area {
+    let mut problems = vec![];
+    let polygons = way_nodes(way, &mut problems)
+        .map(|nodes| Ring {
+            nodes,
+            way_ids: vec![way.id],
+        })
+        .and_then(|ring| check_ring(ring, &mut problems))
+        .map(|ring| {
+            vec![AreaPolygon {
+                outer: ring.into_locations(true),
+                inners: vec![],
+            }]
+        })
+        .unwrap_or_default();
+
+    Area {
+        source: AreaSource::Way(way.id),
+        tags,
+        polygons,
+        problems,
+    }
+}
+
+/// Returns the ring if it has at least three distinct nodes.
+fn check_ring(ring: Ring, problems: &mut Vec<AreaProblem>) -> Option<Ring> {
+    if ring.nodes.len() < 4 {
+        problems.push(AreaProblem::DegenerateRing {
+            way_ids: ring.way_ids,
+        });
+        None
+    } else {
+        Some(ring)
+    }
+}
+
+/// Joins the ways into closed rings.
+fn stitch_rings(
+    ways: &[(&AreaWay, Vec<(i64, WayNodeLocation)>)],
+    problems: &mut Vec<AreaProblem>,
+) -> Vec<Ring> {
+    let mut unused: Vec<bool> = vec![true; ways.len()];
+    let mut endpoints: HashMap<i64, Vec<usize>> = HashMap::new();
+    for (index, (_, nodes)) in ways.iter().enumerate() {
+        if let (Some(first), Some(last)) = (nodes.first(), nodes.last()) {
+            endpoints.entry(first.0).or_default().push(index);
+            endpoints.entry(last.0).or_default().push(index);
+        }
+    }
+
+    let mut rings = vec![];
+    for start in 0..ways.len() {
+        if !unused[start] || ways[start].1.is_empty() {
+            continue;
+        }
+        unused[start] = false;
+        let mut ring = Ring {
+            nodes: ways[start].1.clone(),
+            way_ids: vec![ways[start].0.id],
+        };
+
+        loop {
+            let start_id = ring.nodes[0].0;
+            let end_id = ring.nodes[ring.nodes.len() - 1].0;
+            if ring.nodes.len() > 1 && start_id == end_id {
+                rings.push(ring);
+                break;
+            }
+
+            let next = endpoints
+                .get(&end_id)
+                .and_then(|candidates| candidates.iter().copied().find(|&i| unused[i]));
+            let Some(next) = next else {
+                problems.push(AreaProblem::OpenRing {
+                    start_node_id: start_id,
+                    end_node_id: end_id,
+                });
+                break;
+            };
+            unused[next] = false;
+            let (way, nodes) = &ways[next];
+            ring.way_ids.push(way.id);
+            if nodes[0].0 == end_id {
+                ring.nodes.extend_from_slice(&nodes[1..]);
+            } else {
+                ring.nodes.extend(nodes.iter().rev().skip(1));
+            }
+        }
+    }
+    rings
+}
+
+/// Assembles the area of a multipolygon or boundary relation from its member ways. Each member is
+/// the role and the way, or `None` if the way is missing.
+pub(crate) fn assemble_relation(
+    id: i64,
+    tags: Vec<(String, String)>,
+    members: &[(i64, &str, Option<&AreaWay>)],
+) -> Area {
+    let mut problems = vec![];
+    let mut ways = vec![];
+    let mut roles: HashMap<i64, &str> = HashMap::new();
+    let mut broken = false;
+
+    for &(way_id, role, way) in members {
+        if !is_area_role(role) || roles.insert(way_id, role).is_some() {
+            continue;
+        }
+        match way {
+            Some(way) => match way_nodes(way, &mut problems) {
+                Some(nodes) => ways.push((way, nodes)),
+                None => broken = true,
+            },
+            None => {
+                problems.push(AreaProblem::MissingWay { way_id });
+                broken = true;
+            }
+        }
+    }
+
+    if roles.is_empty() {
+        problems.push(AreaProblem::NoMemberWays);
+    }
+
+    let problems_before = problems.len();
+    let rings: Vec<Ring> = stitch_rings(&ways, &mut problems)
+        .into_iter()
+        .filter_map(|ring| check_ring(ring, &mut problems))
+        .collect();
+    broken |= problems.len() > problems_before;
+
+    let polygons = if broken {
+        vec![]
+    } else {
+        nest_rings(rings, &roles, &mut problems)
+    };
+
+    Area {
+        source: AreaSource::Relation(id),
+        tags,
+        polygons,
+        problems,
+    }
+}
+
+/// Decides which rings are outer and inner rings by their nesting and groups them into
+/// polygons. Also checks the roles of the member ways.
+fn nest_rings(
+    all_rings: Vec<Ring>,
+    roles: &HashMap<i64, &str>,
+    problems: &mut Vec<AreaProblem>,
+) -> Vec<AreaPolygon> {
+    let all_coordinates: Vec<Vec<(f64, f64)>> = all_rings.iter().map(Ring::coordinates).collect();
+    let crossing = crossing_rings(&all_coordinates);
+    let mut rings = vec![];
+    let mut coordinates = vec![];
+    for (i, (ring, ring_coordinates)) in all_rings.into_iter().zip(all_coordinates).enumerate() {
+        if crossing.contains(&i) {
+            problems.push(AreaProblem::CrossingRing {
+                way_ids: ring.way_ids,
+            });
+        } else {
+            rings.push(ring);
+            coordinates.push(ring_coordinates);
+        }
+    }
+
+    // For each ring, the indices of all rings that contain it.
+    let containers: Vec<Vec<usize>> = (0..rings.len())
+        .map(|i| {
+            (0..rings.len())
+                .filter(|&j| i != j && rings[i].is_inside(&rings[j], &coordinates[j]))
+                .collect()
+        })
+        .collect();
+
+    // Rings with an even depth are outer rings. The parent of an inner ring is the containing
+    // outer ring with the largest depth.
+    let depth = |i: usize| containers[i].len();
+    let outer_rings: Vec<usize> = (0..rings.len()).filter(|&i| depth(i) % 2 == 0).collect();
+    let mut polygon_of_ring: Vec<Option<usize>> = vec![None; rings.len()];
+    for (polygon, &outer) in outer_rings.iter().enumerate() {
+        polygon_of_ring[outer] = Some(polygon);
+    }
+
+    for (i, ring) in rings.iter().enumerate() {
+        let expected = if depth(i) % 2 == 0 { "outer" } else { "inner" };
+        for way_id in &ring.way_ids {
+            let role = roles[way_id];
+            if !role.is_empty() && role != expected {
+                problems.push(AreaProblem::RoleMismatch {
+                    way_id: *way_id,
+                    role: role.to_string(),
+                    expected,
+                });
+            }
+        }
+    }
+
+    let mut polygons: Vec<AreaPolygon> = vec![];
+    let mut inners: Vec<Vec<usize>> = vec![vec![]; outer_rings.len()];
+    for (i, ring_containers) in containers.iter().enumerate() {
+        if depth(i) % 2 == 1 {
+            // Without crossing rings, each inner ring is inside of an outer ring. Rings that overlap
+            // without crossing segments, e.g. two rings at the same locations, may still end up
+            // without one.
+            let parent = ring_containers
+                .iter()
+                .copied()
+                .filter(|&j| depth(j) % 2 == 0)
+                .max_by_key(|&j| depth(j));
+            match parent.and_then(|parent| polygon_of_ring[parent]) {
+                Some(polygon) => inners[polygon].push(i),
+                None => problems.push(AreaProblem::CrossingRing {
+                    way_ids: rings[i].way_ids.clone(),
+                }),
+            }
+        }
+    }
+
+    let mut rings: Vec<Option<Ring>> = rings.into_iter().map(Some).collect();
+    for (polygon, &outer) in outer_rings.iter().enumerate() {
+        let outer = rings[outer].take().expect("ring is used once");
+        polygons.push(AreaPolygon {
+            outer: outer.into_locations(true),
+            inners: inners[polygon]
+                .iter()
+                .map(|&i| {
+                    rings[i]
+                        .take()
+                        .expect("ring is used once")
+                        .into_locations(false)
+                })
+                .collect(),
+        });
+    }
+    polygons
+}
+
+/// Returns the indices of all rings that cross another ring. Segments that only touch each
+/// other, e.g. at a shared node, do not cross.
+fn crossing_rings(coordinates: &[Vec<(f64, f64)>]) -> HashSet<usize> {
+    // The segments of all rings with their ring index, each from west to east and sorted by their
+    // western end, so that only overlapping longitude ranges are compared.
+    let mut segments: Vec<_> = coordinates
+        .iter()
+        .enumerate()
+        .flat_map(|(ring, ring_coordinates)| {
+            ring_coordinates.windows(2).map(move |w| {
+                if w[0].0 <= w[1].0 {
+                    (ring, w[0], w[1])
+                } else {
+                    (ring, w[1], w[0])
+                }
+            })
+        })
+        .collect();
+    segments.sort_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+
+    let mut crossing = HashSet::new();
+    for (k, &(ring, a, b)) in segments.iter().enumerate() {
+        for &(other_ring, c, d) in &segments[k + 1..] {
+            if c.0 > b.0 {
+                break;
+            }
+            if ring != other_ring && segments_cross(a, b, c, d) {
+                crossing.insert(ring);
+                crossing.insert(other_ring);
+            }
+        }
+    }
+    crossing
+}
+
+/// Returns true if the segments a-b and c-d cross in a single point that is not an end point.
+fn segments_cross(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    // Positive if r is left of the line from p to q, negative if it is right of it.
+    let side = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+    side(a, b, c) * side(a, b, d) < 0.0 && side(c, d, a) * side(c, d, b) < 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn way(id: i64, nodes: &[(i64, f64, f64)]) -> AreaWay {
+        AreaWay {
+            id,
+            refs: nodes.iter().map(|n| n.0).collect(),
+            locations: nodes
+                .iter()
+                .map(|n| Some(WayNodeLocation::new((n.2 * 1e9) as i64, (n.1 * 1e9) as i64)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_square_with_hole() {
+        // Clockwise outer ring split into two ways and a counterclockwise inner ring
+        let a = way(1, &[(1, 0.0, 0.0), (2, 0.0, 10.0), (3, 10.0, 10.0)]);
+        let b = way(2, &[(1, 0.0, 0.0), (4, 10.0, 0.0), (3, 10.0, 10.0)]);
+        let hole = way(
+            3,
+            &[
+                (5, 4.0, 4.0),
+                (6, 6.0, 4.0),
+                (7, 6.0, 6.0),
+                (8, 4.0, 6.0),
+                (5, 4.0, 4.0),
+            ],
+        );
+        let area = assemble_relation(
+            10,
+            vec![],
+            &[
+                (1, "outer", Some(&a)),
+                (2, "", Some(&b)),
+                (3, "outer", Some(&hole)),
+            ],
+        );
+
+        assert_eq!(
+            area.problems,
+            [AreaProblem::RoleMismatch {
+                way_id: 3,
+                role: "outer".to_string(),
+                expected: "inner"
+            }]
+        );
+        assert_eq!(area.polygons.len(), 1);
+        let polygon = &area.polygons[0];
+        assert_eq!(polygon.outer.len(), 5);
+        assert_eq!(polygon.inners.len(), 1);
+        let ring = |locations: &[WayNodeLocation]| Ring {
+            nodes: locations.iter().map(|l| (0, *l)).collect(),
+            way_ids: vec![],
+        };
+        assert!(ring(&polygon.outer).signed_area() > 0.0);
+        assert!(ring(&polygon.inners[0]).signed_area() < 0.0);
+    }
+}
@@ -0,0 +1,283 @@
+//! Read blobs and elements from asynchronous readers
+//!
+//! This module is only available with the `async` feature.
+
+use crate::blob::{check_header_size, Blob, BlobType, ByteOffset, MAX_BLOB_MESSAGE_SIZE};
+use crate::block::PrimitiveBlock;
+use crate::elements::Element;
+use crate::error::{new_blob_error, new_protobuf_error, BlobError, Result};
+use crate::proto::fileformat;
+use futures_core::Stream;
+use protobuf::Message;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+
+/// A reader for PBF files that allows iterating over [`Blob`]s from an asynchronous reader.
+///
+/// This is the asynchronous counterpart of [`BlobReader`](crate::blob::BlobReader).
+#[derive(Debug)]
+pub struct AsyncBlobReader<R: AsyncRead + Unpin> {
+    reader: R,
+    /// Current reader offset in bytes from the start of the stream.
+    offset: Option<ByteOffset>,
+    last_blob_ok: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBlobReader<R> {
+    /// Creates a new `AsyncBlobReader`.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # async fn foo() -> Result<()> {
+    /// let f = tokio::fs::File::open("tests/test.osm.pbf").await?;
+    /// let buf_reader = tokio::io::BufReader::new(f);
+    ///
+    /// let reader = AsyncBlobReader::new(buf_reader);
+    ///
+    /// # Ok(())
+    /// # }
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(foo()).unwrap();
+    /// ```
+    pub fn new(reader: R) -> AsyncBlobReader<R> {
+        AsyncBlobReader {
+            reader,
+            offset: None,
+            last_blob_ok: true,
+        }
+    }
+
+    /// Reads the next [`Blob`]. Returns [`None`] at the end of the stream or after an error
+    /// occurred.
+    pub async fn next_blob(&mut self) -> Option<Result<Blob>> {
+        // Stop iteration if there was an error.
+        if !self.last_blob_ok {
+            return None;
+        }
+
+        let prev_offset = self.offset;
+
+        let header_size = match self.reader.read_u32().await {
+            Ok(n) => u64::from(n),
+            Err(e) => {
+                self.offset = None;
+                return match e.kind() {
+                    ::std::io::ErrorKind::UnexpectedEof => None,
+                    _ => {
+                        self.last_blob_ok = false;
+                        Some(Err(new_blob_error(BlobError::InvalidHeaderSize)))
+                    }
+                };
+            }
+        };
+
+        if let Err(err) = check_header_size(header_size) {
+            self.last_blob_ok = false;
+            return Some(Err(err));
+        }
+
+        let header: fileformat::BlobHeader =
+            match self.read_message(header_size, "blob header").await {
+                Ok(header) => header,
+                Err(err) => return Some(Err(err)),
+            };
+
+        let data_size = header.datasize() as u64;
+        if data_size >= MAX_BLOB_MESSAGE_SIZE {
+            self.last_blob_ok = false;
+            return Some(Err(new_blob_error(BlobError::MessageTooBig {
+                size: data_size,
+            })));
+        }
+
+        let blob = match self.read_message(data_size, "blob content").await {
+            Ok(blob) => blob,
+            Err(err) => return Some(Err(err)),
+        };
+
+        self.offset = self
+            .offset
+            .map(|x| ByteOffset(x.0 + 4 + header_size + data_size));
+
+        Some(Ok(Blob::new(header, blob, prev_offset)))
+    }
+
+    /// Converts this reader into a [`Stream`] of [`Blob`]s.
+    ///
+    /// # Example
+    /// ```
+    /// use futures_util::StreamExt;
+    /// use osmpbf::*;
+    ///
+    /// # async fn foo() -> Result<()> {
+    /// let reader = AsyncBlobReader::from_path("tests/test.osm.pbf").await?;
+    /// let blobs: Vec<Result<Blob>> = reader.into_stream().collect().await;
+    ///
+    /// assert_eq!(blobs.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(foo()).unwrap();
+    /// ```
+    pub fn into_stream(self) -> impl Stream<Item = Result<Blob>> {
+        futures_util::stream::unfold(self, |mut reader| async move {
+            reader.next_blob().await.map(|blob| (blob, reader))
+        })
+    }
+
+    /// Reads exactly `size` bytes and parses them as a protobuf message.
+    async fn read_message<M: Message>(&mut self, size: u64, location: &'static str) -> Result<M> {
+        let mut buf = vec![0; size as usize];
+        if let Err(e) = self.reader.read_exact(&mut buf).await {
+            self.offset = None;
+            self.last_blob_ok = false;
+            return Err(e.into());
+        }
+        M::parse_from_bytes(&buf).map_err(|e| {
+            self.offset = None;
+            self.last_blob_ok = false;
+            new_protobuf_error(e, location)
+        })
+    }
+}
+
+impl AsyncBlobReader<BufReader<File>> {
+    /// Tries to open the file at the given path and constructs an `AsyncBlobReader` from this.
+    /// If there are no errors, each blob will have a valid ([`Some`]) offset.
+    ///
+    /// # Errors
+    /// Returns the same errors that `tokio::fs::File::open` returns.
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path).await?;
+        Ok(AsyncBlobReader {
+            reader: BufReader::new(f),
+            offset: Some(ByteOffset(0)),
+            last_blob_ok: true,
+        })
+    }
+}
+
+/// Decodes the blob to a [`PrimitiveBlock`] on the blocking thread pool of the tokio runtime, so
+/// that the expensive decompression does not block the executor.
+async fn decode_primitive_block(blob: Blob) -> Result<PrimitiveBlock> {
+    tokio::task::spawn_blocking(move || blob.to_primitiveblock())
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// A reader for PBF files that gives access to the stored elements from an asynchronous reader.
+///
+/// This is the asynchronous counterpart of [`ElementReader`](crate::reader::ElementReader).
+/// Blobs are read asynchronously and decoded on the blocking thread pool of the tokio runtime.
+#[derive(Debug)]
+pub struct AsyncElementReader<R: AsyncRead + Unpin> {
+    blob_reader: AsyncBlobReader<R>,
+    failed: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncElementReader<R> {
+    /// Creates a new `AsyncElementReader`.
+    pub fn new(reader: R) -> AsyncElementReader<R> {
+        AsyncElementReader {
+            blob_reader: AsyncBlobReader::new(reader),
+            failed: false,
+        }
+    }
+
+    /// Reads and decodes the next [`PrimitiveBlock`]. Header blocks are checked for unsupported
+    /// required features and skipped, unknown blobs are skipped. Returns [`None`] at the end of
+    /// the stream or after an error occurred.
+    ///
+    /// # Errors
+    /// Returns an error of kind
+    /// [`ErrorKind::UnsupportedRequiredFeature`](crate::error::ErrorKind::UnsupportedRequiredFeature)
+    /// if a header block requires a feature that is not supported by this library.
+    pub async fn next_block(&mut self) -> Option<Result<PrimitiveBlock>> {
+        if self.failed {
+            return None;
+        }
+        let result = self.read_next_block().await;
+        if let Some(Err(_)) = result {
+            self.failed = true;
+        }
+        result
+    }
+
+    async fn read_next_block(&mut self) -> Option<Result<PrimitiveBlock>> {
+        loop {
+            let blob = match self.blob_reader.next_blob().await? {
+                Ok(blob) => blob,
+                Err(err) => return Some(Err(err)),
+            };
+            match blob.get_type() {
+                BlobType::OsmHeader => {
+                    let checked = blob
+                        .to_headerblock()
+                        .and_then(|header| header.check_required_features());
+                    if let Err(err) = checked {
+                        return Some(Err(err));
+                    }
+                }
+                BlobType::OsmData => return Some(decode_primitive_block(blob).await),
+                BlobType::Unknown(_) => {}
+            }
+        }
+    }
+
+    /// Converts this reader into a [`Stream`] of decoded [`PrimitiveBlock`]s.
+    pub fn into_block_stream(self) -> impl Stream<Item = Result<PrimitiveBlock>> {
+        futures_util::stream::unfold(self, |mut reader| async move {
+            reader.next_block().await.map(|block| (block, reader))
+        })
+    }
+
+    /// Decodes the PBF structure and calls the given closure on each element.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # async fn foo() -> Result<()> {
+    /// let reader = AsyncElementReader::from_path("tests/test.osm.pbf").await?;
+    /// let mut ways = 0_u64;
+    ///
+    /// // Increment the counter by one for each way.
+    /// reader.for_each(|element| {
+    ///     if let Element::Way(_) = element {
+    ///         ways += 1;
+    ///     }
+    /// }).await?;
+    ///
+    /// println!("Number of ways: {ways}");
+    /// # assert_eq!(ways, 1);
+    /// # Ok(())
+    /// # }
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(foo()).unwrap();
+    /// ```
+    pub async fn for_each<F>(mut self, mut f: F) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        while let Some(block) = self.next_block().await {
+            block?.for_each_element(&mut f);
+        }
+        Ok(())
+    }
+}
+
+impl AsyncElementReader<BufReader<File>> {
+    /// Tries to open the file at the given path and constructs an `AsyncElementReader` from this.
+    ///
+    /// # Errors
+    /// Returns the same errors that `tokio::fs::File::open` returns.
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(AsyncElementReader {
+            blob_reader: AsyncBlobReader::from_path(path).await?,
+            failed: false,
+        })
+    }
+}
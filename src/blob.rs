@@ -68,7 +68,7 @@ pub struct Blob {
 }
 
 impl Blob {
-    fn new(
+    pub(crate) fn new(
         header: fileformat::BlobHeader,
         blob: fileformat::Blob,
         offset: Option<ByteOffset>,
@@ -186,6 +186,11 @@ impl<R: Read + Send> BlobReader<R> {
         }
     }
 
+    /// Returns a reference to the underlying reader.
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
     fn read_blob_header(&mut self) -> Option<Result<fileformat::BlobHeader>> {
         let header_size: u64 = match self.reader.read_u32::<byteorder::BigEndian>() {
             Ok(n) => {
@@ -207,11 +212,9 @@ impl<R: Read + Send> BlobReader<R> {
             }
         };
 
-        if header_size >= MAX_BLOB_HEADER_SIZE {
+        if let Err(err) = check_header_size(header_size) {
             self.last_blob_ok = false;
-            return Some(Err(new_blob_error(BlobError::HeaderTooBig {
-                size: header_size,
-            })));
+            return Some(Err(err));
         }
 
         let mut reader = self.reader.by_ref().take(header_size);
@@ -452,25 +455,121 @@ impl BlobReader<BufReader<File>> {
     }
 }
 
+/// Returns an error if a [`BlobHeader`] of the given size in bytes is not smaller than
+/// [`MAX_BLOB_HEADER_SIZE`].
+pub(crate) fn check_header_size(size: u64) -> Result<()> {
+    if size >= MAX_BLOB_HEADER_SIZE {
+        Err(new_blob_error(BlobError::HeaderTooBig { size }))
+    } else {
+        Ok(())
+    }
+}
+
 pub(crate) fn decode_blob<T: Message>(blob: &fileformat::Blob) -> Result<T> {
-    if blob.has_raw() {
-        let size = blob.raw().len() as u64;
-        if size < MAX_BLOB_MESSAGE_SIZE {
-            T::parse_from_bytes(blob.raw()).map_err(|e| new_protobuf_error(e, "raw blob data"))
-        } else {
-            Err(new_blob_error(BlobError::MessageTooBig { size }))
+    use fileformat::blob::Data;
+
+    match blob.data.as_ref() {
+        Some(Data::Raw(raw)) => {
+            let size = raw.len() as u64;
+            if size < MAX_BLOB_MESSAGE_SIZE {
+                T::parse_from_bytes(raw).map_err(|e| new_protobuf_error(e, "raw blob data"))
+            } else {
+                Err(new_blob_error(BlobError::MessageTooBig { size }))
+            }
         }
-    } else if blob.has_zlib_data() {
-        let mut decoder = ZlibDecoder::new(blob.zlib_data()).take(MAX_BLOB_MESSAGE_SIZE);
-        T::parse_from_reader(&mut decoder).map_err(|e| new_protobuf_error(e, "blob zlib data"))
-    } else {
-        Err(new_blob_error(BlobError::Empty))
+        Some(Data::ZlibData(data)) => {
+            let mut decoder = ZlibDecoder::new(data.as_slice()).take(MAX_BLOB_MESSAGE_SIZE);
+            T::parse_from_reader(&mut decoder).map_err(|e| new_protobuf_error(e, "blob zlib data"))
+        }
+        Some(Data::ZstdData(data)) => decode_zstd(data),
+        Some(Data::Lz4Data(data)) => decode_lz4(data, blob.raw_size()),
+        Some(Data::LzmaData(data)) => decode_lzma(data),
+        Some(Data::OBSOLETEBzip2Data(_)) => {
+            Err(new_blob_error(BlobError::UnsupportedCompression {
+                compression: "bzip2",
+            }))
+        }
+        None => Err(new_blob_error(BlobError::Empty)),
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decode_zstd<T: Message>(data: &[u8]) -> Result<T> {
+    let mut decoder = zstd::stream::read::Decoder::new(data)?.take(MAX_BLOB_MESSAGE_SIZE);
+    T::parse_from_reader(&mut decoder).map_err(|e| new_protobuf_error(e, "blob zstd data"))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decode_zstd<T: Message>(_data: &[u8]) -> Result<T> {
+    Err(new_blob_error(BlobError::UnsupportedCompression {
+        compression: "zstd",
+    }))
+}
+
+#[cfg(feature = "lz4")]
+fn decode_lz4<T: Message>(data: &[u8], raw_size: i32) -> Result<T> {
+    // The LZ4 block format does not store the uncompressed size, so it has to be taken from the
+    // `raw_size` field.
+    let size = u64::try_from(raw_size).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "missing raw size of lz4 blob",
+        )
+    })?;
+    if size >= MAX_BLOB_MESSAGE_SIZE {
+        return Err(new_blob_error(BlobError::MessageTooBig { size }));
     }
+    let raw = lz4_flex::block::decompress(data, size as usize)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    T::parse_from_bytes(&raw).map_err(|e| new_protobuf_error(e, "blob lz4 data"))
+}
+
+#[cfg(not(feature = "lz4"))]
+fn decode_lz4<T: Message>(_data: &[u8], _raw_size: i32) -> Result<T> {
+    Err(new_blob_error(BlobError::UnsupportedCompression {
+        compression: "lz4",
+    }))
+}
+
+#[cfg(feature = "lzma")]
+fn decode_lzma<T: Message>(data: &[u8]) -> Result<T> {
+    /// A writer that fails as soon as more than `MAX_BLOB_MESSAGE_SIZE` bytes are written.
+    struct LimitedWriter(Vec<u8>);
+
+    impl std::io::Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if (self.0.len() + buf.len()) as u64 > MAX_BLOB_MESSAGE_SIZE {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "lzma blob data is too big",
+                ));
+            }
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut raw = LimitedWriter(vec![]);
+    lzma_rs::lzma_decompress(&mut BufReader::new(data), &mut raw)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    T::parse_from_bytes(&raw.0).map_err(|e| new_protobuf_error(e, "blob lzma data"))
+}
+
+#[cfg(not(feature = "lzma"))]
+fn decode_lzma<T: Message>(_data: &[u8]) -> Result<T> {
+    Err(new_blob_error(BlobError::UnsupportedCompression {
+        compression: "lzma",
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::proto::osmformat;
 
     #[test]
     fn test_get_type() {
@@ -490,4 +589,34 @@ mod tests {
             assert_eq!(blob.get_type(), *blob_type);
         }
     }
+
+    #[test]
+    fn test_unsupported_compression() {
+        let mut blob = fileformat::Blob::new();
+        blob.set_OBSOLETE_bzip2_data(vec![1, 2, 3]);
+        let err = decode_blob::<osmformat::PrimitiveBlock>(&blob).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Blob(BlobError::UnsupportedCompression {
+                compression: "bzip2"
+            })
+        ));
+
+        #[cfg(not(feature = "zstd"))]
+        {
+            let mut blob = fileformat::Blob::new();
+            blob.set_zstd_data(vec![1, 2, 3]);
+            let err = decode_blob::<osmformat::PrimitiveBlock>(&blob).unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                ErrorKind::Blob(BlobError::UnsupportedCompression {
+                    compression: "zstd"
+                })
+            ));
+        }
+
+        let blob = fileformat::Blob::new();
+        let err = decode_blob::<osmformat::PrimitiveBlock>(&blob).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Blob(BlobError::Empty)));
+    }
 }
@@ -1,11 +1,16 @@
 //! `HeaderBlock`, `PrimitiveBlock` and `PrimitiveGroup`s
 
-use crate::dense::DenseNodeIter;
-use crate::elements::{Element, Node, Relation, Way};
+use crate::dense::{DenseNodeIter, DenseNodePosition};
+use crate::elements::{Changeset, Element, Node, Relation, Way};
 use crate::error::{new_error, ErrorKind, Result};
+use crate::filter::TagFilter;
 use crate::proto::osmformat;
 use std;
 
+/// The required features of a [`HeaderBlock`] that this crate can handle.
+pub const SUPPORTED_REQUIRED_FEATURES: &[&str] =
+    &["OsmSchema-V0.6", "DenseNodes", "HistoricalInformation"];
+
 /// A `HeaderBlock`. It contains metadata about following [`PrimitiveBlock`]s.
 #[derive(Clone, Debug)]
 pub struct HeaderBlock {
@@ -17,6 +22,10 @@ impl HeaderBlock {
         HeaderBlock { header }
     }
 
+    pub(crate) fn as_proto(&self) -> &osmformat::HeaderBlock {
+        &self.header
+    }
+
     /// Returns the (optional) bounding box of the included features.
     pub fn bbox(&self) -> Option<HeaderBBox> {
         self.header.bbox.as_ref().map(|bbox| HeaderBBox {
@@ -33,11 +42,40 @@ impl HeaderBlock {
         self.header.required_features.as_slice()
     }
 
+    /// Checks that all [`required_features`](HeaderBlock::required_features) are supported by
+    /// this crate (see [`SUPPORTED_REQUIRED_FEATURES`]).
+    ///
+    /// # Errors
+    /// Returns an [`ErrorKind::UnsupportedRequiredFeature`] error for the first required feature
+    /// that is not supported.
+    pub fn check_required_features(&self) -> Result<()> {
+        match self
+            .required_features()
+            .iter()
+            .find(|feature| !SUPPORTED_REQUIRED_FEATURES.contains(&feature.as_str()))
+        {
+            Some(feature) => Err(new_error(ErrorKind::UnsupportedRequiredFeature {
+                feature: feature.clone(),
+            })),
+            None => Ok(()),
+        }
+    }
+
     /// Returns a list of optional features that a parser can choose to ignore.
     pub fn optional_features(&self) -> &[String] {
         self.header.optional_features.as_slice()
     }
 
+    /// Returns true if the optional feature `Sort.Type_then_ID` is declared, i.e. the writer of
+    /// the file claims that all nodes come before all ways and all ways before all relations, and
+    /// that elements of the same type are sorted by ascending ID. The claim can be verified with
+    /// [`ElementReader::check_sort_order`](crate::reader::ElementReader::check_sort_order).
+    pub fn is_sorted_by_type_then_id(&self) -> bool {
+        self.optional_features()
+            .iter()
+            .any(|feature| feature == "Sort.Type_then_ID")
+    }
+
     /// Returns the name of the program that generated the file or `None` if unset.
     pub fn writing_program(&self) -> Option<&str> {
         if self.header.has_writingprogram() {
@@ -88,14 +126,15 @@ impl HeaderBlock {
 /// A bounding box that is usually included in a [`HeaderBlock`].
 /// The maximum precision of the coordinates is one nanodegree (10⁻⁹).
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct HeaderBBox {
     /// left coordinate in degrees (minimum longitude)
     pub left: f64,
     /// right coordinate in degrees (maximum longitude)
     pub right: f64,
-    /// top coordinate in degrees (minimum latitude)
+    /// top coordinate in degrees (maximum latitude)
     pub top: f64,
-    /// bottom coordinate in degrees (maximum latitude)
+    /// bottom coordinate in degrees (minimum latitude)
     pub bottom: f64,
 }
 
@@ -110,6 +149,10 @@ impl PrimitiveBlock {
         PrimitiveBlock { block }
     }
 
+    pub(crate) fn as_proto(&self) -> &osmformat::PrimitiveBlock {
+        &self.block
+    }
+
     /// Returns an iterator over the elements in this `PrimitiveBlock`.
     pub fn elements(&self) -> BlockElementsIter<'_> {
         BlockElementsIter::new(&self.block)
@@ -141,6 +184,58 @@ impl PrimitiveBlock {
         }
     }
 
+    /// Calls the given closure on each element with tags that match the filter. The filter is
+    /// compiled once for this block, see [`TagFilter::compile`].
+    pub fn for_each_element_filtered<F>(&self, filter: &TagFilter, mut f: F)
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        let filter = filter.compile(self);
+        if !filter.can_match() {
+            return;
+        }
+        self.for_each_element(|element| {
+            if filter.matches(&element) {
+                f(element);
+            }
+        });
+    }
+
+    /// Calls the given closure on each changeset, see [`PrimitiveGroup::changesets`].
+    pub fn for_each_changeset<F>(&self, mut f: F)
+    where
+        F: for<'a> FnMut(Changeset<'a>),
+    {
+        for group in self.groups() {
+            group.changesets().for_each(&mut f);
+        }
+    }
+
+    /// Returns the granularity of coordinates in nanodegrees (10⁻⁹). The default is 100.
+    ///
+    /// Coordinates are stored as integers and converted to nanodegrees with
+    /// `offset + granularity * value`.
+    pub fn granularity(&self) -> i32 {
+        self.block.granularity()
+    }
+
+    /// Returns the offset that is added to all latitude coordinates of this block in
+    /// nanodegrees (10⁻⁹). The default is 0.
+    pub fn lat_offset(&self) -> i64 {
+        self.block.lat_offset()
+    }
+
+    /// Returns the offset that is added to all longitude coordinates of this block in
+    /// nanodegrees (10⁻⁹). The default is 0.
+    pub fn lon_offset(&self) -> i64 {
+        self.block.lon_offset()
+    }
+
+    /// Returns the granularity of time stamps in milliseconds. The default is 1000.
+    pub fn date_granularity(&self) -> i32 {
+        self.block.date_granularity()
+    }
+
     /// Returns the raw stringtable. Elements in a `PrimitiveBlock` do not store strings
     /// themselves; instead, they just store indices to the stringtable. By convention, the
     /// contained strings are UTF-8 encoded but it is not safe to assume that (use
@@ -184,6 +279,12 @@ impl<'a> PrimitiveGroup<'a> {
     pub fn relations(&self) -> GroupRelationIter<'a> {
         GroupRelationIter::new(self.block, self.group)
     }
+
+    /// Returns an iterator over the changesets in this group. Changesets are not included in
+    /// [`PrimitiveBlock::elements`] and [`PrimitiveBlock::for_each_element`].
+    pub fn changesets(&self) -> GroupChangesetIter<'a> {
+        GroupChangesetIter::new(self.group)
+    }
 }
 
 /// An iterator over the elements in a [`PrimitiveGroup`].
@@ -198,7 +299,7 @@ pub struct BlockElementsIter<'a> {
     relations: std::slice::Iter<'a, osmformat::Relation>,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 enum ElementsIterState {
     Group,
     DenseNode,
@@ -220,6 +321,92 @@ impl<'a> BlockElementsIter<'a> {
         }
     }
 
+    /// Returns the current position that does not borrow the block.
+    pub(crate) fn position(&self) -> ElementsPosition {
+        let groups_consumed = self.block.primitivegroup.len() - self.groups.len();
+        let mut position = ElementsPosition {
+            groups_consumed,
+            state: self.state,
+            consumed: 0,
+            dense: DenseNodePosition::default(),
+        };
+
+        if let Some(group) = groups_consumed
+            .checked_sub(1)
+            .map(|i| &self.block.primitivegroup[i])
+        {
+            match self.state {
+                ElementsIterState::Group => {}
+                ElementsIterState::DenseNode => {
+                    position.dense = self.dense_nodes.position(group.dense.get_or_default())
+                }
+                ElementsIterState::Node => position.consumed = group.nodes.len() - self.nodes.len(),
+                ElementsIterState::Way => position.consumed = group.ways.len() - self.ways.len(),
+                ElementsIterState::Relation => {
+                    position.consumed = group.relations.len() - self.relations.len()
+                }
+            }
+        }
+
+        position
+    }
+
+    /// Creates an iterator that resumes at the given position. The position has to be created
+    /// from an iterator over the same block.
+    pub(crate) fn at_position(
+        block: &'a osmformat::PrimitiveBlock,
+        position: &ElementsPosition,
+    ) -> BlockElementsIter<'a> {
+        let mut iter = BlockElementsIter::new(block);
+        iter.groups = block.primitivegroup[position.groups_consumed..].iter();
+        iter.state = position.state;
+
+        if position.state != ElementsIterState::Group {
+            let group = &block.primitivegroup[position.groups_consumed - 1];
+
+            // Lists of states that were already passed are empty, lists of following states are
+            // complete.
+            fn rest<'b, T>(
+                slice: &'b [T],
+                list_state: ElementsIterState,
+                position: &ElementsPosition,
+            ) -> std::slice::Iter<'b, T> {
+                match position.state.cmp(&list_state) {
+                    std::cmp::Ordering::Less => slice.iter(),
+                    std::cmp::Ordering::Equal => slice[position.consumed..].iter(),
+                    std::cmp::Ordering::Greater => [].iter(),
+                }
+            }
+
+            if position.state == ElementsIterState::DenseNode {
+                iter.dense_nodes = DenseNodeIter::at_position(
+                    block,
+                    group.dense.get_or_default(),
+                    &position.dense,
+                );
+            }
+            iter.nodes = rest(&group.nodes, ElementsIterState::Node, position);
+            iter.ways = rest(&group.ways, ElementsIterState::Way, position);
+            iter.relations = rest(&group.relations, ElementsIterState::Relation, position);
+        }
+
+        iter
+    }
+
+    /// Returns true if there are no more elements.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.dense_nodes.len() == 0
+            && self.nodes.len() == 0
+            && self.ways.len() == 0
+            && self.relations.len() == 0
+            && self.groups.clone().all(|group| {
+                group.dense.id.is_empty()
+                    && group.nodes.is_empty()
+                    && group.ways.is_empty()
+                    && group.relations.is_empty()
+            })
+    }
+
     /// Performs an internal iteration step. Returns [`None`] until there is a value for the iterator to
     /// return. Returns [`Some(None)`] to end the iteration.
     #[inline]
@@ -282,6 +469,28 @@ impl<'a> Iterator for BlockElementsIter<'a> {
     }
 }
 
+/// The position of a [`BlockElementsIter`]. It does not borrow the block, so the iteration can be
+/// resumed later with [`BlockElementsIter::at_position`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ElementsPosition {
+    groups_consumed: usize,
+    state: ElementsIterState,
+    /// Number of consumed nodes, ways or relations in the current group (depending on `state`).
+    consumed: usize,
+    dense: DenseNodePosition,
+}
+
+impl Default for ElementsPosition {
+    fn default() -> Self {
+        ElementsPosition {
+            groups_consumed: 0,
+            state: ElementsIterState::Group,
+            consumed: 0,
+            dense: DenseNodePosition::default(),
+        }
+    }
+}
+
 /// An iterator over the groups in a [`PrimitiveBlock`].
 #[derive(Clone, Debug)]
 pub struct GroupIter<'a> {
@@ -423,6 +632,34 @@ impl<'a> Iterator for GroupRelationIter<'a> {
 
 impl ExactSizeIterator for GroupRelationIter<'_> {}
 
+/// An iterator over the changesets in a [`PrimitiveGroup`].
+#[derive(Clone, Debug)]
+pub struct GroupChangesetIter<'a> {
+    changesets: std::slice::Iter<'a, osmformat::ChangeSet>,
+}
+
+impl<'a> GroupChangesetIter<'a> {
+    fn new(group: &'a osmformat::PrimitiveGroup) -> GroupChangesetIter<'a> {
+        GroupChangesetIter {
+            changesets: group.changesets.iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for GroupChangesetIter<'a> {
+    type Item = Changeset<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.changesets.next().map(Changeset::new)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.changesets.size_hint()
+    }
+}
+
+impl ExactSizeIterator for GroupChangesetIter<'_> {}
+
 pub(crate) fn str_from_stringtable(
     block: &osmformat::PrimitiveBlock,
     index: usize,
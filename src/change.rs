@@ -0,0 +1,388 @@
+//! Read OsmChange files and apply them to PBF files
+//!
+//! OsmChange files (`.osc`, often compressed as `.osc.gz`) are the format of the minutely, hourly
+//! and daily replication diffs that keep a copy of the OpenStreetMap data up to date. Each file
+//! lists elements in `<create>`, `<modify>` and `<delete>` sections.
+//!
+//! This module is only available with the `xml` feature.
+
+use crate::blob::BlobDecode;
+use crate::block::PrimitiveBlock;
+use crate::error::{new_error, ErrorKind, Result};
+use crate::owned::OwnedElement;
+use crate::reader::ElementReader;
+use crate::writer::{HeaderBuilder, PbfWriter, ELEMENTS_PER_BLOCK};
+use crate::xml::{new_reader, open_xml_file, parse_element, read_event, xml_error};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+/// The action of a [`Change`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChangeAction {
+    /// The element was created.
+    Create,
+    /// A new version of the element replaces the old one.
+    Modify,
+    /// The element was deleted.
+    Delete,
+}
+
+/// A single change of an OsmChange file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Change {
+    /// What happened to the element.
+    pub action: ChangeAction,
+    /// The new version of the element. Deleted elements usually only have an ID and metadata.
+    pub element: OwnedElement,
+}
+
+/// A reader for OsmChange files. It is an iterator over the [`Change`]s in file order.
+///
+/// The iteration stops after the first error.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let osc = br#"<osmChange version="0.6">
+///   <delete><node id="106" version="2"/></delete>
+/// </osmChange>"#;
+///
+/// for change in OsmChangeReader::new(&osc[..]) {
+///     let change = change?;
+///     assert_eq!(change.action, ChangeAction::Delete);
+///     assert_eq!(change.element.id(), 106);
+/// }
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub struct OsmChangeReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    action: Option<ChangeAction>,
+    has_root: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> OsmChangeReader<R> {
+    /// Creates a new `OsmChangeReader` that reads uncompressed XML.
+    pub fn new(reader: R) -> OsmChangeReader<R> {
+        OsmChangeReader {
+            reader: new_reader(reader),
+            buf: vec![],
+            action: None,
+            has_root: false,
+            finished: false,
+        }
+    }
+
+    fn next_change(&mut self) -> Result<Option<Change>> {
+        loop {
+            self.buf.clear();
+            let event = read_event(&mut self.reader, &mut self.buf)?;
+            let (start, is_empty) = match event {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+                Event::End(end) => {
+                    if matches!(end.name().as_ref(), b"create" | b"modify" | b"delete") {
+                        self.action = None;
+                    }
+                    continue;
+                }
+                Event::Eof => return Ok(None),
+                _ => continue,
+            };
+
+            if !self.has_root {
+                if start.name().as_ref() != b"osmChange" {
+                    return Err(xml_error(&self.reader, "expected <osmChange> root element"));
+                }
+                self.has_root = true;
+                continue;
+            }
+
+            let action = match start.name().as_ref() {
+                b"create" => Some(ChangeAction::Create),
+                b"modify" => Some(ChangeAction::Modify),
+                b"delete" => Some(ChangeAction::Delete),
+                _ => None,
+            };
+            if let Some(action) = action {
+                if !is_empty {
+                    self.action = Some(action);
+                }
+                continue;
+            }
+
+            if let Some(element) = parse_element(&mut self.reader, &start, is_empty)? {
+                let action = self.action.ok_or_else(|| {
+                    xml_error(
+                        &self.reader,
+                        "element outside of <create>, <modify> or <delete>",
+                    )
+                })?;
+                return Ok(Some(Change { action, element }));
+            }
+        }
+    }
+}
+
+impl OsmChangeReader<BufReader<Box<dyn Read + Send>>> {
+    /// Opens the OsmChange file at the given path. Files that are compressed with gzip (like
+    /// `.osc.gz`) are detected by their content and decompressed.
+    ///
+    /// # Errors
+    /// Returns the same errors that `std::fs::File::open` returns.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(OsmChangeReader::new(open_xml_file(path)?))
+    }
+}
+
+impl<R: BufRead> Iterator for OsmChangeReader<R> {
+    type Item = Result<Change>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_change().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.finished = true;
+        }
+        result
+    }
+}
+
+/// Sort key of the `Sort.Type_then_ID` order.
+fn sort_key(element: &OwnedElement) -> (u8, i64) {
+    match element {
+        OwnedElement::Node(node) => (0, node.id),
+        OwnedElement::Way(way) => (1, way.id),
+        OwnedElement::Relation(relation) => (2, relation.id),
+    }
+}
+
+/// Writes elements in blocks that each contain only one element type.
+struct BlockSink<W: Write> {
+    writer: PbfWriter<W>,
+    pending: Vec<OwnedElement>,
+}
+
+impl<W: Write> BlockSink<W> {
+    fn push(&mut self, element: OwnedElement) -> Result<()> {
+        if let Some(last) = self.pending.last() {
+            if self.pending.len() >= ELEMENTS_PER_BLOCK || sort_key(last).0 != sort_key(&element).0
+            {
+                self.flush()?;
+            }
+        }
+        self.pending.push(element);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.write_elements(self.pending.drain(..))
+    }
+}
+
+/// Applies the changes to a sorted PBF file and writes the updated file to `writer`. Returns the
+/// writer after flushing it.
+///
+/// The input has to be sorted by type, then ID. The changes can be in any
+/// order; if an element is changed more than once, the change with the highest version is
+/// applied. Elements of the input that have a higher version than their change are kept.
+/// If the version of the change or of the element it is compared to is missing, the versions
+/// cannot be compared and the change wins, so that a later change replaces an earlier one.
+/// Created and modified elements replace the elements of the input, deleted elements are
+/// removed.
+///
+/// The header of the input is copied with these updates:
+/// * the replication sequence number is incremented by one if it is set, use
+///   [`apply_changes_with_sequence_number`] to set the one of the changes instead,
+/// * the replication timestamp is set to the newest timestamp of the changes if that is newer,
+/// * the optional feature `Sort.Type_then_ID` is added.
+///
+/// All changes are kept in memory, while the input is streamed.
+///
+/// # Errors
+/// Returns the first error of the changes, or of reading or writing the PBF files. Returns an
+/// [`ErrorKind::UnsortedInput`] error if the input is not sorted.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let osc = br#"<osmChange version="0.6">
+///   <delete><node id="106" version="2"/></delete>
+/// </osmChange>"#;
+///
+/// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+/// let changes = OsmChangeReader::new(&osc[..]);
+/// let bytes = apply_changes(reader, changes, Vec::new())?;
+///
+/// let mut nodes = 0;
+/// ElementReader::new(bytes.as_slice()).for_each(|element| {
+///     if let Element::DenseNode(_) | Element::Node(_) = element {
+///         nodes += 1;
+///     }
+/// })?;
+/// assert_eq!(nodes, 2);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub fn apply_changes<R, I, W>(reader: ElementReader<R>, changes: I, writer: W) -> Result<W>
+where
+    R: Read + Send,
+    I: IntoIterator<Item = Result<Change>>,
+    W: Write,
+{
+    apply_changes_impl(reader, changes, None, writer)
+}
+
+/// Just like [`apply_changes`], but sets the replication sequence number of the output header
+/// instead of incrementing the one of the input, usually to the sequence number of the applied
+/// OsmChange file.
+///
+/// # Errors
+/// Returns the first error of the changes, or of reading or writing the PBF files. Returns an
+/// [`ErrorKind::UnsortedInput`] error if the input is not sorted.
+pub fn apply_changes_with_sequence_number<R, I, W>(
+    reader: ElementReader<R>,
+    changes: I,
+    sequence_number: i64,
+    writer: W,
+) -> Result<W>
+where
+    R: Read + Send,
+    I: IntoIterator<Item = Result<Change>>,
+    W: Write,
+{
+    apply_changes_impl(reader, changes, Some(sequence_number), writer)
+}
+
+/// Returns true if a change with the version `new` replaces an element or an earlier change with
+/// the version `old`. Missing versions cannot be compared, so the change wins.
+fn replaces(new: Option<i32>, old: Option<i32>) -> bool {
+    match (new, old) {
+        (Some(new), Some(old)) => new >= old,
+        _ => true,
+    }
+}
+
+fn apply_changes_impl<R, I, W>(
+    reader: ElementReader<R>,
+    changes: I,
+    sequence_number: Option<i64>,
+    writer: W,
+) -> Result<W>
+where
+    R: Read + Send,
+    I: IntoIterator<Item = Result<Change>>,
+    W: Write,
+{
+    let mut newest_timestamp = None;
+    let mut changes_by_key: BTreeMap<(u8, i64), Change> = BTreeMap::new();
+    for change in changes {
+        let change = change?;
+        newest_timestamp = newest_timestamp.max(change.element.info().milli_timestamp);
+        let key = sort_key(&change.element);
+        match changes_by_key.get(&key) {
+            Some(old) if !replaces(change.element.info().version, old.element.info().version) => {}
+            _ => {
+                changes_by_key.insert(key, change);
+            }
+        }
+    }
+
+    let mut blobs = reader.into_blob_reader();
+    let mut first_block = None;
+    let mut header_builder = HeaderBuilder::new();
+    if let Some(blob) = blobs.next() {
+        match blob?.decode()? {
+            BlobDecode::OsmHeader(header) => {
+                header.check_required_features()?;
+                header_builder = HeaderBuilder::from_header(&header);
+                if let Some(number) = header.osmosis_replication_sequence_number() {
+                    header_builder.set_osmosis_replication_sequence_number(number + 1);
+                }
+                let timestamp = header
+                    .osmosis_replication_timestamp()
+                    .max(newest_timestamp.map(|t| t.div_euclid(1000)));
+                if let Some(timestamp) = timestamp {
+                    header_builder.set_osmosis_replication_timestamp(timestamp);
+                }
+            }
+            BlobDecode::OsmData(block) => first_block = Some(block),
+            BlobDecode::Unknown(_) => {}
+        }
+    }
+    if let Some(sequence_number) = sequence_number {
+        header_builder.set_osmosis_replication_sequence_number(sequence_number);
+    }
+    header_builder.add_optional_feature("Sort.Type_then_ID");
+
+    let mut sink = BlockSink {
+        writer: PbfWriter::new(writer, &header_builder.build())?,
+        pending: vec![],
+    };
+    let mut changes = changes_by_key.into_iter().peekable();
+    let mut last_key = None;
+
+    {
+        let mut merge_block = |block: PrimitiveBlock, sink: &mut BlockSink<W>| -> Result<()> {
+            for element in block.elements() {
+                let element = element.to_owned()?;
+                let key = sort_key(&element);
+                if last_key >= Some(key) {
+                    let element_type = ["node", "way", "relation"][key.0 as usize];
+                    return Err(new_error(ErrorKind::UnsortedInput {
+                        element_type,
+                        id: key.1,
+                    }));
+                }
+                last_key = Some(key);
+
+                while let Some((_, change)) = changes.next_if(|(k, _)| *k < key) {
+                    if change.action != ChangeAction::Delete {
+                        sink.push(change.element)?;
+                    }
+                }
+                match changes.next_if(|(k, _)| *k == key) {
+                    Some((_, change))
+                        if replaces(change.element.info().version, element.info().version) =>
+                    {
+                        if change.action != ChangeAction::Delete {
+                            sink.push(change.element)?;
+                        }
+                    }
+                    _ => sink.push(element)?,
+                }
+            }
+            Ok(())
+        };
+
+        if let Some(block) = first_block {
+            merge_block(block, &mut sink)?;
+        }
+        for blob in blobs {
+            if let BlobDecode::OsmData(block) = blob?.decode()? {
+                merge_block(block, &mut sink)?;
+            }
+        }
+    }
+
+    for (_, change) in changes {
+        if change.action != ChangeAction::Delete {
+            sink.push(change.element)?;
+        }
+    }
+    sink.flush()?;
+    sink.writer.into_inner()
+}
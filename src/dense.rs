@@ -135,6 +135,79 @@ impl<'a> DenseNodeIter<'a> {
     }
 }
 
+/// The position of a [`DenseNodeIter`]. It does not borrow the block, so the iteration can be
+/// resumed later with [`DenseNodeIter::at_position`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct DenseNodePosition {
+    consumed: usize,
+    cid: i64,
+    clat: i64,
+    clon: i64,
+    keys_vals_index: usize,
+    ctimestamp: i64,
+    cchangeset: i64,
+    cuid: i32,
+    cuser_sid: i32,
+}
+
+/// Returns an iterator over the slice that skips the first `n` items.
+fn skip<T>(slice: &[T], n: usize) -> std::slice::Iter<'_, T> {
+    slice.get(n..).unwrap_or(&[]).iter()
+}
+
+impl<'a> DenseNodeIter<'a> {
+    /// Returns the current position. `osmdense` has to be the same value that was used to
+    /// create this iterator.
+    pub(crate) fn position(&self, osmdense: &osmformat::DenseNodes) -> DenseNodePosition {
+        let info = self.info_iter.as_ref();
+        DenseNodePosition {
+            consumed: osmdense.id.len() - self.dids.len(),
+            cid: self.cid,
+            clat: self.clat,
+            clon: self.clon,
+            keys_vals_index: self.keys_vals_index,
+            ctimestamp: info.map_or(0, |info| info.ctimestamp),
+            cchangeset: info.map_or(0, |info| info.cchangeset),
+            cuid: info.map_or(0, |info| info.cuid),
+            cuser_sid: info.map_or(0, |info| info.cuser_sid),
+        }
+    }
+
+    /// Creates an iterator that resumes at the given position.
+    pub(crate) fn at_position(
+        block: &'a osmformat::PrimitiveBlock,
+        osmdense: &'a osmformat::DenseNodes,
+        position: &DenseNodePosition,
+    ) -> DenseNodeIter<'a> {
+        let n = position.consumed;
+        let info = osmdense.denseinfo.get_or_default();
+        DenseNodeIter {
+            block,
+            dids: skip(&osmdense.id, n),
+            cid: position.cid,
+            dlats: skip(&osmdense.lat, n),
+            clat: position.clat,
+            dlons: skip(&osmdense.lon, n),
+            clon: position.clon,
+            keys_vals_slice: osmdense.keys_vals.as_slice(),
+            keys_vals_index: position.keys_vals_index,
+            info_iter: Some(DenseNodeInfoIter {
+                block,
+                versions: skip(&info.version, n),
+                dtimestamps: skip(&info.timestamp, n),
+                ctimestamp: position.ctimestamp,
+                dchangesets: skip(&info.changeset, n),
+                cchangeset: position.cchangeset,
+                duids: skip(&info.uid, n),
+                cuid: position.cuid,
+                duser_sids: skip(&info.user_sid, n),
+                cuser_sid: position.cuser_sid,
+                visible: skip(&info.visible, n),
+            }),
+        }
+    }
+}
+
 impl<'a> Iterator for DenseNodeIter<'a> {
     type Item = DenseNode<'a>;
 
@@ -196,8 +269,9 @@ pub struct DenseNodeInfo<'a> {
     uid: i32,
     /// String IDs for usernames.
     user_sid: i32,
-    /// Is the element visible (true) or was it deleted (false).
-    visible: bool,
+    /// Is the element visible (true) or was it deleted (false). `None` if the block has no
+    /// visible flags.
+    visible: Option<bool>,
 }
 
 impl<'a> DenseNodeInfo<'a> {
@@ -229,13 +303,20 @@ impl<'a> DenseNodeInfo<'a> {
     /// Returns the visibility status of an element. This is only relevant if the PBF file contains
     /// historical information.
     pub fn visible(&self) -> bool {
-        self.visible
+        // If the visible flag is not present it must be assumed to be true.
+        self.visible.unwrap_or(true)
+    }
+
+    /// Returns true if the visible flag is present. It is usually only present in files with
+    /// historical information.
+    pub fn has_visible(&self) -> bool {
+        self.visible.is_some()
     }
 
     /// Returns true if the element was deleted.
     /// This is a convenience function that just returns the inverse of `DenseNodeInfo::visible`.
     pub fn deleted(&self) -> bool {
-        !self.visible
+        !self.visible()
     }
 }
 
@@ -307,7 +388,7 @@ impl<'a> Iterator for DenseNodeInfoIter<'a> {
                     changeset: self.cchangeset,
                     uid: self.cuid,
                     user_sid: self.cuser_sid,
-                    visible: *visible_opt.unwrap_or(&true),
+                    visible: visible_opt.copied(),
                 })
             }
             _ => None,
@@ -26,6 +26,39 @@ pub enum Element<'a> {
     Relation(Relation<'a>),
 }
 
+impl Element<'_> {
+    /// Returns the type of this element. Nodes and dense nodes are both of type
+    /// [`ElementType::Node`].
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            Element::Node(_) | Element::DenseNode(_) => ElementType::Node,
+            Element::Way(_) => ElementType::Way,
+            Element::Relation(_) => ElementType::Relation,
+        }
+    }
+
+    /// Returns the ID of this element.
+    pub fn id(&self) -> i64 {
+        match self {
+            Element::Node(node) => node.id(),
+            Element::DenseNode(node) => node.id(),
+            Element::Way(way) => way.id(),
+            Element::Relation(relation) => relation.id(),
+        }
+    }
+}
+
+/// The type of an element. The variants are ordered just like the elements of a file that is
+/// sorted by type, then by ID (see [`HeaderBlock::is_sorted_by_type_then_id`]).
+///
+/// [`HeaderBlock::is_sorted_by_type_then_id`]: crate::block::HeaderBlock::is_sorted_by_type_then_id
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ElementType {
+    Node,
+    Way,
+    Relation,
+}
+
 /// An OpenStreetMap node element (See [OSM wiki](http://wiki.openstreetmap.org/wiki/Node)).
 #[derive(Clone, Debug)]
 pub struct Node<'a> {
@@ -320,6 +353,27 @@ impl<'a> Relation<'a> {
     }
 }
 
+/// A changeset entry of a [`PrimitiveGroup`](crate::block::PrimitiveGroup).
+///
+/// The PBF format allows storing changesets next to nodes, ways and relations, but only their ID
+/// is encoded. They are rarely used and therefore not part of [`Element`]. See
+/// [`PrimitiveGroup::changesets`](crate::block::PrimitiveGroup::changesets).
+#[derive(Clone, Debug)]
+pub struct Changeset<'a> {
+    osmchangeset: &'a osmformat::ChangeSet,
+}
+
+impl<'a> Changeset<'a> {
+    pub(crate) fn new(osmchangeset: &'a osmformat::ChangeSet) -> Changeset<'a> {
+        Changeset { osmchangeset }
+    }
+
+    /// Returns the changeset id.
+    pub fn id(&self) -> i64 {
+        self.osmchangeset.id()
+    }
+}
+
 /// An iterator over the references of a way.
 ///
 /// Each reference corresponds to a node id.
@@ -349,13 +403,22 @@ impl Iterator for WayRefIter<'_> {
 
 impl ExactSizeIterator for WayRefIter<'_> {}
 
+/// A node location that contains latitude and longitude coordinates.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct WayNodeLocation {
     lat: i64,
     lon: i64,
 }
 
-/// A node location that contains latitude and longitude coordinates.
 impl WayNodeLocation {
+    /// Creates a new `WayNodeLocation` from coordinates in nanodegrees (10⁻⁹).
+    pub fn new(nano_lat: i64, nano_lon: i64) -> WayNodeLocation {
+        WayNodeLocation {
+            lat: nano_lat,
+            lon: nano_lon,
+        }
+    }
+
     /// Returns the latitude coordinate in degrees.
     pub fn lat(&self) -> f64 {
         1e-9 * self.nano_lat() as f64
@@ -424,6 +487,8 @@ impl ExactSizeIterator for WayNodeLocationsIter<'_> {}
 
 /// The element type of a relation member.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum RelMemberType {
     Node,
     Way,
@@ -440,6 +505,16 @@ impl From<EnumOrUnknown<MemberType>> for RelMemberType {
     }
 }
 
+impl From<&RelMemberType> for ElementType {
+    fn from(member_type: &RelMemberType) -> ElementType {
+        match member_type {
+            RelMemberType::Node => ElementType::Node,
+            RelMemberType::Way => ElementType::Way,
+            RelMemberType::Relation => ElementType::Relation,
+        }
+    }
+}
+
 //TODO encapsulate member_id based on member_type (NodeId, WayId, RelationId)
 /// A member of a relation.
 ///
@@ -618,6 +693,12 @@ impl<'a> Info<'a> {
         self.info.visible.unwrap_or(true)
     }
 
+    /// Returns true if the visible flag is present. It is usually only present in files with
+    /// historical information.
+    pub fn has_visible(&self) -> bool {
+        self.info.visible.is_some()
+    }
+
     /// Returns true if the element was deleted.
     /// This is a convenience function that just returns the inverse of [`Info::visible`].
     pub fn deleted(&self) -> bool {
@@ -58,6 +58,27 @@ pub enum ErrorKind {
     StringtableIndexOutOfBounds { index: usize },
     /// An error that occurs when decoding `Blob`s.
     Blob(BlobError),
+    /// A saved index of an [`IndexedReader`](crate::indexed::IndexedReader) could not be loaded
+    /// because it is malformed or does not match the PBF file.
+    InvalidIndex { reason: &'static str },
+    /// The header of the file lists a required feature that is not supported by this crate (see
+    /// [`SUPPORTED_REQUIRED_FEATURES`](crate::block::SUPPORTED_REQUIRED_FEATURES)).
+    UnsupportedRequiredFeature { feature: String },
+    /// A [`NodeLocationStore`](crate::location_store::NodeLocationStore) cannot hold the ID or
+    /// the location of a node.
+    InvalidNodeLocation { id: i64 },
+    /// An XML document (e.g. an OsmChange file) could not be parsed. `position` is the byte
+    /// offset in the (decompressed) input.
+    InvalidXml { position: u64, reason: String },
+    /// The input is not sorted by type and then by ID, which is required to merge it with other
+    /// data. `id` is the first element that is out of order.
+    UnsortedInput { element_type: &'static str, id: i64 },
+    /// A line of an OPL file could not be parsed. `line` is the line number, starting at 1.
+    InvalidOpl { line: u64, reason: String },
+    /// A closure or the reader panicked on a background thread, e.g. the `map_op` of
+    /// [`ElementReader::par_map_blocks`](crate::reader::ElementReader::par_map_blocks).
+    /// `message` is the panic message if it is a string.
+    Panicked { message: String },
     //TODO add UnexpectedPrimitiveBlock
 }
 
@@ -77,8 +98,15 @@ pub enum BlobError {
         /// Blob content size in bytes.
         size: u64,
     },
-    /// The blob is empty because the `raw` and `zlib-data` fields are missing.
+    /// The blob is empty because all of its data fields (`raw`, `zlib_data`, ...) are missing.
     Empty,
+    /// The blob content is compressed with a method that is not supported. Some compression
+    /// methods are only available if the corresponding cargo feature is enabled (`zstd`, `lz4`
+    /// or `lzma`).
+    UnsupportedCompression {
+        /// Name of the compression method.
+        compression: &'static str,
+    },
 }
 
 impl From<io::Error> for Error {
@@ -123,12 +151,22 @@ impl StdError for Error {
             ErrorKind::Protobuf { .. } => "protobuf error",
             ErrorKind::StringtableUtf8 { .. } => "UTF-8 error in stringtable",
             ErrorKind::StringtableIndexOutOfBounds { .. } => "stringtable index out of bounds",
+            ErrorKind::InvalidIndex { .. } => "invalid index",
+            ErrorKind::UnsupportedRequiredFeature { .. } => "unsupported required feature",
+            ErrorKind::InvalidNodeLocation { .. } => "invalid node location",
+            ErrorKind::InvalidXml { .. } => "invalid XML",
+            ErrorKind::UnsortedInput { .. } => "input is not sorted",
+            ErrorKind::InvalidOpl { .. } => "invalid OPL",
+            ErrorKind::Panicked { .. } => "background thread panicked",
             ErrorKind::Blob(BlobError::InvalidHeaderSize) => {
                 "blob header size could not be decoded"
             }
             ErrorKind::Blob(BlobError::HeaderTooBig { .. }) => "blob header is too big",
             ErrorKind::Blob(BlobError::MessageTooBig { .. }) => "blob message is too big",
-            ErrorKind::Blob(BlobError::Empty) => "blob is missing fields 'raw' and 'zlib_data",
+            ErrorKind::Blob(BlobError::Empty) => "blob is missing its data field",
+            ErrorKind::Blob(BlobError::UnsupportedCompression { .. }) => {
+                "blob compression is not supported"
+            }
         }
     }
 
@@ -138,10 +176,18 @@ impl StdError for Error {
             ErrorKind::Protobuf { ref err, .. } => Some(err),
             ErrorKind::StringtableUtf8 { ref err, .. } => Some(err),
             ErrorKind::StringtableIndexOutOfBounds { .. } => None,
+            ErrorKind::InvalidIndex { .. } => None,
+            ErrorKind::UnsupportedRequiredFeature { .. } => None,
+            ErrorKind::InvalidNodeLocation { .. } => None,
+            ErrorKind::InvalidXml { .. } => None,
+            ErrorKind::UnsortedInput { .. } => None,
+            ErrorKind::InvalidOpl { .. } => None,
+            ErrorKind::Panicked { .. } => None,
             ErrorKind::Blob(BlobError::InvalidHeaderSize) => None,
             ErrorKind::Blob(BlobError::HeaderTooBig { .. }) => None,
             ErrorKind::Blob(BlobError::MessageTooBig { .. }) => None,
             ErrorKind::Blob(BlobError::Empty) => None,
+            ErrorKind::Blob(BlobError::UnsupportedCompression { .. }) => None,
         }
     }
 }
@@ -159,6 +205,33 @@ impl fmt::Display for Error {
             ErrorKind::StringtableIndexOutOfBounds { index } => {
                 write!(f, "stringtable index out of bounds: {index}")
             }
+            ErrorKind::InvalidIndex { reason } => {
+                write!(f, "invalid index: {reason}")
+            }
+            ErrorKind::UnsupportedRequiredFeature { ref feature } => {
+                write!(f, "unsupported required feature: {feature}")
+            }
+            ErrorKind::InvalidNodeLocation { id } => {
+                write!(f, "location of node {id} cannot be stored")
+            }
+            ErrorKind::InvalidXml {
+                position,
+                ref reason,
+            } => {
+                write!(f, "invalid XML at byte {position}: {reason}")
+            }
+            ErrorKind::UnsortedInput { element_type, id } => {
+                write!(
+                    f,
+                    "input is not sorted by type and ID at {element_type} {id}"
+                )
+            }
+            ErrorKind::InvalidOpl { line, ref reason } => {
+                write!(f, "invalid OPL in line {line}: {reason}")
+            }
+            ErrorKind::Panicked { ref message } => {
+                write!(f, "background thread panicked: {message}")
+            }
             ErrorKind::Blob(BlobError::InvalidHeaderSize) => {
                 write!(f, "blob header size could not be decoded")
             }
@@ -169,7 +242,10 @@ impl fmt::Display for Error {
                 write!(f, "blob message is too big: {size} bytes")
             }
             ErrorKind::Blob(BlobError::Empty) => {
-                write!(f, "blob is missing fields 'raw' and 'zlib_data'")
+                write!(f, "blob is missing its data field")
+            }
+            ErrorKind::Blob(BlobError::UnsupportedCompression { compression }) => {
+                write!(f, "blob compression is not supported: {compression}")
             }
         }
     }
@@ -0,0 +1,375 @@
+//! Extract the elements of a region into a new PBF file
+//!
+//! The extraction follows the "complete_ways" strategy of `osmium extract`:
+//! * all nodes inside the region,
+//! * all ways that reference at least one of these nodes, together with all of their nodes (even
+//!   the ones outside of the region),
+//! * all relations that have at least one node inside the region or one of these ways as a
+//!   member, and all parent relations of these relations (recursively). Members of relations
+//!   that are outside of the region are not added.
+//!
+//! The input has to be sorted by type, then ID. The output is sorted in the same way and declares
+//! the optional feature `Sort.Type_then_ID`.
+
+use crate::block::HeaderBBox;
+use crate::elements::{Element, ElementType, RelMemberType};
+use crate::error::{new_error, ErrorKind, Result};
+use crate::indexed::IndexedReader;
+use crate::validate::{allows_duplicate_ids, sort_violation_kind, IdSet};
+use crate::writer::{BlockBuilder, HeaderBuilder, PbfWriter, ELEMENTS_PER_BLOCK};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+
+/// A region on the earth's surface that can be extracted.
+pub trait Region {
+    /// Returns true if the given coordinate (in nanodegrees) is inside the region.
+    fn contains(&self, nano_lat: i64, nano_lon: i64) -> bool;
+
+    /// Returns the bounding box of the region. It is stored in the header of the extract, unless
+    /// one of its coordinates is not finite.
+    fn bbox(&self) -> HeaderBBox;
+}
+
+impl Region for HeaderBBox {
+    /// Returns true if the coordinate is inside the box or on its edges. Boxes that cross the
+    /// antimeridian are not supported.
+    fn contains(&self, nano_lat: i64, nano_lon: i64) -> bool {
+        let (lat, lon) = (1e-9 * nano_lat as f64, 1e-9 * nano_lon as f64);
+        self.bottom <= lat && lat <= self.top && self.left <= lon && lon <= self.right
+    }
+
+    fn bbox(&self) -> HeaderBBox {
+        self.clone()
+    }
+}
+
+/// A polygon that consists of one or more rings of `(lon, lat)` coordinates in degrees.
+///
+/// A coordinate is inside the polygon if it is inside an odd number of rings (even-odd rule), so
+/// inner rings cut holes into outer rings. Rings do not need to be closed explicitly.
+#[derive(Clone, Debug)]
+pub struct Polygon {
+    rings: Vec<Vec<(f64, f64)>>,
+    bbox: HeaderBBox,
+}
+
+impl Polygon {
+    /// Creates a polygon with a single outer ring.
+    pub fn new(ring: Vec<(f64, f64)>) -> Polygon {
+        Polygon::with_rings(vec![ring])
+    }
+
+    /// Creates a polygon from outer and inner rings. A polygon without any coordinates contains
+    /// nothing and has an infinite bounding box.
+    pub fn with_rings(rings: Vec<Vec<(f64, f64)>>) -> Polygon {
+        let mut bbox = HeaderBBox {
+            left: f64::INFINITY,
+            right: f64::NEG_INFINITY,
+            top: f64::NEG_INFINITY,
+            bottom: f64::INFINITY,
+        };
+        for &(lon, lat) in rings.iter().flatten() {
+            bbox.left = bbox.left.min(lon);
+            bbox.right = bbox.right.max(lon);
+            bbox.bottom = bbox.bottom.min(lat);
+            bbox.top = bbox.top.max(lat);
+        }
+        Polygon { rings, bbox }
+    }
+
+    /// Returns the rings of this polygon.
+    pub fn rings(&self) -> &[Vec<(f64, f64)>] {
+        &self.rings
+    }
+}
+
+/// Returns true if a ray from the point in the direction of positive longitude crosses an odd
+/// number of edges of the ring.
+pub(crate) fn ring_contains(ring: &[(f64, f64)], lon: f64, lat: f64) -> bool {
+    let mut inside = false;
+    let mut prev = match ring.last() {
+        Some(&last) => last,
+        None => return false,
+    };
+    for &(x, y) in ring {
+        let (prev_x, prev_y) = prev;
+        if (y > lat) != (prev_y > lat) && lon < (prev_x - x) * (lat - y) / (prev_y - y) + x {
+            inside = !inside;
+        }
+        prev = (x, y);
+    }
+    inside
+}
+
+impl Region for Polygon {
+    fn contains(&self, nano_lat: i64, nano_lon: i64) -> bool {
+        if !self.bbox.contains(nano_lat, nano_lon) {
+            return false;
+        }
+        let (lat, lon) = (1e-9 * nano_lat as f64, 1e-9 * nano_lon as f64);
+        self.rings
+            .iter()
+            .filter(|ring| ring_contains(ring, lon, lat))
+            .count()
+            % 2
+            == 1
+    }
+
+    fn bbox(&self) -> HeaderBBox {
+        self.bbox.clone()
+    }
+}
+
+/// Extracts all elements inside the bounding box and writes them as a new PBF file to `writer`.
+/// Returns the writer after flushing it. See the [module documentation](mod@crate::extract) for the
+/// selected elements.
+///
+/// # Errors
+/// Returns the first Error encountered while reading or writing.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+/// // Only node 105 is inside, but way 107 references it.
+/// let bbox = HeaderBBox {
+///     left: 11.627,
+///     right: 11.63,
+///     top: 52.123,
+///     bottom: 52.121,
+/// };
+///
+/// let bytes = extract_bbox(&mut reader, &bbox, Vec::new())?;
+///
+/// // The extract contains the complete way 107 with all three nodes and relation 120.
+/// let mut count = 0;
+/// ElementReader::new(bytes.as_slice()).for_each(|_| count += 1)?;
+/// assert_eq!(count, 5);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub fn extract_bbox<R, W>(reader: &mut IndexedReader<R>, bbox: &HeaderBBox, writer: W) -> Result<W>
+where
+    R: Read + Seek + Send,
+    W: Write,
+{
+    extract(reader, bbox, writer)
+}
+
+/// Extracts all elements inside the polygon and writes them as a new PBF file to `writer`.
+/// Returns the writer after flushing it. See the [module documentation](mod@crate::extract) for the
+/// selected elements.
+///
+/// # Errors
+/// Returns the first Error encountered while reading or writing.
+pub fn extract_polygon<R, W>(
+    reader: &mut IndexedReader<R>,
+    polygon: &Polygon,
+    writer: W,
+) -> Result<W>
+where
+    R: Read + Seek + Send,
+    W: Write,
+{
+    extract(reader, polygon, writer)
+}
+
+/// Writes the selected elements in blocks of at most [`ELEMENTS_PER_BLOCK`] elements and checks
+/// that they are sorted.
+struct SortedSink<W: Write> {
+    writer: PbfWriter<W>,
+    builder: BlockBuilder,
+    allow_duplicates: bool,
+    previous: Option<(ElementType, i64)>,
+}
+
+impl<W: Write> SortedSink<W> {
+    fn push(&mut self, element: &Element) -> Result<()> {
+        let current = (element.element_type(), element.id());
+        if let Some(previous) = self.previous {
+            if sort_violation_kind(previous, current, self.allow_duplicates).is_some() {
+                let element_type = match current.0 {
+                    ElementType::Node => "node",
+                    ElementType::Way => "way",
+                    ElementType::Relation => "relation",
+                };
+                return Err(new_error(ErrorKind::UnsortedInput {
+                    element_type,
+                    id: current.1,
+                }));
+            }
+        }
+        let type_changed = self
+            .previous
+            .is_some_and(|previous| previous.0 != current.0);
+        self.previous = Some(current);
+
+        // Each block contains only one type of elements
+        if type_changed || self.builder.len() >= ELEMENTS_PER_BLOCK {
+            self.flush()?;
+        }
+        self.builder.add_element(&element.to_owned()?);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if !self.builder.is_empty() {
+            self.writer
+                .write_block(&std::mem::take(&mut self.builder).build())?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts all elements inside the given region and writes them as a new PBF file to `writer`.
+/// Returns the writer after flushing it. See the [module documentation](mod@crate::extract) for the
+/// selected elements.
+///
+/// The IDs of the selected elements are kept in memory (see [`IdSet`]), together with the IDs of
+/// all relations that are members of other relations and their parents, which are needed to find
+/// the parent relations. The selected elements are written in a final pass over the input, which
+/// has to be sorted by type, then ID. The header of the input is copied, e.g. to keep the required
+/// feature `HistoricalInformation`, with the bounding box of the region and the optional feature
+/// `Sort.Type_then_ID`.
+///
+/// # Errors
+/// Returns the first Error encountered while reading or writing. Returns an
+/// [`ErrorKind::UnsortedInput`] error if the input is not sorted.
+pub fn extract<R, G, W>(reader: &mut IndexedReader<R>, region: &G, writer: W) -> Result<W>
+where
+    R: Read + Seek + Send,
+    G: Region + ?Sized,
+    W: Write,
+{
+    // First pass: Nodes inside the region
+    let mut inside_node_ids = IdSet::new();
+    reader.for_each_node(|element| {
+        let inside = match &element {
+            Element::Node(node) => region.contains(node.nano_lat(), node.nano_lon()),
+            Element::DenseNode(node) => region.contains(node.nano_lat(), node.nano_lon()),
+            _ => false,
+        };
+        if inside {
+            inside_node_ids.insert(element.id());
+        }
+    })?;
+
+    // Second pass: Ways with at least one node inside the region and all of their nodes
+    let mut way_ids = IdSet::new();
+    let mut way_node_ids = IdSet::new();
+    reader.for_each_way(|element| {
+        if let Element::Way(way) = element {
+            if way.refs().any(|id| inside_node_ids.contains(id)) {
+                way_ids.insert(way.id());
+                for id in way.refs() {
+                    way_node_ids.insert(id);
+                }
+            }
+        }
+    })?;
+
+    // Third pass: Relations with selected members. Like osmium, only nodes inside the region
+    // count, not the additional nodes of complete ways. Also remember the parents of all
+    // relations.
+    let mut relation_ids = IdSet::new();
+    let mut pending: Vec<i64> = vec![];
+    let mut parents: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    reader.for_each_relation(|element| {
+        if let Element::Relation(relation) = element {
+            let mut selected = false;
+            for member in relation.members() {
+                match member.member_type {
+                    RelMemberType::Node => selected |= inside_node_ids.contains(member.member_id),
+                    RelMemberType::Way => selected |= way_ids.contains(member.member_id),
+                    RelMemberType::Relation => parents
+                        .entry(member.member_id)
+                        .or_default()
+                        .push(relation.id()),
+                }
+            }
+            if selected && relation_ids.insert(relation.id()) {
+                pending.push(relation.id());
+            }
+        }
+    })?;
+
+    // Parent relations of selected relations
+    while let Some(id) = pending.pop() {
+        for &parent in parents.get(&id).into_iter().flatten() {
+            if relation_ids.insert(parent) {
+                pending.push(parent);
+            }
+        }
+    }
+    drop(parents);
+
+    let mut header_builder = HeaderBuilder::new();
+    let mut allow_duplicates = false;
+    if let Some(header) = reader.read_header()? {
+        header.check_required_features()?;
+        header_builder = HeaderBuilder::from_header(&header);
+        allow_duplicates = allows_duplicate_ids(&header);
+    }
+    let bbox = region.bbox();
+    if [bbox.left, bbox.right, bbox.top, bbox.bottom]
+        .iter()
+        .all(|coordinate| coordinate.is_finite())
+    {
+        header_builder.set_bbox(&bbox);
+    }
+    header_builder.add_optional_feature("Sort.Type_then_ID");
+
+    let mut sink = SortedSink {
+        writer: PbfWriter::new(writer, &header_builder.build())?,
+        builder: BlockBuilder::new(),
+        allow_duplicates,
+        previous: None,
+    };
+    let mut result = Ok(());
+    let mut push = |selected: bool, element: Element| {
+        if selected && result.is_ok() {
+            result = sink.push(&element);
+        }
+    };
+
+    // Final passes: Write the selected elements in file order
+    reader.for_each_node(|element| {
+        let id = element.id();
+        push(
+            inside_node_ids.contains(id) || way_node_ids.contains(id),
+            element,
+        )
+    })?;
+    reader.for_each_way(|element| push(way_ids.contains(element.id()), element))?;
+    reader.for_each_relation(|element| push(relation_ids.contains(element.id()), element))?;
+    result?;
+
+    sink.flush()?;
+    sink.writer.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon_contains() {
+        let square = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let hole = vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)];
+        let polygon = Polygon::with_rings(vec![square, hole]);
+        let nano = |degrees: f64| (degrees * 1e9) as i64;
+
+        assert!(polygon.contains(nano(1.0), nano(1.0)));
+        assert!(polygon.contains(nano(9.0), nano(5.0)));
+        assert!(!polygon.contains(nano(5.0), nano(5.0)));
+        assert!(!polygon.contains(nano(5.0), nano(11.0)));
+        assert!(!polygon.contains(nano(-1.0), nano(5.0)));
+
+        let bbox = polygon.bbox();
+        assert_eq!((bbox.left, bbox.right), (0.0, 10.0));
+        assert_eq!((bbox.bottom, bbox.top), (0.0, 10.0));
+    }
+}
@@ -0,0 +1,211 @@
+//! Filter elements by their tags without decoding strings
+
+use crate::block::PrimitiveBlock;
+use crate::elements::Element;
+use std::collections::HashMap;
+
+/// A filter that selects elements by their tags.
+///
+/// Matching tags with `element.tags()` decodes and compares strings for every single element. A
+/// `TagFilter` instead looks up its keys and values once per [`PrimitiveBlock`] in the
+/// stringtable (see [`compile`](TagFilter::compile)), so that matching an element only compares
+/// the indices of its raw tags.
+///
+/// A filter consists of a list of predicates. A filter created with [`any`](TagFilter::any)
+/// matches elements that satisfy at least one predicate, a filter created with
+/// [`all`](TagFilter::all) matches elements that satisfy every predicate.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+/// let mut filter = TagFilter::any();
+/// filter.key_value("building", "yes").key("amenity");
+///
+/// let mut matches = 0;
+/// reader.for_each_filtered(&filter, |_element| matches += 1)?;
+///
+/// # assert_eq!(matches, 1);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct TagFilter {
+    all: bool,
+    /// Maps each distinct key and value of the predicates to a slot.
+    strings: HashMap<Vec<u8>, usize>,
+    predicates: Vec<Predicate>,
+}
+
+/// A predicate with the string slots of the key and the accepted values.
+#[derive(Clone, Debug)]
+struct Predicate {
+    key: usize,
+    /// `None` accepts any value.
+    values: Option<Vec<usize>>,
+}
+
+impl TagFilter {
+    /// Creates a filter that matches elements with at least one tag that satisfies one of the
+    /// predicates. Without any predicates, no element matches.
+    pub fn any() -> TagFilter {
+        TagFilter {
+            all: false,
+            strings: HashMap::new(),
+            predicates: vec![],
+        }
+    }
+
+    /// Creates a filter that matches elements that satisfy all predicates. Without any
+    /// predicates, every element matches.
+    pub fn all() -> TagFilter {
+        TagFilter {
+            all: true,
+            ..TagFilter::any()
+        }
+    }
+
+    /// Adds a predicate that matches a tag with the given key and any value.
+    pub fn key(&mut self, key: &str) -> &mut Self {
+        let key = self.slot(key);
+        self.predicates.push(Predicate { key, values: None });
+        self
+    }
+
+    /// Adds a predicate that matches the tag with the given key and value.
+    pub fn key_value(&mut self, key: &str, value: &str) -> &mut Self {
+        self.key_values(key, [value])
+    }
+
+    /// Adds a predicate that matches a tag with the given key and one of the given values.
+    pub fn key_values<I, V>(&mut self, key: &str, values: I) -> &mut Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<str>,
+    {
+        let key = self.slot(key);
+        let values = values.into_iter().map(|v| self.slot(v.as_ref())).collect();
+        self.predicates.push(Predicate {
+            key,
+            values: Some(values),
+        });
+        self
+    }
+
+    /// Looks up the keys and values of the predicates in the stringtable of the given block. The
+    /// returned [`BlockTagFilter`] can only be used for elements of this block.
+    pub fn compile(&self, block: &PrimitiveBlock) -> BlockTagFilter {
+        // A stringtable may contain duplicate strings, so collect all indices for each slot.
+        let mut indices: Vec<Vec<u32>> = vec![vec![]; self.strings.len()];
+        for (index, string) in block.raw_stringtable().iter().enumerate() {
+            if let Some(&slot) = self.strings.get(string.as_slice()) {
+                indices[slot].push(index as u32);
+            }
+        }
+
+        let predicates = self
+            .predicates
+            .iter()
+            .map(|predicate| CompiledPredicate {
+                keys: indices[predicate.key].clone(),
+                values: predicate.values.as_ref().map(|values| {
+                    let mut values: Vec<u32> = values
+                        .iter()
+                        .flat_map(|&slot| indices[slot].iter().copied())
+                        .collect();
+                    values.sort_unstable();
+                    values
+                }),
+            })
+            .collect();
+
+        BlockTagFilter {
+            all: self.all,
+            predicates,
+        }
+    }
+
+    fn slot(&mut self, string: &str) -> usize {
+        let next = self.strings.len();
+        *self
+            .strings
+            .entry(string.as_bytes().to_vec())
+            .or_insert(next)
+    }
+}
+
+/// A [`TagFilter`] that is compiled for the stringtable of a specific [`PrimitiveBlock`].
+///
+/// Matching an element only compares the indices of its raw tags, no strings are decoded.
+#[derive(Clone, Debug)]
+pub struct BlockTagFilter {
+    all: bool,
+    predicates: Vec<CompiledPredicate>,
+}
+
+/// A predicate with the stringtable indices of the key and the accepted values.
+#[derive(Clone, Debug)]
+struct CompiledPredicate {
+    keys: Vec<u32>,
+    /// Sorted indices. `None` accepts any value.
+    values: Option<Vec<u32>>,
+}
+
+impl CompiledPredicate {
+    fn can_match(&self) -> bool {
+        !self.keys.is_empty() && self.values.as_ref().is_none_or(|v| !v.is_empty())
+    }
+
+    fn matches_tag(&self, (key, value): (u32, u32)) -> bool {
+        self.keys.contains(&key)
+            && self
+                .values
+                .as_ref()
+                .is_none_or(|values| values.binary_search(&value).is_ok())
+    }
+}
+
+impl BlockTagFilter {
+    /// Returns false if no element of the block can match, e.g. because a key does not occur
+    /// in the stringtable. Such blocks can be skipped entirely.
+    pub fn can_match(&self) -> bool {
+        if self.all {
+            self.predicates.iter().all(CompiledPredicate::can_match)
+        } else {
+            self.predicates.iter().any(CompiledPredicate::can_match)
+        }
+    }
+
+    /// Returns true if the given raw tags (pairs of stringtable indices, see
+    /// [`Way::raw_tags`](crate::elements::Way::raw_tags)) match the filter.
+    pub fn matches_raw<I>(&self, tags: I) -> bool
+    where
+        I: IntoIterator<Item = (u32, u32)>,
+        I::IntoIter: Clone,
+    {
+        let tags = tags.into_iter();
+        let satisfied =
+            |predicate: &CompiledPredicate| tags.clone().any(|tag| predicate.matches_tag(tag));
+        if self.all {
+            self.predicates.iter().all(satisfied)
+        } else {
+            self.predicates.iter().any(satisfied)
+        }
+    }
+
+    /// Returns true if the tags of the given element match the filter.
+    pub fn matches(&self, element: &Element) -> bool {
+        match element {
+            Element::Node(node) => self.matches_raw(node.raw_tags()),
+            Element::DenseNode(node) => self.matches_raw(
+                node.raw_tags()
+                    .map(|(key, value)| (key as u32, value as u32)),
+            ),
+            Element::Way(way) => self.matches_raw(way.raw_tags()),
+            Element::Relation(relation) => self.matches_raw(relation.raw_tags()),
+        }
+    }
+}
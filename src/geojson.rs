@@ -0,0 +1,479 @@
+//! Export elements as GeoJSON
+//!
+//! [`GeoJsonWriter`] writes features in one of two formats (see [`GeoJsonFormat`]): a single
+//! GeoJSON `FeatureCollection` or newline-delimited GeoJSON (GeoJSONSeq, as GDAL calls it) with
+//! one feature per line. These are not RFC 8142 GeoJSON Text Sequences, which start each feature
+//! with a record separator. Each feature has the ID of its element (like `"n105"` or `"w107"`)
+//! and the tags as properties.
+//!
+//! [`export_geojson`] and [`export_geojson_indexed`] export all tagged elements of a PBF file:
+//! * tagged nodes as `Point`s,
+//! * closed ways with area tags as `Polygon`s, e.g. with `building`, `landuse` or `area=yes`
+//!   (see the [`area`](crate::area) module),
+//! * other tagged ways as `LineString`s, including closed ways of linear features like
+//!   `highway`, `barrier`, `railway` or `waterway` without `area=yes`,
+//! * and with an [`IndexedReader`] optionally multipolygon and boundary relations as `Polygon`s
+//!   or `MultiPolygon`s.
+//!
+//! Ways with missing node locations and areas that could not be assembled are skipped.
+//! Coordinates are written with seven decimal places.
+
+use crate::area::{assemble_way, is_closed_way_area, Area, AreaSource, AreaWay};
+use crate::elements::{Element, Way, WayNodeLocation};
+use crate::error::Result;
+use crate::indexed::IndexedReader;
+use crate::location_store::NodeLocationStore;
+use crate::opl::push_coordinate;
+use crate::owned::{OwnedElement, OwnedNode, OwnedWay};
+use crate::reader::ElementReader;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::io::{Read, Seek, Write};
+
+/// The output format of a [`GeoJsonWriter`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GeoJsonFormat {
+    /// A single GeoJSON object of type `FeatureCollection` that contains all features.
+    FeatureCollection,
+    /// One GeoJSON feature per line (newline-delimited GeoJSON, also known as GeoJSONSeq).
+    Sequence,
+}
+
+fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_position(out: &mut String, nano_lon: i64, nano_lat: i64) {
+    out.push('[');
+    push_coordinate(out, nano_lon);
+    out.push(',');
+    push_coordinate(out, nano_lat);
+    out.push(']');
+}
+
+fn push_positions(out: &mut String, locations: &[WayNodeLocation]) {
+    out.push('[');
+    for (i, location) in locations.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_position(out, location.nano_lon(), location.nano_lat());
+    }
+    out.push(']');
+}
+
+/// A writer for GeoJSON features.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let mut writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+/// writer.write_node(&OwnedNode {
+///     id: 1,
+///     nano_lat: 52_500_000_000,
+///     nano_lon: 13_400_000_000,
+///     tags: vec![("amenity".to_string(), "bench".to_string())],
+///     ..Default::default()
+/// })?;
+///
+/// let text = String::from_utf8(writer.finish()?).unwrap();
+/// assert_eq!(
+///     text,
+///     r#"{"type":"Feature","id":"n1","properties":{"amenity":"bench"},"geometry":{"type":"Point","coordinates":[13.4,52.5]}}"#
+///         .to_string()
+///         + "\n"
+/// );
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct GeoJsonWriter<W: Write> {
+    writer: W,
+    format: GeoJsonFormat,
+    features: u64,
+}
+
+impl<W: Write> GeoJsonWriter<W> {
+    /// Creates a new `GeoJsonWriter`.
+    pub fn new(writer: W, format: GeoJsonFormat) -> GeoJsonWriter<W> {
+        GeoJsonWriter {
+            writer,
+            format,
+            features: 0,
+        }
+    }
+
+    /// Returns the number of features that were written so far.
+    pub fn features(&self) -> u64 {
+        self.features
+    }
+
+    /// Writes a node as a `Point` feature.
+    pub fn write_node(&mut self, node: &OwnedNode) -> Result<()> {
+        let mut geometry = String::from(r#"{"type":"Point","coordinates":"#);
+        push_position(&mut geometry, node.nano_lon, node.nano_lat);
+        geometry.push('}');
+        self.write_feature('n', node.id, &node.tags, &geometry)
+    }
+
+    /// Writes a way as a `LineString` feature with the coordinates of
+    /// [`OwnedWay::node_locations`].
+    pub fn write_way(&mut self, way: &OwnedWay) -> Result<()> {
+        let mut geometry = String::from(r#"{"type":"LineString","coordinates":"#);
+        push_positions(&mut geometry, &way.node_locations);
+        geometry.push('}');
+        self.write_feature('w', way.id, &way.tags, &geometry)
+    }
+
+    /// Writes an area as a `Polygon` feature, or as a `MultiPolygon` feature if it consists of
+    /// more than one polygon. Areas without polygons are skipped.
+    pub fn write_area(&mut self, area: &Area) -> Result<()> {
+        let (element_type, id) = match area.source {
+            AreaSource::Way(id) => ('w', id),
+            AreaSource::Relation(id) => ('r', id),
+        };
+        let push_polygon = |geometry: &mut String, index: usize| {
+            let polygon = &area.polygons[index];
+            geometry.push('[');
+            push_positions(geometry, &polygon.outer);
+            for inner in &polygon.inners {
+                geometry.push(',');
+                push_positions(geometry, inner);
+            }
+            geometry.push(']');
+        };
+
+        let mut geometry = String::new();
+        match area.polygons.len() {
+            0 => return Ok(()),
+            1 => {
+                geometry.push_str(r#"{"type":"Polygon","coordinates":"#);
+                push_polygon(&mut geometry, 0);
+            }
+            len => {
+                geometry.push_str(r#"{"type":"MultiPolygon","coordinates":["#);
+                for index in 0..len {
+                    if index > 0 {
+                        geometry.push(',');
+                    }
+                    push_polygon(&mut geometry, index);
+                }
+                geometry.push(']');
+            }
+        }
+        geometry.push('}');
+        self.write_feature(element_type, id, &area.tags, &geometry)
+    }
+
+    fn write_feature(
+        &mut self,
+        element_type: char,
+        id: i64,
+        tags: &[(String, String)],
+        geometry: &str,
+    ) -> Result<()> {
+        let mut out = String::new();
+        match self.format {
+            GeoJsonFormat::FeatureCollection if self.features == 0 => {
+                out.push_str("{\"type\":\"FeatureCollection\",\"features\":[\n");
+            }
+            GeoJsonFormat::FeatureCollection => out.push_str(",\n"),
+            GeoJsonFormat::Sequence => {}
+        }
+        let _ = write!(
+            out,
+            r#"{{"type":"Feature","id":"{element_type}{id}","properties":{{"#
+        );
+        for (i, (key, value)) in tags.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_string(&mut out, key);
+            out.push(':');
+            push_json_string(&mut out, value);
+        }
+        out.push_str(r#"},"geometry":"#);
+        out.push_str(geometry);
+        out.push('}');
+        if self.format == GeoJsonFormat::Sequence {
+            out.push('\n');
+        }
+        self.writer.write_all(out.as_bytes())?;
+        self.features += 1;
+        Ok(())
+    }
+
+    /// Completes the output, flushes the underlying writer and returns it.
+    pub fn finish(mut self) -> Result<W> {
+        if self.format == GeoJsonFormat::FeatureCollection {
+            let end = if self.features == 0 {
+                "{\"type\":\"FeatureCollection\",\"features\":[]}\n"
+            } else {
+                "\n]}\n"
+            };
+            self.writer.write_all(end.as_bytes())?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Calls `f` on the ID and the location of a node and writes the node as a point if it is
+/// tagged. Only tagged nodes are converted to owned nodes.
+fn export_node<W, F>(element: &Element, writer: &mut GeoJsonWriter<W>, mut f: F) -> Result<()>
+where
+    W: Write,
+    F: FnMut(i64, WayNodeLocation) -> Result<()>,
+{
+    let (id, location, tagged) = match element {
+        Element::Node(node) => (
+            node.id(),
+            WayNodeLocation::new(node.nano_lat(), node.nano_lon()),
+            node.tags().len() > 0,
+        ),
+        Element::DenseNode(node) => (
+            node.id(),
+            WayNodeLocation::new(node.nano_lat(), node.nano_lon()),
+            node.tags().len() > 0,
+        ),
+        _ => return Ok(()),
+    };
+    f(id, location)?;
+    if tagged {
+        if let OwnedElement::Node(node) = element.to_owned()? {
+            writer.write_node(&node)?;
+        }
+    }
+    Ok(())
+}
+
+/// Keys that make a closed way an area.
+const AREA_KEYS: &[&str] = &[
+    "amenity",
+    "building",
+    "building:part",
+    "craft",
+    "historic",
+    "landuse",
+    "leisure",
+    "man_made",
+    "military",
+    "natural",
+    "office",
+    "place",
+    "shop",
+    "tourism",
+    "water",
+    "wetland",
+];
+
+/// Values of `natural` and `man_made` that are linear features.
+const LINEAR_VALUES: &[&str] = &[
+    "arete",
+    "breakwater",
+    "cliff",
+    "coastline",
+    "cutline",
+    "embankment",
+    "groyne",
+    "pipeline",
+    "ridge",
+    "tree_row",
+];
+
+/// Keys of linear features. Closed ways with these keys are only areas if tagged `area=yes`.
+const LINEAR_KEYS: &[&str] = &["barrier", "highway", "railway", "waterway"];
+
+/// Returns true if a closed way with these tags is exported as a polygon.
+fn has_area_tags(tags: &[(String, String)]) -> bool {
+    let has_key = |key: &str| tags.iter().any(|(k, _)| k == key);
+    if let Some((_, area)) = tags.iter().find(|(k, _)| k == "area") {
+        return area == "yes";
+    }
+    if LINEAR_KEYS.iter().any(|key| has_key(key)) {
+        return false;
+    }
+    tags.iter().any(|(key, value)| {
+        AREA_KEYS.contains(&key.as_str())
+            && !((key == "natural" || key == "man_made") && LINEAR_VALUES.contains(&value.as_str()))
+    })
+}
+
+/// Writes a tagged way as an area or a line string, unless a node location is missing.
+fn write_way<W: Write>(
+    writer: &mut GeoJsonWriter<W>,
+    way: &Way,
+    locations: &[Option<WayNodeLocation>],
+    with_areas: bool,
+) -> Result<()> {
+    let mut owned = way.to_owned()?;
+    if owned.tags.is_empty() {
+        return Ok(());
+    }
+    if is_closed_way_area(&owned.refs, &owned.tags) && has_area_tags(&owned.tags) {
+        if with_areas {
+            let area_way = AreaWay {
+                id: owned.id,
+                refs: owned.refs,
+                locations: locations.to_vec(),
+            };
+            writer.write_area(&assemble_way(&area_way, owned.tags))?;
+        }
+        return Ok(());
+    }
+    if let Some(locations) = locations.iter().copied().collect::<Option<Vec<_>>>() {
+        owned.node_locations = locations;
+        writer.write_way(&owned)?;
+    }
+    Ok(())
+}
+
+/// Exports the tagged nodes and ways of a PBF file in a single pass. See the
+/// [module documentation](crate::geojson) for the exported features. Returns the underlying
+/// writer of the `GeoJsonWriter` after finishing it.
+///
+/// The locations of all nodes are stored in `store` to look up the node locations of ways, so
+/// nodes have to come before ways like in files that are sorted by type. Files with node
+/// locations on ways can be exported with any store.
+///
+/// # Errors
+/// Returns the first Error encountered while reading, storing a location or writing.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+/// let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::FeatureCollection);
+/// let bytes = export_geojson(reader, &mut SparseLocationStore::new(), writer)?;
+///
+/// // The closed building way 107 is exported as a polygon.
+/// let text = String::from_utf8(bytes).unwrap();
+/// assert!(text.contains(r#""id":"w107""#));
+/// assert!(text.contains(r#""type":"Polygon""#));
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub fn export_geojson<R, S, W>(
+    reader: ElementReader<R>,
+    store: &mut S,
+    mut writer: GeoJsonWriter<W>,
+) -> Result<W>
+where
+    R: Read + Send,
+    S: NodeLocationStore + ?Sized,
+    W: Write,
+{
+    let mut result = Ok(());
+    // Locations that were stored after the last call of `finish`
+    let mut store_dirty = false;
+    let mut locations = vec![];
+    reader.for_each(|element| {
+        if result.is_err() {
+            return;
+        }
+        result = match element {
+            Element::Node(_) | Element::DenseNode(_) => {
+                export_node(&element, &mut writer, |id, location| {
+                    store_dirty = true;
+                    store.set(id, location)
+                })
+            }
+            Element::Way(way) => {
+                let finished = if store_dirty {
+                    store_dirty = false;
+                    store.finish()
+                } else {
+                    Ok(())
+                };
+                finished.and_then(|_| {
+                    locations.clear();
+                    let node_locations = way.node_locations();
+                    if node_locations.len() > 0 {
+                        locations.extend(node_locations.map(Some));
+                    } else {
+                        locations.extend(way.refs().map(|id| store.get(id)));
+                    }
+                    write_way(&mut writer, &way, &locations, true)
+                })
+            }
+            Element::Relation(_) => Ok(()),
+        };
+    })?;
+    result?;
+    writer.finish()
+}
+
+/// Exports the tagged nodes and ways of a PBF file and, if `multipolygons` is true, also the
+/// areas of multipolygon and boundary relations. See the
+/// [module documentation](crate::geojson) for the exported features. Returns the underlying
+/// writer of the `GeoJsonWriter` after finishing it.
+///
+/// The locations of all nodes are written to the given store first.
+///
+/// # Errors
+/// Returns the first Error encountered while reading, storing a location or writing.
+pub fn export_geojson_indexed<R, S, W>(
+    reader: &mut IndexedReader<R>,
+    store: &mut S,
+    mut writer: GeoJsonWriter<W>,
+    multipolygons: bool,
+) -> Result<W>
+where
+    R: Read + Seek + Send,
+    S: NodeLocationStore + ?Sized,
+    W: Write,
+{
+    let mut result = Ok(());
+    reader.for_each_node(|element| {
+        if result.is_ok() {
+            // The locations are stored by `for_each_way_and_area`
+            result = export_node(&element, &mut writer, |_, _| Ok(()));
+        }
+    })?;
+    result?;
+
+    // Areas of closed ways with area tags are written by the area callback.
+    let writer = RefCell::new(writer);
+    let result = RefCell::new(Ok(()));
+    reader.for_each_way_and_area(
+        store,
+        |way, locations| {
+            let mut result = result.borrow_mut();
+            if result.is_ok() {
+                *result = write_way(&mut writer.borrow_mut(), way, locations, false);
+            }
+        },
+        |area| {
+            let mut result = result.borrow_mut();
+            let selected = match area.source {
+                AreaSource::Way(_) => has_area_tags(&area.tags),
+                AreaSource::Relation(_) => multipolygons,
+            };
+            if result.is_ok() && selected {
+                *result = writer.borrow_mut().write_area(&area);
+            }
+        },
+    )?;
+    result.into_inner()?;
+    writer.into_inner().finish()
+}
@@ -0,0 +1,183 @@
+//! Iterate over the versions of elements in history files
+//!
+//! Full-history files (`*.osh.pbf`) contain all versions of each element, including deleted
+//! versions (see [`Info::deleted`](crate::elements::Info::deleted)). The versions of an element
+//! are stored one after another, sorted by version. [`HistoryIter`] groups these consecutive
+//! versions into an [`ElementHistory`] and [`Snapshot`] yields the state of the data at a point in
+//! time.
+
+use crate::error::Result;
+use crate::owned::OwnedElement;
+use crate::reader::BlockIter;
+use std::collections::VecDeque;
+use std::io::Read;
+
+/// All versions of a single element in file order, which is usually ascending by version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ElementHistory {
+    versions: Vec<OwnedElement>,
+}
+
+/// Returns true if both elements have the same type and ID.
+fn is_same_element(a: &OwnedElement, b: &OwnedElement) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b) && a.id() == b.id()
+}
+
+/// Returns true if the version is marked as deleted.
+fn is_deleted(element: &OwnedElement) -> bool {
+    element.info().visible == Some(false)
+}
+
+impl ElementHistory {
+    /// Returns the ID of the element.
+    pub fn id(&self) -> i64 {
+        self.versions[0].id()
+    }
+
+    /// Returns all versions. The slice is never empty.
+    pub fn versions(&self) -> &[OwnedElement] {
+        &self.versions
+    }
+
+    /// Returns the versions and consumes the history.
+    pub fn into_versions(self) -> Vec<OwnedElement> {
+        self.versions
+    }
+
+    /// Returns the last version.
+    pub fn latest(&self) -> &OwnedElement {
+        self.versions.last().expect("history is not empty")
+    }
+
+    /// Returns true if the last version is deleted.
+    pub fn is_deleted(&self) -> bool {
+        is_deleted(self.latest())
+    }
+
+    /// Returns the version that was valid at the given time in milliseconds since the epoch, i.e.
+    /// the last version with a time stamp that is not after `milli_timestamp`. Returns `None` if
+    /// the element did not exist yet or if that version is deleted. Versions without a time stamp
+    /// are ignored.
+    pub fn at(&self, milli_timestamp: i64) -> Option<&OwnedElement> {
+        self.index_at(milli_timestamp)
+            .map(|index| &self.versions[index])
+    }
+
+    fn index_at(&self, milli_timestamp: i64) -> Option<usize> {
+        self.versions
+            .iter()
+            .rposition(|v| {
+                v.info()
+                    .milli_timestamp
+                    .is_some_and(|t| t <= milli_timestamp)
+            })
+            .filter(|&index| !is_deleted(&self.versions[index]))
+    }
+}
+
+/// An iterator over the histories of all elements, see the [module documentation](crate::history).
+///
+/// Consecutive elements with the same type and ID are grouped into one [`ElementHistory`], even
+/// if they are stored in different blocks. The iteration stops after the first error.
+///
+/// This struct is created by [`ElementReader::histories`](crate::reader::ElementReader::histories).
+#[derive(Debug)]
+pub struct HistoryIter<R: Read + Send> {
+    blocks: BlockIter<R>,
+    elements: VecDeque<OwnedElement>,
+    finished: bool,
+}
+
+impl<R: Read + Send> HistoryIter<R> {
+    pub(crate) fn new(blocks: BlockIter<R>) -> HistoryIter<R> {
+        HistoryIter {
+            blocks,
+            elements: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns an iterator over the elements as they were at the given time in milliseconds
+    /// since the epoch. See [`Snapshot`].
+    pub fn as_of(self, milli_timestamp: i64) -> Snapshot<R> {
+        Snapshot {
+            histories: self,
+            milli_timestamp,
+        }
+    }
+
+    /// Makes sure that at least one element is buffered unless the end of the file is reached.
+    fn fill(&mut self) -> Result<bool> {
+        while self.elements.is_empty() {
+            match self.blocks.next() {
+                Some(block) => {
+                    for element in block?.elements() {
+                        self.elements.push_back(element.to_owned()?);
+                    }
+                }
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    fn next_history(&mut self) -> Result<Option<ElementHistory>> {
+        if !self.fill()? {
+            return Ok(None);
+        }
+        let mut versions = vec![self.elements.pop_front().expect("element is buffered")];
+        while self.fill()? {
+            match self.elements.front() {
+                Some(next) if is_same_element(next, &versions[0]) => {
+                    versions.push(self.elements.pop_front().expect("element is buffered"));
+                }
+                _ => break,
+            }
+        }
+        Ok(Some(ElementHistory { versions }))
+    }
+}
+
+impl<R: Read + Send> Iterator for HistoryIter<R> {
+    type Item = Result<ElementHistory>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_history().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.finished = true;
+        }
+        result
+    }
+}
+
+/// An iterator over the elements as they were at a point in time. For each element it yields the
+/// version given by [`ElementHistory::at`] and skips elements that did not exist or were
+/// deleted at that time.
+///
+/// This struct is created by [`HistoryIter::as_of`].
+#[derive(Debug)]
+pub struct Snapshot<R: Read + Send> {
+    histories: HistoryIter<R>,
+    milli_timestamp: i64,
+}
+
+impl<R: Read + Send> Iterator for Snapshot<R> {
+    type Item = Result<OwnedElement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for history in self.histories.by_ref() {
+            match history {
+                Ok(mut history) => {
+                    if let Some(index) = history.index_at(self.milli_timestamp) {
+                        return Some(Ok(history.versions.swap_remove(index)));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
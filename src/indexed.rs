@@ -1,12 +1,30 @@
 //! Speed up searches by using an index
 
-use crate::error::Result;
-use crate::{BlobReader, BlobType, ByteOffset, Element, PrimitiveBlock, Way};
-use std::collections::BTreeSet;
+use crate::area::{
+    assemble_relation, assemble_way, is_area_relation, is_area_role, is_closed_way_area, Area,
+    AreaWay,
+};
+use crate::error::{new_error, ErrorKind, Result};
+use crate::validate::{
+    allows_duplicate_ids, sort_violation_kind, IdSet, MissingReference, SortViolation,
+};
+use crate::{
+    BlobReader, BlobType, ByteOffset, Element, ElementType, HeaderBlock, NodeLocationStore,
+    PrimitiveBlock, RelMemberType, Relation, TagFilter, TagIter, Way, WayNodeLocation,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs::File;
-use std::io::{Read, Seek};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
 use std::ops::RangeInclusive;
 use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Magic bytes at the start of a saved index file.
+const INDEX_MAGIC: &[u8; 8] = b"OSMPBFIX";
+
+/// Version of the saved index file format.
+const INDEX_VERSION: u32 = 1;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum SimpleBlobType {
@@ -22,6 +40,17 @@ enum ElementsAvailable {
     Unknown,
 }
 
+/// The result of a binary search for the blob that contains a specific element.
+enum Candidate {
+    /// Position of the blob in the index and the decoded block if it had to be decoded during the
+    /// search.
+    Blob(usize, Option<PrimitiveBlock>),
+    /// The element is not in the file.
+    None,
+    /// The search is not possible, e.g. because a blob does not contain any elements.
+    Unknown,
+}
+
 /// Returns true if the given set contains at least one value that is inside the given range.
 fn range_included(range: RangeInclusive<i64>, node_ids: &BTreeSet<i64>) -> bool {
     node_ids.range(range).next().is_some()
@@ -35,15 +64,38 @@ enum RangeIncluded {
 }
 
 /// Stores the minimum and maximum id of every element type.
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 pub struct IdRanges {
     node_ids: Option<RangeInclusive<i64>>,
     way_ids: Option<RangeInclusive<i64>>,
-    //TODO actually use this field
-    #[allow(dead_code)]
     relation_ids: Option<RangeInclusive<i64>>,
 }
 
+impl IdRanges {
+    fn get(&self, element_type: ElementType) -> &Option<RangeInclusive<i64>> {
+        match element_type {
+            ElementType::Node => &self.node_ids,
+            ElementType::Way => &self.way_ids,
+            ElementType::Relation => &self.relation_ids,
+        }
+    }
+
+    /// Returns the first and the last element (type and ID) of a blob in a file that is sorted by
+    /// type, then by ID. Returns `None` if the blob has no elements.
+    fn sorted_bounds(&self) -> Option<((ElementType, i64), (ElementType, i64))> {
+        let types = [ElementType::Node, ElementType::Way, ElementType::Relation];
+        let mut present = types
+            .into_iter()
+            .filter_map(|t| self.get(t).as_ref().map(|range| (t, range)));
+        let (first_type, first_range) = present.next()?;
+        let (last_type, last_range) = present.next_back().unwrap_or((first_type, first_range));
+        Some((
+            (first_type, *first_range.start()),
+            (last_type, *last_range.end()),
+        ))
+    }
+}
+
 /// A part of the index that stores information about a specific blob.
 #[derive(Debug)]
 struct BlobInfo {
@@ -75,28 +127,57 @@ impl BlobInfo {
         }
     }
 
-    /*
     /// Is there at least one relation in this blob?
     fn relations_available(&self) -> ElementsAvailable {
         match self.id_ranges {
-            Some(IdRanges {relation_ids: Some(_), ..}) => ElementsAvailable::Yes,
-            Some(IdRanges {relation_ids: None, ..}) => ElementsAvailable::No,
+            Some(IdRanges {
+                relation_ids: Some(_),
+                ..
+            }) => ElementsAvailable::Yes,
+            Some(IdRanges {
+                relation_ids: None, ..
+            }) => ElementsAvailable::No,
             None => ElementsAvailable::Unknown,
         }
     }
-    */
 
     /// Compute if the range of node IDs of this blob (min and max ID value) is included in the
     /// given set of IDs with at least one ID inside of this range.
     fn node_range_included(&self, node_ids: &BTreeSet<i64>) -> RangeIncluded {
-        match self.id_ranges.as_ref() {
+        self.id_range_included(node_ids, |ranges| &ranges.node_ids)
+    }
+
+    /// Compute if the range of way IDs of this blob (min and max ID value) is included in the
+    /// given set of IDs with at least one ID inside of this range.
+    fn way_range_included(&self, way_ids: &BTreeSet<i64>) -> RangeIncluded {
+        self.id_range_included(way_ids, |ranges| &ranges.way_ids)
+    }
+
+    /// Compute if the range of relation IDs of this blob (min and max ID value) is included in
+    /// the given set of IDs with at least one ID inside of this range.
+    fn relation_range_included(&self, relation_ids: &BTreeSet<i64>) -> RangeIncluded {
+        self.id_range_included(relation_ids, |ranges| &ranges.relation_ids)
+    }
+
+    /// Compute if the range of IDs of the given element type is included in the given set of IDs
+    /// with at least one ID inside of this range.
+    fn element_range_included(
+        &self,
+        element_type: ElementType,
+        ids: &BTreeSet<i64>,
+    ) -> RangeIncluded {
+        self.id_range_included(ids, |ranges| ranges.get(element_type))
+    }
+
+    fn id_range_included<F>(&self, ids: &BTreeSet<i64>, select_range: F) -> RangeIncluded
+    where
+        F: Fn(&IdRanges) -> &Option<RangeInclusive<i64>>,
+    {
+        match self.id_ranges.as_ref().map(select_range) {
             None => RangeIncluded::Unknown,
-            Some(IdRanges { node_ids: None, .. }) => RangeIncluded::No,
-            Some(IdRanges {
-                node_ids: Some(range),
-                ..
-            }) => {
-                if range_included(range.clone(), node_ids) {
+            Some(None) => RangeIncluded::No,
+            Some(Some(range)) => {
+                if range_included(range.clone(), ids) {
                     RangeIncluded::Yes(range.clone())
                 } else {
                     RangeIncluded::No
@@ -106,12 +187,217 @@ impl BlobInfo {
     }
 }
 
+/// Size and modification time of a PBF file. A saved index is only valid for the file it was
+/// created from, so these values are stored in the index and compared when loading it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct FileStamp {
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+}
+
+impl FileStamp {
+    fn from_file(file: &File) -> Result<FileStamp> {
+        let metadata = file.metadata()?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(FileStamp {
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+}
+
+fn invalid_index(reason: &'static str) -> crate::Error {
+    new_error(ErrorKind::InvalidIndex { reason })
+}
+
+/// Serializes the index. All integers are stored in big-endian byte order:
+///
+/// * magic bytes `OSMPBFIX`, format version (u32)
+/// * file size (u64), modification time in seconds (u64) and nanoseconds (u32)
+/// * number of blobs (u64) followed by each blob:
+///   * offset (u64), blob type (u8), flags (u8) that mark which ID ranges are known/present
+///   * min and max ID (2 × i64) for each present range (nodes, ways, relations)
+fn write_index<W: Write>(mut writer: W, stamp: FileStamp, index: &[BlobInfo]) -> Result<()> {
+    writer.write_all(INDEX_MAGIC)?;
+    writer.write_u32::<BigEndian>(INDEX_VERSION)?;
+    writer.write_u64::<BigEndian>(stamp.size)?;
+    writer.write_u64::<BigEndian>(stamp.mtime_secs)?;
+    writer.write_u32::<BigEndian>(stamp.mtime_nanos)?;
+    writer.write_u64::<BigEndian>(index.len() as u64)?;
+
+    for info in index {
+        writer.write_u64::<BigEndian>(info.offset.0)?;
+        writer.write_u8(match info.blob_type {
+            SimpleBlobType::Header => 0,
+            SimpleBlobType::Primitive => 1,
+            SimpleBlobType::Unknown => 2,
+        })?;
+
+        let ranges = match info.id_ranges.as_ref() {
+            Some(ranges) => [&ranges.node_ids, &ranges.way_ids, &ranges.relation_ids],
+            None => {
+                writer.write_u8(0)?;
+                continue;
+            }
+        };
+        let mut flags = 1;
+        for (i, range) in ranges.iter().enumerate() {
+            if range.is_some() {
+                flags |= 2 << i;
+            }
+        }
+        writer.write_u8(flags)?;
+        for range in ranges.into_iter().flatten() {
+            writer.write_i64::<BigEndian>(*range.start())?;
+            writer.write_i64::<BigEndian>(*range.end())?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Deserializes an index that was written with `write_index` and checks that it matches the
+/// given file stamp.
+fn read_index<R: Read>(reader: R, stamp: FileStamp) -> Result<Vec<BlobInfo>> {
+    read_index_entries(reader, stamp).map_err(|err| match err.kind() {
+        ErrorKind::Io(io_err) if io_err.kind() == io::ErrorKind::UnexpectedEof => {
+            invalid_index("truncated index")
+        }
+        _ => err,
+    })
+}
+
+fn read_index_entries<R: Read>(mut reader: R, stamp: FileStamp) -> Result<Vec<BlobInfo>> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != INDEX_MAGIC {
+        return Err(invalid_index("not an index file"));
+    }
+    if reader.read_u32::<BigEndian>()? != INDEX_VERSION {
+        return Err(invalid_index("unsupported format version"));
+    }
+
+    let saved_stamp = FileStamp {
+        size: reader.read_u64::<BigEndian>()?,
+        mtime_secs: reader.read_u64::<BigEndian>()?,
+        mtime_nanos: reader.read_u32::<BigEndian>()?,
+    };
+    if saved_stamp.size != stamp.size {
+        return Err(invalid_index("file size does not match"));
+    }
+    if saved_stamp != stamp {
+        return Err(invalid_index("file modification time does not match"));
+    }
+
+    let len = reader.read_u64::<BigEndian>()?;
+    let mut index = vec![];
+    for _ in 0..len {
+        let offset = reader.read_u64::<BigEndian>()?;
+        if offset >= stamp.size {
+            return Err(invalid_index("blob offset is out of bounds"));
+        }
+        let blob_type = match reader.read_u8()? {
+            0 => SimpleBlobType::Header,
+            1 => SimpleBlobType::Primitive,
+            2 => SimpleBlobType::Unknown,
+            _ => return Err(invalid_index("unknown blob type")),
+        };
+
+        let flags = reader.read_u8()?;
+        let id_ranges = if flags & 1 == 0 {
+            None
+        } else {
+            let mut ranges = [None, None, None];
+            for (i, range) in ranges.iter_mut().enumerate() {
+                if flags & (2 << i) != 0 {
+                    let min = reader.read_i64::<BigEndian>()?;
+                    let max = reader.read_i64::<BigEndian>()?;
+                    if min > max {
+                        return Err(invalid_index("invalid ID range"));
+                    }
+                    *range = Some(RangeInclusive::new(min, max));
+                }
+            }
+            let [node_ids, way_ids, relation_ids] = ranges;
+            Some(IdRanges {
+                node_ids,
+                way_ids,
+                relation_ids,
+            })
+        };
+
+        index.push(BlobInfo {
+            offset: ByteOffset(offset),
+            blob_type,
+            id_ranges,
+        });
+    }
+
+    Ok(index)
+}
+
 /// Allows filtering elements and iterating over their dependencies.
 /// It chooses an efficient method for navigating the PBF structure to achieve this in reasonable
 /// time and with reasonable memory.
 pub struct IndexedReader<R: Read + Seek + Send> {
     reader: BlobReader<R>,
     index: Vec<BlobInfo>,
+    /// Does the header declare that the file is sorted by type, then by ID? Only known after the
+    /// header has been read.
+    sorted_by_type_then_id: Option<bool>,
+}
+
+/// Calls the closure on each element of the given type with an ID from the given set and adds
+/// the IDs of these elements to `found`.
+fn for_each_element_with_id<F>(
+    block: &PrimitiveBlock,
+    element_type: ElementType,
+    ids: &BTreeSet<i64>,
+    found: &mut BTreeSet<i64>,
+    f: &mut F,
+) where
+    F: for<'a> FnMut(&Element<'a>),
+{
+    for group in block.groups() {
+        match element_type {
+            ElementType::Node => {
+                for node in group.nodes() {
+                    if ids.contains(&node.id()) {
+                        found.insert(node.id());
+                        f(&Element::Node(node));
+                    }
+                }
+                for node in group.dense_nodes() {
+                    if ids.contains(&node.id) {
+                        found.insert(node.id);
+                        f(&Element::DenseNode(node));
+                    }
+                }
+            }
+            ElementType::Way => {
+                for way in group.ways() {
+                    if ids.contains(&way.id()) {
+                        found.insert(way.id());
+                        f(&Element::Way(way));
+                    }
+                }
+            }
+            ElementType::Relation => {
+                for relation in group.relations() {
+                    if ids.contains(&relation.id()) {
+                        found.insert(relation.id());
+                        f(&Element::Relation(relation));
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl<R: Read + Seek + Send> IndexedReader<R> {
@@ -136,6 +422,7 @@ impl<R: Read + Seek + Send> IndexedReader<R> {
         Ok(Self {
             reader,
             index: vec![],
+            sorted_by_type_then_id: None,
         })
     }
 
@@ -261,7 +548,56 @@ impl<R: Read + Seek + Send> IndexedReader<R> {
     /// # }
     /// # foo().unwrap();
     /// ```
-    pub fn read_ways_and_deps<F, E>(&mut self, mut filter: F, mut element_callback: E) -> Result<()>
+    pub fn read_ways_and_deps<F, E>(&mut self, filter: F, element_callback: E) -> Result<()>
+    where
+        F: for<'a> FnMut(&Way<'a>) -> bool,
+        E: for<'a> FnMut(&Element<'a>),
+    {
+        self.read_ways_and_deps_impl(None, filter, element_callback)
+    }
+
+    /// Just like [`read_ways_and_deps`](IndexedReader::read_ways_and_deps), but filters ways by
+    /// their tags with a [`TagFilter`]. The filter is compiled once per block, so matching a way
+    /// does not decode any strings.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut filter = TagFilter::any();
+    /// filter.key_value("building", "yes");
+    /// let mut nodes = 0;
+    ///
+    /// reader.read_ways_and_deps_filtered(&filter, |element| {
+    ///     if let Element::Node(_) | Element::DenseNode(_) = element {
+    ///         nodes += 1;
+    ///     }
+    /// })?;
+    ///
+    /// # assert_eq!(nodes, 3);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn read_ways_and_deps_filtered<E>(
+        &mut self,
+        filter: &TagFilter,
+        element_callback: E,
+    ) -> Result<()>
+    where
+        E: for<'a> FnMut(&Element<'a>),
+    {
+        self.read_ways_and_deps_impl(Some(filter), |_| true, element_callback)
+    }
+
+    fn read_ways_and_deps_impl<F, E>(
+        &mut self,
+        tag_filter: Option<&TagFilter>,
+        mut filter: F,
+        mut element_callback: E,
+    ) -> Result<()>
     where
         F: for<'a> FnMut(&Way<'a>) -> bool,
         E: for<'a> FnMut(&Element<'a>),
@@ -283,10 +619,18 @@ impl<R: Read + Seek + Send> IndexedReader<R> {
                     .to_primitiveblock()?;
                 Self::update_element_id_ranges(info, &block);
 
+                let tag_filter = tag_filter.map(|tag_filter| tag_filter.compile(&block));
+                if tag_filter.as_ref().is_some_and(|f| !f.can_match()) {
+                    continue;
+                }
+
                 for group in block.groups() {
                     // filter ways and record node IDs
                     for way in group.ways() {
-                        if filter(&way) {
+                        let tags_match = tag_filter
+                            .as_ref()
+                            .is_none_or(|f| f.matches_raw(way.raw_tags()));
+                        if tags_match && filter(&way) {
                             let refs = way.refs();
 
                             node_ids.extend(refs);
@@ -301,8 +645,21 @@ impl<R: Read + Seek + Send> IndexedReader<R> {
 
         // Second pass:
         //   * Iterate only over blobs that may include the node IDs we're searching for
+        self.read_nodes_by_id(&node_ids, &mut element_callback)
+    }
+
+    /// Call the closure on each node with an ID from the given set.
+    /// Only visits blobs that may include the given IDs according to the index.
+    pub(crate) fn read_nodes_by_id<E>(
+        &mut self,
+        node_ids: &BTreeSet<i64>,
+        mut element_callback: E,
+    ) -> Result<()>
+    where
+        E: for<'a> FnMut(&Element<'a>),
+    {
         for info in &mut self.index {
-            if let RangeIncluded::Yes(node_id_range) = info.node_range_included(&node_ids) {
+            if let RangeIncluded::Yes(node_id_range) = info.node_range_included(node_ids) {
                 //TODO Only collect into Vec if range has a reasonable size
                 let node_ids: Vec<i64> = node_ids.range(node_id_range).copied().collect();
                 let block = self
@@ -329,48 +686,105 @@ impl<R: Read + Seek + Send> IndexedReader<R> {
         Ok(())
     }
 
-    /// Decodes the PBF structure sequentially and calls the given closure on each node.
+    /// Filter relations using a closure and return matching relations and their dependencies in
+    /// another closure. The dependencies are the member ways, the nodes of these ways and the
+    /// member nodes. Member relations are not returned; use
+    /// [`read_relations_and_deps_recursive`](IndexedReader::read_relations_and_deps_recursive)
+    /// to include them.
+    /// Elements are returned in this order: relations, ways, nodes.
     /// This method also creates a lightweight in-memory index that speeds up future invocations of
     /// this or any other method of `IndexedReader`.
     ///
-    /// # Errors
-    /// Returns the first Error encountered while parsing the PBF structure.
-    ///
     /// # Example
     /// ```
     /// use osmpbf::*;
     ///
     /// # fn foo() -> Result<()> {
     /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut relations = 0;
+    /// let mut ways = 0;
     /// let mut nodes = 0;
     ///
-    /// reader.for_each_node(
+    /// // Filter all relations with a specific tag and count their dependencies.
+    /// reader.read_relations_and_deps(
+    ///     |relation| relation.tags().any(|key_value| key_value == ("rel_key", "rel_value")),
     ///     |element| {
     ///         match element {
-    ///             Element::Node(node) => nodes += 1,
-    ///             Element::DenseNode(dense_node) => nodes += 1,
-    ///             _ => {}
+    ///             Element::Relation(_) => relations += 1,
+    ///             Element::Way(_) => ways += 1,
+    ///             Element::Node(_) | Element::DenseNode(_) => nodes += 1,
     ///         }
     ///     },
     /// )?;
     ///
-    /// println!("nodes: {nodes}");
+    /// println!("relations: {relations}\nways: {ways}\nnodes: {nodes}");
     ///
+    /// # assert_eq!(relations, 1);
+    /// # assert_eq!(ways, 1);
     /// # assert_eq!(nodes, 3);
     /// # Ok(())
     /// # }
     /// # foo().unwrap();
     /// ```
-    pub fn for_each_node<F>(&mut self, mut f: F) -> Result<()>
+    pub fn read_relations_and_deps<F, E>(&mut self, filter: F, element_callback: E) -> Result<()>
     where
-        F: for<'a> FnMut(Element<'a>),
+        F: for<'a> FnMut(&Relation<'a>) -> bool,
+        E: for<'a> FnMut(&Element<'a>),
+    {
+        self.read_relations_and_deps_impl(filter, false, element_callback)
+    }
+
+    /// Just like [`read_relations_and_deps`](IndexedReader::read_relations_and_deps), but also
+    /// returns member relations and all of their dependencies, recursively. Each element is
+    /// only returned once, even if it is referenced multiple times or if relations reference each
+    /// other in a cycle.
+    pub fn read_relations_and_deps_recursive<F, E>(
+        &mut self,
+        filter: F,
+        element_callback: E,
+    ) -> Result<()>
+    where
+        F: for<'a> FnMut(&Relation<'a>) -> bool,
+        E: for<'a> FnMut(&Element<'a>),
+    {
+        self.read_relations_and_deps_impl(filter, true, element_callback)
+    }
+
+    fn read_relations_and_deps_impl<F, E>(
+        &mut self,
+        mut filter: F,
+        recursive: bool,
+        mut element_callback: E,
+    ) -> Result<()>
+    where
+        F: for<'a> FnMut(&Relation<'a>) -> bool,
+        E: for<'a> FnMut(&Element<'a>),
     {
         self.create_index()?;
 
+        let mut relation_ids: BTreeSet<i64> = BTreeSet::new();
+        let mut way_ids: BTreeSet<i64> = BTreeSet::new();
+        let mut node_ids: BTreeSet<i64> = BTreeSet::new();
+        let mut pending_relation_ids: BTreeSet<i64> = BTreeSet::new();
+
+        let mut record_members = |relation: &Relation, pending_relation_ids: &mut BTreeSet<i64>| {
+            for member in relation.members() {
+                match member.member_type {
+                    RelMemberType::Node => node_ids.insert(member.member_id),
+                    RelMemberType::Way => way_ids.insert(member.member_id),
+                    RelMemberType::Relation if recursive => {
+                        pending_relation_ids.insert(member.member_id)
+                    }
+                    RelMemberType::Relation => false,
+                };
+            }
+        };
+
+        // First pass:
+        //   * Filter relations and store their members as IDs
         for info in &mut self.index {
-            // Skip header blobs and blobs where there are certainly no nodes available.
             if info.blob_type == SimpleBlobType::Primitive
-                && info.nodes_available() != ElementsAvailable::No
+                && info.relations_available() != ElementsAvailable::No
             {
                 let block = self
                     .reader
@@ -379,44 +793,989 @@ impl<R: Read + Seek + Send> IndexedReader<R> {
                 Self::update_element_id_ranges(info, &block);
 
                 for group in block.groups() {
-                    for node in group.nodes() {
-                        f(Element::Node(node));
+                    for relation in group.relations() {
+                        if filter(&relation) {
+                            relation_ids.insert(relation.id());
+                            record_members(&relation, &mut pending_relation_ids);
+                            element_callback(&Element::Relation(relation));
+                        }
                     }
-                    for dense_node in group.dense_nodes() {
-                        f(Element::DenseNode(dense_node));
+                }
+            }
+        }
+
+        // Further passes (only if recursive):
+        //   * Find member relations that were not returned yet and store their members
+        pending_relation_ids.retain(|id| !relation_ids.contains(id));
+        while !pending_relation_ids.is_empty() {
+            let current_ids = std::mem::take(&mut pending_relation_ids);
+            for info in &mut self.index {
+                if let RangeIncluded::Yes(_) = info.relation_range_included(&current_ids) {
+                    let block = self
+                        .reader
+                        .blob_from_offset(info.offset)?
+                        .to_primitiveblock()?;
+                    for group in block.groups() {
+                        for relation in group.relations() {
+                            if current_ids.contains(&relation.id())
+                                && relation_ids.insert(relation.id())
+                            {
+                                record_members(&relation, &mut pending_relation_ids);
+                                element_callback(&Element::Relation(relation));
+                            }
+                        }
                     }
                 }
             }
+            pending_relation_ids.retain(|id| !relation_ids.contains(id));
         }
 
-        Ok(())
+        // Pass over ways:
+        //   * Iterate only over blobs that may include the way IDs we're searching for
+        //   * Store the node IDs of these ways
+        for info in &mut self.index {
+            if let RangeIncluded::Yes(_) = info.way_range_included(&way_ids) {
+                let block = self
+                    .reader
+                    .blob_from_offset(info.offset)?
+                    .to_primitiveblock()?;
+                for group in block.groups() {
+                    for way in group.ways() {
+                        if way_ids.contains(&way.id()) {
+                            node_ids.extend(way.refs());
+                            element_callback(&Element::Way(way));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Pass over nodes:
+        //   * Iterate only over blobs that may include the node IDs we're searching for
+        self.read_nodes_by_id(&node_ids, &mut element_callback)
     }
-}
 
-impl IndexedReader<File> {
-    /// Creates a new `IndexedReader` from a given path.
+    /// Looks up the node with the given ID and returns the result of calling the closure on it.
+    /// Returns `Ok(None)` if there is no such node.
+    ///
+    /// If the header of the file declares the optional feature `Sort.Type_then_ID`, the blob
+    /// that contains the node is found with a binary search, so only a few blobs need to be
+    /// decoded. The ID ranges that are learned during the search are kept in the index, so later
+    /// lookups usually decode only the one candidate blob. Unsorted files are scanned
+    /// sequentially, skipping blobs whose known ID ranges do not contain the ID.
     ///
     /// # Example
     /// ```
     /// use osmpbf::*;
     ///
     /// # fn foo() -> Result<()> {
-    /// let reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// let lat = reader.get_node(105, |element| match element {
+    ///     Element::Node(node) => node.lat(),
+    ///     Element::DenseNode(node) => node.lat(),
+    ///     _ => unreachable!(),
+    /// })?;
     ///
+    /// println!("latitude: {lat:?}");
+    /// # assert!(lat.is_some());
     /// # Ok(())
     /// # }
     /// # foo().unwrap();
     /// ```
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
-        //TODO take some more measurements to determine if `BufReader` should be used here
-        let f = File::open(path)?;
-        Self::new(f)
+    pub fn get_node<F, T>(&mut self, id: i64, f: F) -> Result<Option<T>>
+    where
+        F: for<'a> FnOnce(&Element<'a>) -> T,
+    {
+        self.get_element(ElementType::Node, id, f)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Looks up the way with the given ID and returns the result of calling the closure on it.
+    /// Returns `Ok(None)` if there is no such way. See [`get_node`](IndexedReader::get_node) for
+    /// details.
+    pub fn get_way<F, T>(&mut self, id: i64, f: F) -> Result<Option<T>>
+    where
+        F: for<'a> FnOnce(&Element<'a>) -> T,
+    {
+        self.get_element(ElementType::Way, id, f)
+    }
+
+    /// Looks up the relation with the given ID and returns the result of calling the closure on
+    /// it. Returns `Ok(None)` if there is no such relation. See
+    /// [`get_node`](IndexedReader::get_node) for details.
+    pub fn get_relation<F, T>(&mut self, id: i64, f: F) -> Result<Option<T>>
+    where
+        F: for<'a> FnOnce(&Element<'a>) -> T,
+    {
+        self.get_element(ElementType::Relation, id, f)
+    }
+
+    /// Looks up all nodes with the given IDs and calls the closure on each of them. IDs that are
+    /// not in the file are ignored. Each blob is decoded at most once, so this is faster than
+    /// calling [`get_node`](IndexedReader::get_node) for each ID.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut found = 0;
+    ///
+    /// reader.get_nodes([105, 106, 999], |_| found += 1)?;
+    ///
+    /// # assert_eq!(found, 2);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn get_nodes<I, F>(&mut self, ids: I, f: F) -> Result<()>
+    where
+        I: IntoIterator<Item = i64>,
+        F: for<'a> FnMut(&Element<'a>),
+    {
+        self.get_elements(ElementType::Node, ids, f)
+    }
+
+    /// Looks up all ways with the given IDs and calls the closure on each of them. See
+    /// [`get_nodes`](IndexedReader::get_nodes) for details.
+    pub fn get_ways<I, F>(&mut self, ids: I, f: F) -> Result<()>
+    where
+        I: IntoIterator<Item = i64>,
+        F: for<'a> FnMut(&Element<'a>),
+    {
+        self.get_elements(ElementType::Way, ids, f)
+    }
+
+    /// Looks up all relations with the given IDs and calls the closure on each of them. See
+    /// [`get_nodes`](IndexedReader::get_nodes) for details.
+    pub fn get_relations<I, F>(&mut self, ids: I, f: F) -> Result<()>
+    where
+        I: IntoIterator<Item = i64>,
+        F: for<'a> FnMut(&Element<'a>),
+    {
+        self.get_elements(ElementType::Relation, ids, f)
+    }
+
+    fn get_element<F, T>(&mut self, element_type: ElementType, id: i64, f: F) -> Result<Option<T>>
+    where
+        F: for<'a> FnOnce(&Element<'a>) -> T,
+    {
+        let mut f = Some(f);
+        let mut result = None;
+        self.get_elements(element_type, [id], |element| {
+            // Only use the first match if there are multiple versions of the element.
+            if let Some(f) = f.take() {
+                result = Some(f(element));
+            }
+        })?;
+        Ok(result)
+    }
+
+    fn get_elements<I, F>(&mut self, element_type: ElementType, ids: I, mut f: F) -> Result<()>
+    where
+        I: IntoIterator<Item = i64>,
+        F: for<'a> FnMut(&Element<'a>),
+    {
+        self.create_index()?;
+
+        let mut remaining: BTreeSet<i64> = ids.into_iter().collect();
+
+        if self.is_sorted_by_type_then_id()? {
+            let primitive_blobs: Vec<usize> = (0..self.index.len())
+                .filter(|&pos| self.index[pos].blob_type == SimpleBlobType::Primitive)
+                .collect();
+            let mut unsorted_ids = BTreeSet::new();
+
+            // IDs are visited in ascending order, so each candidate blob is only decoded once.
+            while let Some(id) = remaining.pop_first() {
+                let (pos, block) =
+                    match self.find_sorted_blob(&primitive_blobs, element_type, id)? {
+                        Candidate::Blob(pos, block) => (pos, block),
+                        Candidate::None => continue,
+                        Candidate::Unknown => {
+                            unsorted_ids.insert(id);
+                            continue;
+                        }
+                    };
+                let block = match block {
+                    Some(block) => block,
+                    None => self
+                        .reader
+                        .blob_from_offset(self.index[pos].offset)?
+                        .to_primitiveblock()?,
+                };
+
+                // Handle all remaining IDs that fall into the range of this blob.
+                let range = self.index[pos]
+                    .id_ranges
+                    .as_ref()
+                    .and_then(|ranges| ranges.get(element_type).clone())
+                    .unwrap_or(id..=id);
+                let mut ids: BTreeSet<i64> = remaining.range(range).copied().collect();
+                for id in &ids {
+                    remaining.remove(id);
+                }
+                ids.insert(id);
+
+                for_each_element_with_id(&block, element_type, &ids, &mut BTreeSet::new(), &mut f);
+            }
+
+            remaining = unsorted_ids;
+        }
+
+        // Sequential scan that skips blobs whose ID ranges do not match.
+        for info in &mut self.index {
+            if remaining.is_empty() {
+                break;
+            }
+            if info.blob_type != SimpleBlobType::Primitive
+                || info.element_range_included(element_type, &remaining) == RangeIncluded::No
+            {
+                continue;
+            }
+
+            let block = self
+                .reader
+                .blob_from_offset(info.offset)?
+                .to_primitiveblock()?;
+            Self::update_element_id_ranges(info, &block);
+
+            let mut found = BTreeSet::new();
+            for_each_element_with_id(&block, element_type, &remaining, &mut found, &mut f);
+            remaining.retain(|id| !found.contains(id));
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the header of the file declares that the elements are sorted by type,
+    /// then by ID.
+    fn is_sorted_by_type_then_id(&mut self) -> Result<bool> {
+        if let Some(sorted) = self.sorted_by_type_then_id {
+            return Ok(sorted);
+        }
+
+        let sorted = self
+            .read_header()?
+            .is_some_and(|header| header.is_sorted_by_type_then_id());
+
+        self.sorted_by_type_then_id = Some(sorted);
+        Ok(sorted)
+    }
+
+    /// Decodes the first header block of the file, if there is one.
+    pub(crate) fn read_header(&mut self) -> Result<Option<HeaderBlock>> {
+        self.create_index()?;
+        match self
+            .index
+            .iter()
+            .find(|info| info.blob_type == SimpleBlobType::Header)
+        {
+            Some(info) => Ok(Some(
+                self.reader
+                    .blob_from_offset(info.offset)?
+                    .to_headerblock()?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Binary search over the given primitive blobs (positions in the index) of a file that is
+    /// sorted by type, then by ID. Blobs with unknown ID ranges are decoded on the way.
+    fn find_sorted_blob(
+        &mut self,
+        primitive_blobs: &[usize],
+        element_type: ElementType,
+        id: i64,
+    ) -> Result<Candidate> {
+        let key = (element_type, id);
+        let mut low = 0;
+        let mut high = primitive_blobs.len();
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let info = &mut self.index[primitive_blobs[mid]];
+
+            let mut block = None;
+            if info.id_ranges.is_none() {
+                let decoded = self
+                    .reader
+                    .blob_from_offset(info.offset)?
+                    .to_primitiveblock()?;
+                Self::update_element_id_ranges(info, &decoded);
+                block = Some(decoded);
+            }
+
+            let Some((first, last)) = info.id_ranges.as_ref().and_then(IdRanges::sorted_bounds)
+            else {
+                return Ok(Candidate::Unknown);
+            };
+
+            if key < first {
+                high = mid;
+            } else if key > last {
+                low = mid + 1;
+            } else {
+                return Ok(Candidate::Blob(primitive_blobs[mid], block));
+            }
+        }
+
+        Ok(Candidate::None)
+    }
+
+    /// Checks the order by type, then by ID of the file by comparing the ID ranges of consecutive
+    /// blobs. Returns the first blob that starts before the end of the previous blob, or `None` if
+    /// no such blob exists. The reported element is the smallest element of that blob (first by
+    /// type, then by ID) and the previous element is the largest element of the previous blob.
+    ///
+    /// This check is cheap if the ID ranges are already known, e.g. after loading a saved index or
+    /// after calling another method that visits all blobs. Otherwise each blob is decoded once.
+    /// Unsorted elements inside of a single blob are not detected, use
+    /// [`ElementReader::check_sort_order`](crate::reader::ElementReader::check_sort_order) to
+    /// check every element.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// if let Some(violation) = reader.check_blob_sort_order()? {
+    ///     println!("blob at {:?} is out of order", violation.blob_offset);
+    /// }
+    /// # assert!(reader.check_blob_sort_order()?.is_none());
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn check_blob_sort_order(&mut self) -> Result<Option<SortViolation>> {
+        let allow_duplicates = self
+            .read_header()?
+            .is_some_and(|header| allows_duplicate_ids(&header));
+
+        let mut previous: Option<(ElementType, i64)> = None;
+        for info in &mut self.index {
+            if info.blob_type != SimpleBlobType::Primitive {
+                continue;
+            }
+            if info.id_ranges.is_none() {
+                let block = self
+                    .reader
+                    .blob_from_offset(info.offset)?
+                    .to_primitiveblock()?;
+                Self::update_element_id_ranges(info, &block);
+            }
+            let Some((first, last)) = info.id_ranges.as_ref().and_then(IdRanges::sorted_bounds)
+            else {
+                continue;
+            };
+
+            if let Some(previous) = previous {
+                if let Some(kind) = sort_violation_kind(previous, first, allow_duplicates) {
+                    return Ok(Some(SortViolation {
+                        kind,
+                        blob_offset: Some(info.offset),
+                        element_type: first.0,
+                        id: first.1,
+                        previous_type: previous.0,
+                        previous_id: previous.1,
+                    }));
+                }
+            }
+            previous = Some(last);
+        }
+
+        Ok(None)
+    }
+
+    /// Checks that all node references of ways and all members of relations are part of the file
+    /// and calls the closure on each [`MissingReference`].
+    ///
+    /// The IDs of all elements are stored in [`IdSet`]s, so the memory usage is bounded by one bit
+    /// per ID. The file is read in four passes over the nodes, ways, relations and again over the
+    /// relations, but blobs that certainly do not contain the element type of a pass are skipped.
+    /// The missing nodes of ways are reported before the missing members of relations.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure or decoding the role of
+    /// a missing member.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut missing = vec![];
+    ///
+    /// reader.for_each_missing_reference(|reference| missing.push(reference))?;
+    ///
+    /// for reference in &missing {
+    ///     println!(
+    ///         "{:?} {} references missing {:?} {}",
+    ///         reference.referrer_type, reference.referrer_id, reference.element_type, reference.id
+    ///     );
+    /// }
+    /// # assert!(missing.is_empty());
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_missing_reference<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(MissingReference),
+    {
+        let mut node_ids = IdSet::new();
+        self.for_each_node(|element| {
+            node_ids.insert(element.id());
+        })?;
+
+        // All nodes are known, so the node references can be checked while collecting way IDs.
+        let mut way_ids = IdSet::new();
+        self.for_each_way(|element| {
+            if let Element::Way(way) = element {
+                way_ids.insert(way.id());
+                for node_id in way.refs() {
+                    if !node_ids.contains(node_id) {
+                        f(MissingReference {
+                            element_type: ElementType::Node,
+                            id: node_id,
+                            referrer_type: ElementType::Way,
+                            referrer_id: way.id(),
+                            role: None,
+                        });
+                    }
+                }
+            }
+        })?;
+
+        // Relations may reference relations that come later in the file.
+        let mut relation_ids = IdSet::new();
+        self.for_each_relation(|element| {
+            relation_ids.insert(element.id());
+        })?;
+
+        let mut result = Ok(());
+        self.for_each_relation(|element| {
+            if result.is_err() {
+                return;
+            }
+            if let Element::Relation(relation) = element {
+                for member in relation.members() {
+                    let ids = match member.member_type {
+                        RelMemberType::Node => &node_ids,
+                        RelMemberType::Way => &way_ids,
+                        RelMemberType::Relation => &relation_ids,
+                    };
+                    if ids.contains(member.member_id) {
+                        continue;
+                    }
+                    match member.role() {
+                        Ok(role) => f(MissingReference {
+                            element_type: ElementType::from(&member.member_type),
+                            id: member.member_id,
+                            referrer_type: ElementType::Relation,
+                            referrer_id: relation.id(),
+                            role: Some(role.to_string()),
+                        }),
+                        Err(e) => {
+                            result = Err(e);
+                            return;
+                        }
+                    }
+                }
+            }
+        })?;
+        result
+    }
+
+    /// Decodes the PBF structure sequentially and calls the given closure on each node.
+    /// This method also creates a lightweight in-memory index that speeds up future invocations of
+    /// this or any other method of `IndexedReader`.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut nodes = 0;
+    ///
+    /// reader.for_each_node(
+    ///     |element| {
+    ///         match element {
+    ///             Element::Node(node) => nodes += 1,
+    ///             Element::DenseNode(dense_node) => nodes += 1,
+    ///             _ => {}
+    ///         }
+    ///     },
+    /// )?;
+    ///
+    /// println!("nodes: {nodes}");
+    ///
+    /// # assert_eq!(nodes, 3);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_node<F>(&mut self, f: F) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        self.for_each_element_of_type(ElementType::Node, f)
+    }
+
+    /// Decodes the PBF structure sequentially and calls the given closure on each way.
+    /// Just like [`for_each_node`](IndexedReader::for_each_node), blobs that certainly contain
+    /// no ways are skipped.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure.
+    pub fn for_each_way<F>(&mut self, f: F) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        self.for_each_element_of_type(ElementType::Way, f)
+    }
+
+    /// Decodes the PBF structure sequentially and calls the given closure on each relation.
+    /// Just like [`for_each_node`](IndexedReader::for_each_node), blobs that certainly contain
+    /// no relations are skipped.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure.
+    pub fn for_each_relation<F>(&mut self, f: F) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        self.for_each_element_of_type(ElementType::Relation, f)
+    }
+
+    /// Calls the closure on each way together with the locations of its nodes.
+    ///
+    /// In a first pass, the locations of all nodes are written to the given store. In a second
+    /// pass, the locations of the nodes of each way are looked up. A location is `None` if the
+    /// node is missing in the file. If the file already includes the node locations of ways
+    /// (see [`Way::node_locations`]), these are used instead of the store.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure or storing a location.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut store = SparseLocationStore::new();
+    ///
+    /// reader.for_each_way_with_locations(&mut store, |way, locations| {
+    ///     let lats: Vec<f64> = locations.iter().flatten().map(|loc| loc.lat()).collect();
+    ///     println!("way {}: {lats:?}", way.id());
+    /// #   assert_eq!(lats.len(), 4);
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_way_with_locations<S, F>(&mut self, store: &mut S, mut f: F) -> Result<()>
+    where
+        S: NodeLocationStore + ?Sized,
+        F: for<'a> FnMut(&Way<'a>, &[Option<WayNodeLocation>]),
+    {
+        // First pass: Store node locations
+        let mut result = Ok(());
+        self.for_each_node(|element| {
+            if result.is_err() {
+                return;
+            }
+            result = match element {
+                Element::Node(node) => store.set(
+                    node.id(),
+                    WayNodeLocation::new(node.nano_lat(), node.nano_lon()),
+                ),
+                Element::DenseNode(node) => store.set(
+                    node.id(),
+                    WayNodeLocation::new(node.nano_lat(), node.nano_lon()),
+                ),
+                _ => Ok(()),
+            };
+        })?;
+        result?;
+        store.finish()?;
+
+        // Second pass: Look up the node locations of ways
+        let mut locations = vec![];
+        self.for_each_way(|element| {
+            if let Element::Way(way) = element {
+                locations.clear();
+                let node_locations = way.node_locations();
+                if node_locations.len() > 0 {
+                    locations.extend(node_locations.map(Some));
+                } else {
+                    locations.extend(way.refs().map(|id| store.get(id)));
+                }
+                f(&way, &locations);
+            }
+        })
+    }
+
+    /// Assembles areas from closed ways and multipolygon or boundary relations and calls the
+    /// closure on each area. See the [`area`](crate::area) module for details.
+    ///
+    /// The locations of all nodes are written to the given store first. Areas of closed ways
+    /// are returned while reading the ways, areas of relations afterwards. Broken areas are
+    /// also returned, see [`Area::problems`].
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure or storing a location.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// let mut areas = 0;
+    ///
+    /// reader.for_each_area(&mut SparseLocationStore::new(), |area| {
+    ///     if area.is_valid() {
+    ///         areas += 1;
+    ///     } else {
+    ///         println!("broken area {:?}: {:?}", area.source, area.problems);
+    ///     }
+    /// })?;
+    ///
+    /// # assert_eq!(areas, 1);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_area<S, F>(&mut self, store: &mut S, f: F) -> Result<()>
+    where
+        S: NodeLocationStore + ?Sized,
+        F: FnMut(Area),
+    {
+        self.for_each_way_and_area(store, |_, _| {}, f)
+    }
+
+    /// Just like [`for_each_area`](IndexedReader::for_each_area), but also calls `way_f` on each
+    /// way with its node locations, like
+    /// [`for_each_way_with_locations`](IndexedReader::for_each_way_with_locations) does.
+    pub(crate) fn for_each_way_and_area<S, G, F>(
+        &mut self,
+        store: &mut S,
+        mut way_f: G,
+        mut f: F,
+    ) -> Result<()>
+    where
+        S: NodeLocationStore + ?Sized,
+        G: for<'a> FnMut(&Way<'a>, &[Option<WayNodeLocation>]),
+        F: FnMut(Area),
+    {
+        let owned_tags = |tags: TagIter| -> Vec<(String, String)> {
+            tags.map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+
+        // First pass: Relations and their member ways
+        let mut relations = vec![];
+        let mut member_way_ids: HashSet<i64> = HashSet::new();
+        let mut result = Ok(());
+        self.for_each_relation(|element| {
+            if let Element::Relation(relation) = element {
+                if !is_area_relation(relation.tags()) {
+                    return;
+                }
+                let mut members = vec![];
+                for member in relation.members() {
+                    if member.member_type != RelMemberType::Way {
+                        continue;
+                    }
+                    match member.role() {
+                        Ok(role) => {
+                            if is_area_role(role) {
+                                member_way_ids.insert(member.member_id);
+                            }
+                            members.push((member.member_id, role.to_string()));
+                        }
+                        Err(e) => {
+                            result = Err(e);
+                            return;
+                        }
+                    }
+                }
+                relations.push((relation.id(), owned_tags(relation.tags()), members));
+            }
+        })?;
+        result?;
+
+        // Second pass: Node locations, third pass: Closed ways and member ways
+        let mut member_ways: HashMap<i64, AreaWay> = HashMap::new();
+        self.for_each_way_with_locations(store, |way, locations| {
+            way_f(way, locations);
+            // Skip the copies for ways that can neither be an area nor a member of one
+            let is_member = member_way_ids.contains(&way.id());
+            let is_closed = way.refs().len() >= 2 && way.refs().next() == way.refs().last();
+            if !is_member && !is_closed {
+                return;
+            }
+
+            let refs: Vec<i64> = way.refs().collect();
+            let tags = owned_tags(way.tags());
+            let area_way = AreaWay {
+                id: way.id(),
+                refs,
+                locations: locations.to_vec(),
+            };
+            if is_closed_way_area(&area_way.refs, &tags) {
+                f(assemble_way(&area_way, tags));
+            }
+            if is_member {
+                member_ways.insert(way.id(), area_way);
+            }
+        })?;
+
+        for (id, tags, members) in relations {
+            let members: Vec<(i64, &str, Option<&AreaWay>)> = members
+                .iter()
+                .map(|(way_id, role)| (*way_id, role.as_str(), member_ways.get(way_id)))
+                .collect();
+            f(assemble_relation(id, tags, &members));
+        }
+
+        Ok(())
+    }
+
+    fn for_each_element_of_type<F>(&mut self, element_type: ElementType, mut f: F) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        self.create_index()?;
+
+        for info in &mut self.index {
+            let available = match element_type {
+                ElementType::Node => info.nodes_available(),
+                ElementType::Way => info.ways_available(),
+                ElementType::Relation => info.relations_available(),
+            };
+            // Skip header blobs and blobs where there are certainly no such elements available.
+            if info.blob_type == SimpleBlobType::Primitive && available != ElementsAvailable::No {
+                let block = self
+                    .reader
+                    .blob_from_offset(info.offset)?
+                    .to_primitiveblock()?;
+                Self::update_element_id_ranges(info, &block);
+
+                for group in block.groups() {
+                    match element_type {
+                        ElementType::Node => {
+                            for node in group.nodes() {
+                                f(Element::Node(node));
+                            }
+                            for dense_node in group.dense_nodes() {
+                                f(Element::DenseNode(dense_node));
+                            }
+                        }
+                        ElementType::Way => {
+                            for way in group.ways() {
+                                f(Element::Way(way));
+                            }
+                        }
+                        ElementType::Relation => {
+                            for relation in group.relations() {
+                                f(Element::Relation(relation));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IndexedReader<File> {
+    /// Creates a new `IndexedReader` from a given path.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        //TODO take some more measurements to determine if `BufReader` should be used here
+        let f = File::open(path)?;
+        Self::new(f)
+    }
+
+    /// Creates a new `IndexedReader` from a given path and loads a previously saved index (see
+    /// [`save_index`](IndexedReader::save_index)), so that the PBF file does not need to be
+    /// scanned again.
+    ///
+    /// # Errors
+    /// Returns an [`ErrorKind::InvalidIndex`] error if the index is malformed or was created for
+    /// a different version of the PBF file (the file size or modification time changed). In this
+    /// case the index can be recreated with [`from_path`](IndexedReader::from_path) and
+    /// [`save_index`](IndexedReader::save_index).
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// # let index_path = std::env::temp_dir().join("osmpbf_doc_test.osm.pbf.idx");
+    /// # IndexedReader::from_path("tests/test.osm.pbf")?.save_index(&index_path)?;
+    /// let reader = IndexedReader::from_path_with_index("tests/test.osm.pbf", &index_path)?;
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn from_path_with_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        path: P,
+        index_path: Q,
+    ) -> Result<Self> {
+        let mut reader = Self::from_path(path)?;
+        reader.load_index(index_path)?;
+        Ok(reader)
+    }
+
+    /// Saves the index to the file at `index_path`. The saved index includes the ID ranges of all
+    /// blobs that have been decoded so far, so saving after a few queries makes later queries
+    /// with a loaded index faster.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let mut reader = IndexedReader::from_path("tests/test.osm.pbf")?;
+    /// # let index_path = std::env::temp_dir().join("osmpbf_doc_test_save.osm.pbf.idx");
+    /// reader.save_index(&index_path)?;
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn save_index<P: AsRef<Path>>(&mut self, index_path: P) -> Result<()> {
+        self.create_index()?;
+        let stamp = FileStamp::from_file(self.reader.get_ref())?;
+        let writer = BufWriter::new(File::create(index_path)?);
+        write_index(writer, stamp, &self.index)
+    }
+
+    /// Replaces the current index with the index saved at `index_path`.
+    ///
+    /// # Errors
+    /// Returns an [`ErrorKind::InvalidIndex`] error if the index is malformed or was created for
+    /// a different version of the PBF file. The current index is left unchanged in this case.
+    pub fn load_index<P: AsRef<Path>>(&mut self, index_path: P) -> Result<()> {
+        let stamp = FileStamp::from_file(self.reader.get_ref())?;
+        let reader = BufReader::new(File::open(index_path)?);
+        self.index = read_index(reader, stamp)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_bounds() {
+        let ranges = IdRanges {
+            node_ids: Some(5..=10),
+            way_ids: None,
+            relation_ids: Some(1..=3),
+        };
+        assert_eq!(
+            ranges.sorted_bounds(),
+            Some(((ElementType::Node, 5), (ElementType::Relation, 3)))
+        );
+
+        let ranges = IdRanges {
+            node_ids: None,
+            way_ids: Some(7..=8),
+            relation_ids: None,
+        };
+        assert_eq!(
+            ranges.sorted_bounds(),
+            Some(((ElementType::Way, 7), (ElementType::Way, 8)))
+        );
+
+        let ranges = IdRanges {
+            node_ids: None,
+            way_ids: None,
+            relation_ids: None,
+        };
+        assert_eq!(ranges.sorted_bounds(), None);
+    }
+
+    #[test]
+    fn test_index_roundtrip() {
+        let stamp = FileStamp {
+            size: 1000,
+            mtime_secs: 1_600_000_000,
+            mtime_nanos: 123,
+        };
+        let index = vec![
+            BlobInfo {
+                offset: ByteOffset(0),
+                blob_type: SimpleBlobType::Header,
+                id_ranges: None,
+            },
+            BlobInfo {
+                offset: ByteOffset(100),
+                blob_type: SimpleBlobType::Primitive,
+                id_ranges: Some(IdRanges {
+                    node_ids: Some(-5..=7),
+                    way_ids: None,
+                    relation_ids: Some(3..=3),
+                }),
+            },
+            BlobInfo {
+                offset: ByteOffset(500),
+                blob_type: SimpleBlobType::Unknown,
+                id_ranges: None,
+            },
+        ];
+
+        let mut buf = vec![];
+        write_index(&mut buf, stamp, &index).unwrap();
+
+        let loaded = read_index(buf.as_slice(), stamp).unwrap();
+        assert_eq!(loaded.len(), index.len());
+        for (a, b) in loaded.iter().zip(index.iter()) {
+            assert_eq!(a.offset, b.offset);
+            assert_eq!(a.blob_type, b.blob_type);
+            assert_eq!(a.id_ranges, b.id_ranges);
+        }
+
+        let modified = FileStamp {
+            mtime_nanos: 124,
+            ..stamp
+        };
+        assert!(matches!(
+            read_index(buf.as_slice(), modified).unwrap_err().kind(),
+            ErrorKind::InvalidIndex { .. }
+        ));
+        assert!(read_index(&buf[..buf.len() - 1], stamp).is_err());
+    }
 
     #[test]
     fn test_range_included_set() {
@@ -73,23 +73,58 @@ std::compile_error!(
     for example by using these cargo flags: --no-default-features --features zlib-ng"
 );
 
+pub use area::*;
+#[cfg(feature = "async")]
+pub use async_reader::*;
 pub use blob::*;
 pub use block::*;
+#[cfg(feature = "xml")]
+pub use change::*;
 pub use dense::*;
 pub use elements::*;
 pub use error::{BlobError, Error, ErrorKind, Result};
+pub use extract::*;
+pub use filter::*;
+pub use geojson::*;
+pub use history::*;
 pub use indexed::*;
+pub use location_store::*;
 pub use mmap_blob::*;
+pub use opl::*;
+pub use owned::*;
 pub use reader::*;
-
+pub use validate::*;
+pub use writer::*;
+#[cfg(feature = "xml")]
+pub use xml::*;
+
+pub mod area;
+#[cfg(feature = "async")]
+pub mod async_reader;
 pub mod blob;
 pub mod block;
+#[cfg(feature = "xml")]
+pub mod change;
 pub mod dense;
 pub mod elements;
 mod error;
+pub mod extract;
+pub mod filter;
+pub mod geojson;
+pub mod history;
 pub mod indexed;
+pub mod location_store;
 pub mod mmap_blob;
+pub mod opl;
+pub mod owned;
 pub mod reader;
+#[cfg(feature = "serde")]
+mod serialize;
+mod timestamp;
+pub mod validate;
+pub mod writer;
+#[cfg(feature = "xml")]
+pub mod xml;
 
 mod proto {
     include!(concat!(env!("OUT_DIR"), "/mod.rs"));
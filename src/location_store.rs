@@ -0,0 +1,349 @@
+//! Store node locations to assemble way geometries
+//!
+//! Ways only reference their nodes by ID, unless the file was written with the `LocationsOnWays`
+//! feature (see [`Way::node_locations`](crate::elements::Way::node_locations)). To get the
+//! coordinates of a way, the locations of all nodes are first stored in a [`NodeLocationStore`]
+//! and then looked up for each way, see
+//! [`IndexedReader::for_each_way_with_locations`](crate::indexed::IndexedReader::for_each_way_with_locations).
+//!
+//! Which store works best depends on the size of the input:
+//! * [`SparseLocationStore`] keeps a hash map and is best for small extracts.
+//! * [`SortedLocationStore`] keeps a sorted vector, which takes less memory than a hash map if
+//!   the input is sorted by ID.
+//! * [`DenseLocationStore`] keeps an array indexed by node ID, which is fast and compact if the
+//!   node IDs are dense, e.g. for a planet file.
+//! * [`MmapLocationStore`] is like [`DenseLocationStore`], but backed by a memory mapped file,
+//!   so the operating system can page it out if the memory is not sufficient.
+
+use crate::elements::WayNodeLocation;
+use crate::error::{new_error, ErrorKind, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+/// A map from node IDs to node locations.
+pub trait NodeLocationStore {
+    /// Stores the location of a node.
+    ///
+    /// # Errors
+    /// Returns an error if the store cannot hold the node ID or location, or if an I/O error
+    /// occurred.
+    fn set(&mut self, id: i64, location: WayNodeLocation) -> Result<()>;
+
+    /// Is called after all locations are stored and before the first call to
+    /// [`get`](NodeLocationStore::get). It is called again if more locations are stored
+    /// afterwards. The default implementation does nothing.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the location of a node or `None` if it was not stored.
+    fn get(&self, id: i64) -> Option<WayNodeLocation>;
+}
+
+/// Stores node locations in a hash map.
+#[derive(Clone, Debug, Default)]
+pub struct SparseLocationStore {
+    locations: HashMap<i64, WayNodeLocation>,
+}
+
+impl SparseLocationStore {
+    /// Creates a new empty `SparseLocationStore`.
+    pub fn new() -> SparseLocationStore {
+        SparseLocationStore::default()
+    }
+
+    /// Returns the number of stored locations.
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Returns true if no locations are stored.
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
+
+impl NodeLocationStore for SparseLocationStore {
+    fn set(&mut self, id: i64, location: WayNodeLocation) -> Result<()> {
+        self.locations.insert(id, location);
+        Ok(())
+    }
+
+    fn get(&self, id: i64) -> Option<WayNodeLocation> {
+        self.locations.get(&id).copied()
+    }
+}
+
+/// Stores node locations in a vector that is sorted by ID. Locations are looked up with a binary
+/// search.
+///
+/// Appending is cheap; the vector is only sorted in [`finish`](NodeLocationStore::finish) if the
+/// IDs were not stored in ascending order. If a node ID is stored more than once, the location
+/// that was stored last is kept.
+///
+/// If locations were stored out of order after the last call of
+/// [`finish`](NodeLocationStore::finish), [`get`](NodeLocationStore::get) falls back to a slow
+/// linear search.
+#[derive(Clone, Debug)]
+pub struct SortedLocationStore {
+    locations: Vec<(i64, WayNodeLocation)>,
+    sorted: bool,
+}
+
+impl SortedLocationStore {
+    /// Creates a new empty `SortedLocationStore`.
+    pub fn new() -> SortedLocationStore {
+        SortedLocationStore {
+            locations: vec![],
+            sorted: true,
+        }
+    }
+
+    /// Returns the number of stored locations.
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Returns true if no locations are stored.
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
+
+impl Default for SortedLocationStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeLocationStore for SortedLocationStore {
+    fn set(&mut self, id: i64, location: WayNodeLocation) -> Result<()> {
+        if let Some(&(last_id, _)) = self.locations.last() {
+            self.sorted &= last_id < id;
+        }
+        self.locations.push((id, location));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if !self.sorted {
+            // The sort is stable, so the last location of each ID is the last one of its run.
+            self.locations.sort_by_key(|&(id, _)| id);
+            let mut deduped: Vec<(i64, WayNodeLocation)> = Vec::with_capacity(self.locations.len());
+            for entry in self.locations.drain(..) {
+                match deduped.last_mut() {
+                    Some(last) if last.0 == entry.0 => *last = entry,
+                    _ => deduped.push(entry),
+                }
+            }
+            self.locations = deduped;
+            self.sorted = true;
+        }
+        Ok(())
+    }
+
+    /// Returns the location of a node or `None` if it was not stored.
+    ///
+    /// This is a linear search if locations were stored out of order and
+    /// [`finish`](NodeLocationStore::finish) was not called afterwards.
+    fn get(&self, id: i64) -> Option<WayNodeLocation> {
+        if !self.sorted {
+            // The location that was stored last wins, just like after sorting.
+            return self
+                .locations
+                .iter()
+                .rev()
+                .find(|&&(stored_id, _)| stored_id == id)
+                .map(|&(_, location)| location);
+        }
+        self.locations
+            .binary_search_by_key(&id, |&(id, _)| id)
+            .ok()
+            .map(|index| self.locations[index].1)
+    }
+}
+
+/// Encodes a location in 64 bits with a precision of 100 nanodegrees. Zero is reserved for
+/// missing locations, so that zero-initialized memory contains no locations.
+fn encode_location(id: i64, location: WayNodeLocation) -> Result<u64> {
+    let lat = i64::from(location.decimicro_lat()) + 900_000_001;
+    let lon = i64::from(location.decimicro_lon()) + 1_800_000_001;
+    if !(1..=1_800_000_001).contains(&lat) || !(1..=3_600_000_001).contains(&lon) {
+        return Err(new_error(ErrorKind::InvalidNodeLocation { id }));
+    }
+    Ok(((lat as u64) << 32) | lon as u64)
+}
+
+fn decode_location(value: u64) -> Option<WayNodeLocation> {
+    if value == 0 {
+        return None;
+    }
+    let lat = (value >> 32) as i64 - 900_000_001;
+    let lon = (value & 0xFFFF_FFFF) as i64 - 1_800_000_001;
+    Some(WayNodeLocation::new(lat * 100, lon * 100))
+}
+
+/// Returns the ID as an array index or an error for negative IDs.
+fn dense_index(id: i64) -> Result<usize> {
+    usize::try_from(id).map_err(|_| new_error(ErrorKind::InvalidNodeLocation { id }))
+}
+
+/// Stores node locations in an array that is indexed by node ID. Each entry takes 8 bytes, so
+/// the memory usage is proportional to the largest node ID.
+///
+/// Coordinates are stored with a precision of 100 nanodegrees, which is the default granularity
+/// of PBF files. Negative node IDs are not supported.
+#[derive(Clone, Debug, Default)]
+pub struct DenseLocationStore {
+    locations: Vec<u64>,
+}
+
+impl DenseLocationStore {
+    /// Creates a new empty `DenseLocationStore`.
+    pub fn new() -> DenseLocationStore {
+        DenseLocationStore::default()
+    }
+
+    /// Creates a new empty `DenseLocationStore` that can hold node IDs up to `max_id` without
+    /// reallocating.
+    pub fn with_max_id(max_id: usize) -> DenseLocationStore {
+        DenseLocationStore {
+            locations: vec![0; max_id + 1],
+        }
+    }
+}
+
+impl NodeLocationStore for DenseLocationStore {
+    fn set(&mut self, id: i64, location: WayNodeLocation) -> Result<()> {
+        let index = dense_index(id)?;
+        let value = encode_location(id, location)?;
+        if index >= self.locations.len() {
+            let len = (index + 1).max(self.locations.len() * 2);
+            self.locations.resize(len, 0);
+        }
+        self.locations[index] = value;
+        Ok(())
+    }
+
+    fn get(&self, id: i64) -> Option<WayNodeLocation> {
+        let index = usize::try_from(id).ok()?;
+        self.locations.get(index).copied().and_then(decode_location)
+    }
+}
+
+/// Stores node locations in a memory mapped file that is indexed by node ID, just like
+/// [`DenseLocationStore`].
+///
+/// The file grows as needed and is not deleted when the store is dropped.
+#[derive(Debug)]
+pub struct MmapLocationStore {
+    file: File,
+    mmap: Option<memmap2::MmapMut>,
+    /// Number of entries that fit into the file.
+    capacity: usize,
+}
+
+impl MmapLocationStore {
+    /// Creates (or truncates) the file at the given path and uses it as a location store.
+    ///
+    /// # Safety
+    /// The file should not be modified by other processes while the store is in use.
+    /// See [memmap-rs issue 25](https://github.com/danburkert/memmap-rs/issues/25) for more
+    /// information on the safety of memory maps.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let path = std::env::temp_dir().join("osmpbf-doc-locations.bin");
+    /// let mut store = unsafe { MmapLocationStore::create(&path)? };
+    /// store.set(42, WayNodeLocation::new(52_000_000_000, 13_000_000_000))?;
+    /// store.finish()?;
+    ///
+    /// assert_eq!(store.get(42).unwrap().lat(), 52.0);
+    /// # drop(store);
+    /// # std::fs::remove_file(path)?;
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub unsafe fn create<P: AsRef<Path>>(path: P) -> Result<MmapLocationStore> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(MmapLocationStore {
+            file,
+            mmap: None,
+            capacity: 0,
+        })
+    }
+
+    fn grow(&mut self, min_capacity: usize) -> Result<()> {
+        let capacity = min_capacity.max(self.capacity * 2).max(1 << 16);
+        // Unmap before resizing the file.
+        self.mmap = None;
+        self.file.set_len(capacity as u64 * 8)?;
+        // SAFETY: The caller of `create` guarantees that the file is not modified elsewhere.
+        self.mmap = Some(unsafe { memmap2::MmapMut::map_mut(&self.file)? });
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    fn entry(&self, index: usize) -> Option<&[u8]> {
+        let mmap = self.mmap.as_ref()?;
+        mmap.get(index * 8..index * 8 + 8)
+    }
+}
+
+impl NodeLocationStore for MmapLocationStore {
+    fn set(&mut self, id: i64, location: WayNodeLocation) -> Result<()> {
+        let index = dense_index(id)?;
+        let value = encode_location(id, location)?;
+        if index >= self.capacity {
+            self.grow(index + 1)?;
+        }
+        let mmap = self.mmap.as_mut().expect("memory map exists after growing");
+        mmap[index * 8..index * 8 + 8].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(mmap) = &self.mmap {
+            mmap.flush_async()?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: i64) -> Option<WayNodeLocation> {
+        let index = usize::try_from(id).ok()?;
+        let bytes = self.entry(index)?;
+        decode_location(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_encoding() {
+        for (lat, lon) in [
+            (0, 0),
+            (90_000_000_000, 180_000_000_000),
+            (-90_000_000_000, -180_000_000_000),
+            (52_122_403_100, -11_628_401_700),
+        ] {
+            let location = WayNodeLocation::new(lat, lon);
+            let value = encode_location(1, location).unwrap();
+            assert_ne!(value, 0);
+            assert_eq!(decode_location(value), Some(location));
+        }
+        assert!(encode_location(1, WayNodeLocation::new(91_000_000_000, 0)).is_err());
+        assert_eq!(decode_location(0), None);
+    }
+}
@@ -1,11 +1,10 @@
 //! Iterate over blobs from a memory map
 
 use self::fileformat::BlobHeader;
-use crate::blob::{decode_blob, BlobDecode, BlobType, ByteOffset};
+use crate::blob::{check_header_size, decode_blob, BlobDecode, BlobType, ByteOffset};
 use crate::block::{HeaderBlock, PrimitiveBlock};
 use crate::error::{new_blob_error, new_protobuf_error, BlobError, Result};
 use crate::proto::{fileformat, osmformat};
-use crate::MAX_BLOB_HEADER_SIZE;
 use byteorder::ByteOrder;
 use protobuf::Message;
 use std::fs::File;
@@ -195,11 +194,9 @@ impl<'a> Iterator for MmapBlobReader<'a> {
 
         let header_size = byteorder::BigEndian::read_u32(slice) as usize;
 
-        if header_size as u64 >= MAX_BLOB_HEADER_SIZE {
+        if let Err(err) = check_header_size(header_size as u64) {
             self.last_blob_ok = false;
-            return Some(Err(new_blob_error(BlobError::HeaderTooBig {
-                size: header_size as u64,
-            })));
+            return Some(Err(err));
         }
 
         if slice.len() < 4 + header_size {
@@ -0,0 +1,526 @@
+//! Read and write the OPL text format
+//!
+//! OPL ("Object Per Line") is the line-based text format of
+//! [osmium](https://osmcode.org/opl-file-format/). Each line describes one element, e.g.
+//!
+//! ```text
+//! n105 v1 dV c0 t2003-04-05T06:07:08Z i17 utestuser T x11.6284017 y52.1224031
+//! w107 v1 dV c0 t2003-04-05T06:07:11Z i17 utestuser Tbuilding=yes,name=triangle Nn105,n106,n108,n105
+//! r120 v1 dV c0 t2003-04-05T06:07:12Z i17 utestuser Trel_key=rel_value Mw107@test_role
+//! ```
+//!
+//! The first field is the element type and ID, all other fields start with a single character
+//! that names the field. Spaces, commas, `=`, `@`, `%` and some other characters in strings are
+//! escaped as `%` followed by the hexadecimal code point and another `%`, e.g. `%20%` for a space.
+//! This makes the format well suited for diffs and for small hand-written test fixtures.
+
+use crate::elements::{Element, RelMemberType, WayNodeLocation};
+use crate::error::{new_error, ErrorKind, Result};
+use crate::owned::{OwnedElement, OwnedInfo, OwnedNode, OwnedRelMember, OwnedRelation, OwnedWay};
+use crate::timestamp::{format_timestamp, parse_timestamp};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Returns true if the character does not need to be escaped. These are the same characters that
+/// osmium writes unescaped.
+fn is_plain_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{21}'..='\u{24}'
+            | '\u{26}'..='\u{2b}'
+            | '\u{2d}'..='\u{3c}'
+            | '\u{3e}'..='\u{3f}'
+            | '\u{41}'..='\u{7e}'
+            | '\u{a1}'..='\u{ac}'
+            | '\u{ae}'..='\u{5ff}'
+    )
+}
+
+fn push_escaped(out: &mut String, value: &str) {
+    for c in value.chars() {
+        if is_plain_char(c) {
+            out.push(c);
+        } else {
+            let _ = write!(out, "%{:x}%", c as u32);
+        }
+    }
+}
+
+fn unescape(value: &str) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('%')
+            .ok_or_else(|| format!("unterminated escape sequence in {value:?}"))?;
+        let c = u32::from_str_radix(&after[..end], 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid escape sequence in {value:?}"))?;
+        out.push(c);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Formats a coordinate in nanodegrees with (at most) seven decimal places like osmium.
+pub(crate) fn push_coordinate(out: &mut String, nano: i64) {
+    let rounded = (nano.abs() + 50) / 100;
+    if nano < 0 && rounded != 0 {
+        out.push('-');
+    }
+    let _ = write!(out, "{}", rounded / 10_000_000);
+    let fraction = rounded % 10_000_000;
+    if fraction != 0 {
+        let digits = format!("{fraction:07}");
+        out.push('.');
+        out.push_str(digits.trim_end_matches('0'));
+    }
+}
+
+fn push_info(out: &mut String, info: &OwnedInfo) {
+    if let Some(version) = info.version {
+        let _ = write!(out, " v{version}");
+    }
+    if let Some(visible) = info.visible {
+        out.push_str(if visible { " dV" } else { " dD" });
+    }
+    if let Some(changeset) = info.changeset {
+        let _ = write!(out, " c{changeset}");
+    }
+    if let Some(milli_timestamp) = info.milli_timestamp {
+        out.push_str(" t");
+        out.push_str(&format_timestamp(milli_timestamp));
+    }
+    if let Some(uid) = info.uid {
+        let _ = write!(out, " i{uid}");
+    }
+    if let Some(user) = &info.user {
+        out.push_str(" u");
+        push_escaped(out, user);
+    }
+}
+
+fn push_tags(out: &mut String, tags: &[(String, String)]) {
+    out.push_str(" T");
+    for (i, (key, value)) in tags.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_escaped(out, key);
+        out.push('=');
+        push_escaped(out, value);
+    }
+}
+
+/// Renders an owned element as one line of OPL, without the line break.
+///
+/// Metadata fields are only written if they are set. Node locations of ways (see
+/// [`OwnedWay::node_locations`]) are appended to the node references.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// let way = OwnedWay {
+///     id: 7,
+///     refs: vec![1, 2],
+///     tags: vec![("name".to_string(), "Main Street".to_string())],
+///     ..Default::default()
+/// };
+/// assert_eq!(to_opl(&way.into()), "w7 Tname=Main%20%Street Nn1,n2");
+/// ```
+pub fn to_opl(element: &OwnedElement) -> String {
+    let mut out = String::new();
+    match element {
+        OwnedElement::Node(node) => {
+            let _ = write!(out, "n{}", node.id);
+            push_info(&mut out, &node.info);
+            push_tags(&mut out, &node.tags);
+            out.push_str(" x");
+            push_coordinate(&mut out, node.nano_lon);
+            out.push_str(" y");
+            push_coordinate(&mut out, node.nano_lat);
+        }
+        OwnedElement::Way(way) => {
+            let _ = write!(out, "w{}", way.id);
+            push_info(&mut out, &way.info);
+            push_tags(&mut out, &way.tags);
+            out.push_str(" N");
+            let has_locations = way.node_locations.len() == way.refs.len();
+            for (i, node_id) in way.refs.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, "n{node_id}");
+                if has_locations {
+                    out.push('x');
+                    push_coordinate(&mut out, way.node_locations[i].nano_lon());
+                    out.push('y');
+                    push_coordinate(&mut out, way.node_locations[i].nano_lat());
+                }
+            }
+        }
+        OwnedElement::Relation(relation) => {
+            let _ = write!(out, "r{}", relation.id);
+            push_info(&mut out, &relation.info);
+            push_tags(&mut out, &relation.tags);
+            out.push_str(" M");
+            for (i, member) in relation.members.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let member_type = match member.member_type {
+                    RelMemberType::Node => 'n',
+                    RelMemberType::Way => 'w',
+                    RelMemberType::Relation => 'r',
+                };
+                let _ = write!(out, "{member_type}{}@", member.member_id);
+                push_escaped(&mut out, &member.role);
+            }
+        }
+    }
+    out
+}
+
+/// A writer that renders elements as OPL lines.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+/// let mut writer = OplWriter::new(Vec::new());
+///
+/// let mut result = Ok(());
+/// reader.for_each(|element| {
+///     if result.is_ok() {
+///         result = writer.write_element(&element);
+///     }
+/// })?;
+/// result?;
+///
+/// let text = String::from_utf8(writer.into_inner()?).unwrap();
+/// assert!(text.starts_with("n105 v1 c0 t2003-04-05T06:07:08Z i17 utestuser T x11.6284017 y52.1224031\n"));
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct OplWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> OplWriter<W> {
+    /// Creates a new `OplWriter`.
+    pub fn new(writer: W) -> OplWriter<W> {
+        OplWriter { writer }
+    }
+
+    /// Writes an element of a PBF file (including dense nodes) as one line.
+    ///
+    /// # Errors
+    /// Returns an error if the strings of the element cannot be decoded or if writing fails.
+    pub fn write_element(&mut self, element: &Element) -> Result<()> {
+        self.write_owned_element(&element.to_owned()?)
+    }
+
+    /// Writes an owned element as one line.
+    pub fn write_owned_element(&mut self, element: &OwnedElement) -> Result<()> {
+        let mut line = to_opl(element);
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer and returns it.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(field: char, value: &str) -> std::result::Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value of field '{field}': {value:?}"))
+}
+
+/// Parses a coordinate in degrees to nanodegrees. An empty value is parsed as zero.
+fn parse_coordinate(field: char, value: &str) -> std::result::Result<i64, String> {
+    if value.is_empty() {
+        return Ok(0);
+    }
+    let degrees: f64 = parse_number(field, value)?;
+    Ok((degrees * 1e9).round() as i64)
+}
+
+fn parse_tags(value: &str) -> std::result::Result<Vec<(String, String)>, String> {
+    if value.is_empty() {
+        return Ok(vec![]);
+    }
+    value
+        .split(',')
+        .map(|tag| {
+            let (key, value) = tag
+                .split_once('=')
+                .ok_or_else(|| format!("tag without '=': {tag:?}"))?;
+            Ok((unescape(key)?, unescape(value)?))
+        })
+        .collect()
+}
+
+/// Parses a node reference like `n105` or `n105x11.6284017y52.1224031`.
+fn parse_node_ref(value: &str) -> std::result::Result<(i64, Option<WayNodeLocation>), String> {
+    let value = value
+        .strip_prefix('n')
+        .ok_or_else(|| format!("invalid node reference: {value:?}"))?;
+    match value.split_once('x') {
+        Some((id, location)) => {
+            let (lon, lat) = location
+                .split_once('y')
+                .ok_or_else(|| format!("invalid node location: {location:?}"))?;
+            let location =
+                WayNodeLocation::new(parse_coordinate('y', lat)?, parse_coordinate('x', lon)?);
+            Ok((parse_number('N', id)?, Some(location)))
+        }
+        None => Ok((parse_number('N', value)?, None)),
+    }
+}
+
+fn parse_member(value: &str) -> std::result::Result<OwnedRelMember, String> {
+    let (member, role) = value
+        .split_once('@')
+        .ok_or_else(|| format!("member without '@': {value:?}"))?;
+    let member_type = match member.chars().next() {
+        Some('n') => RelMemberType::Node,
+        Some('w') => RelMemberType::Way,
+        Some('r') => RelMemberType::Relation,
+        _ => return Err(format!("invalid member type: {member:?}")),
+    };
+    Ok(OwnedRelMember {
+        member_type,
+        member_id: parse_number('M', &member[1..])?,
+        role: unescape(role)?,
+    })
+}
+
+fn parse_line(line: &str) -> std::result::Result<OwnedElement, String> {
+    let mut fields = line.split(' ').filter(|field| !field.is_empty());
+    let first = fields.next().ok_or("empty line")?;
+    let element_type = first.chars().next().unwrap_or_default();
+    if !matches!(element_type, 'n' | 'w' | 'r') {
+        return Err(format!("invalid element type: {element_type:?}"));
+    }
+    let id = parse_number(element_type, &first[1..])?;
+
+    let mut info = OwnedInfo::default();
+    let mut tags = vec![];
+    let (mut nano_lat, mut nano_lon) = (0, 0);
+    let mut refs = vec![];
+    let mut node_locations = vec![];
+    let mut members = vec![];
+    for field in fields {
+        let key = field.chars().next().unwrap_or_default();
+        let value = &field[key.len_utf8()..];
+        match key {
+            'v' => info.version = Some(parse_number(key, value)?),
+            'd' => {
+                info.visible = Some(match value {
+                    "V" => true,
+                    "D" => false,
+                    _ => return Err(format!("invalid visibility: {value:?}")),
+                })
+            }
+            'c' => info.changeset = Some(parse_number(key, value)?),
+            't' if value.is_empty() => {}
+            't' => {
+                info.milli_timestamp = Some(
+                    parse_timestamp(value)
+                        .ok_or_else(|| format!("invalid timestamp: {value:?}"))?,
+                )
+            }
+            'i' => info.uid = Some(parse_number(key, value)?),
+            'u' => info.user = Some(unescape(value)?),
+            'T' => tags = parse_tags(value)?,
+            'x' if element_type == 'n' => nano_lon = parse_coordinate(key, value)?,
+            'y' if element_type == 'n' => nano_lat = parse_coordinate(key, value)?,
+            'N' if element_type == 'w' && !value.is_empty() => {
+                for node_ref in value.split(',') {
+                    let (node_id, location) = parse_node_ref(node_ref)?;
+                    refs.push(node_id);
+                    node_locations.extend(location);
+                }
+            }
+            'M' if element_type == 'r' && !value.is_empty() => {
+                members = value
+                    .split(',')
+                    .map(parse_member)
+                    .collect::<std::result::Result<_, _>>()?;
+            }
+            'N' | 'M' => {}
+            _ => return Err(format!("unexpected field: {key:?}")),
+        }
+    }
+
+    Ok(match element_type {
+        'n' => OwnedElement::Node(OwnedNode {
+            id,
+            nano_lat,
+            nano_lon,
+            tags,
+            info,
+        }),
+        'w' => {
+            if node_locations.len() != refs.len() {
+                node_locations.clear();
+            }
+            OwnedElement::Way(OwnedWay {
+                id,
+                refs,
+                node_locations,
+                tags,
+                info,
+            })
+        }
+        _ => OwnedElement::Relation(OwnedRelation {
+            id,
+            members,
+            tags,
+            info,
+        }),
+    })
+}
+
+/// Parses a single OPL line into an owned element. Missing fields keep their default values.
+///
+/// # Errors
+/// Returns an [`ErrorKind::InvalidOpl`] error for line 1 if the line cannot be parsed.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let element = parse_opl("n1 v2 Tamenity=bench x13.4 y52.5")?;
+/// match element {
+///     OwnedElement::Node(node) => {
+///         assert_eq!(node.nano_lat, 52_500_000_000);
+///         assert_eq!(node.info.version, Some(2));
+///     }
+///     _ => unreachable!(),
+/// }
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub fn parse_opl(line: &str) -> Result<OwnedElement> {
+    parse_line(line).map_err(|reason| new_error(ErrorKind::InvalidOpl { line: 1, reason }))
+}
+
+/// A reader for OPL files. It is an iterator over the elements in file order. Empty lines and
+/// lines that start with `#` are skipped.
+///
+/// The iteration stops after the first error.
+#[derive(Debug)]
+pub struct OplReader<R: BufRead> {
+    reader: R,
+    line_number: u64,
+    line: String,
+    finished: bool,
+}
+
+impl<R: BufRead> OplReader<R> {
+    /// Creates a new `OplReader`.
+    pub fn new(reader: R) -> OplReader<R> {
+        OplReader {
+            reader,
+            line_number: 0,
+            line: String::new(),
+            finished: false,
+        }
+    }
+
+    fn next_element(&mut self) -> Result<Option<OwnedElement>> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = self.line.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return parse_line(line).map(Some).map_err(|reason| {
+                new_error(ErrorKind::InvalidOpl {
+                    line: self.line_number,
+                    reason,
+                })
+            });
+        }
+    }
+}
+
+impl OplReader<BufReader<File>> {
+    /// Opens the OPL file at the given path.
+    ///
+    /// # Errors
+    /// Returns the same errors that `std::fs::File::open` returns.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(OplReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> Iterator for OplReader<R> {
+    type Item = Result<OwnedElement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_element().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.finished = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        let mut out = String::new();
+        push_escaped(&mut out, "a b,c=d@e%f\ng\u{2003}ü");
+        assert_eq!(out, "a%20%b%2c%c%3d%d%40%e%25%f%a%g%2003%ü");
+        assert_eq!(unescape(&out).unwrap(), "a b,c=d@e%f\ng\u{2003}ü");
+        assert!(unescape("a%20").is_err());
+        assert!(unescape("a%zz%").is_err());
+    }
+
+    #[test]
+    fn test_coordinate() {
+        for (nano, text) in [
+            (0, "0"),
+            (52_122_403_100, "52.1224031"),
+            (-11_628_401_700, "-11.6284017"),
+            (1_000_000_000, "1"),
+            (-30, "0"),
+            (-50, "-0.0000001"),
+            (180_000_000_000, "180"),
+        ] {
+            let mut out = String::new();
+            push_coordinate(&mut out, nano);
+            assert_eq!(out, text);
+        }
+    }
+}
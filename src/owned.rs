@@ -0,0 +1,355 @@
+//! Owned elements that do not borrow a `PrimitiveBlock`
+//!
+//! The element types in [`elements`](crate::elements) and [`dense`](crate::dense) are lightweight
+//! views into a decoded [`PrimitiveBlock`](crate::block::PrimitiveBlock). Convert them with
+//! `to_owned()` to keep them beyond the lifetime of the block, e.g. to collect them or to send
+//! them to another thread.
+
+use crate::dense::{DenseNode, DenseNodeInfo};
+use crate::elements::{Element, Info, Node, RelMemberType, Relation, Way, WayNodeLocation};
+use crate::error::{Error, Result};
+
+/// Metadata of an element.
+///
+/// All fields are optional and are only encoded if present when writing with a
+/// [`BlockBuilder`](crate::writer::BlockBuilder).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OwnedInfo {
+    /// The version of the element.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub version: Option<i32>,
+    /// The time stamp in milliseconds since the epoch.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub milli_timestamp: Option<i64>,
+    /// The changeset id.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub changeset: Option<i64>,
+    /// The user id.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub uid: Option<i32>,
+    /// The user name.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub user: Option<String>,
+    /// The visibility status or `None` if the element has no visible flag. This is only relevant
+    /// for files with historical information.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub visible: Option<bool>,
+}
+
+impl TryFrom<&Info<'_>> for OwnedInfo {
+    type Error = Error;
+
+    fn try_from(info: &Info<'_>) -> Result<OwnedInfo> {
+        Ok(OwnedInfo {
+            version: info.version(),
+            milli_timestamp: info.milli_timestamp(),
+            changeset: info.changeset(),
+            uid: info.uid(),
+            user: info.user().transpose()?.map(String::from),
+            visible: info.has_visible().then(|| info.visible()),
+        })
+    }
+}
+
+impl TryFrom<&DenseNodeInfo<'_>> for OwnedInfo {
+    type Error = Error;
+
+    fn try_from(info: &DenseNodeInfo<'_>) -> Result<OwnedInfo> {
+        Ok(OwnedInfo {
+            version: Some(info.version()),
+            milli_timestamp: Some(info.milli_timestamp()),
+            changeset: Some(info.changeset()),
+            uid: Some(info.uid()),
+            user: Some(info.user()?.to_string()),
+            visible: info.has_visible().then(|| info.visible()),
+        })
+    }
+}
+
+/// An owned OpenStreetMap node element. Both [`Node`] and [`DenseNode`] convert to this type.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OwnedNode {
+    /// The node id.
+    pub id: i64,
+    /// The latitude coordinate in nanodegrees (10⁻⁹).
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "lat", with = "crate::serialize::degrees")
+    )]
+    pub nano_lat: i64,
+    /// The longitude coordinate in nanodegrees (10⁻⁹).
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "lon", with = "crate::serialize::degrees")
+    )]
+    pub nano_lon: i64,
+    /// The tags as pairs of strings (key and value).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::tags"))]
+    pub tags: Vec<(String, String)>,
+    /// Additional metadata.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub info: OwnedInfo,
+}
+
+impl OwnedNode {
+    /// Returns the latitude coordinate in degrees.
+    pub fn lat(&self) -> f64 {
+        1e-9 * self.nano_lat as f64
+    }
+
+    /// Returns the longitude coordinate in degrees.
+    pub fn lon(&self) -> f64 {
+        1e-9 * self.nano_lon as f64
+    }
+}
+
+/// An owned OpenStreetMap way element.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OwnedWay {
+    /// The way id.
+    pub id: i64,
+    /// The node ids of this way.
+    pub refs: Vec<i64>,
+    /// The node locations of this way. This is empty unless the PBF file includes the locations
+    /// (see [`Way::node_locations`]).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub node_locations: Vec<WayNodeLocation>,
+    /// The tags as pairs of strings (key and value).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::tags"))]
+    pub tags: Vec<(String, String)>,
+    /// Additional metadata.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub info: OwnedInfo,
+}
+
+/// An owned member of a relation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OwnedRelMember {
+    /// The element type of the member.
+    pub member_type: RelMemberType,
+    /// The id of the member.
+    pub member_id: i64,
+    /// The role of the member.
+    pub role: String,
+}
+
+/// An owned OpenStreetMap relation element.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OwnedRelation {
+    /// The relation id.
+    pub id: i64,
+    /// The members of this relation.
+    pub members: Vec<OwnedRelMember>,
+    /// The tags as pairs of strings (key and value).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::tags"))]
+    pub tags: Vec<(String, String)>,
+    /// Additional metadata.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub info: OwnedInfo,
+}
+
+/// An owned OSM core element: a node, a way or a relation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
+pub enum OwnedElement {
+    /// A node. Dense nodes are also converted to this variant.
+    Node(OwnedNode),
+
+    /// A way.
+    Way(OwnedWay),
+
+    /// A relation.
+    Relation(OwnedRelation),
+}
+
+impl OwnedElement {
+    /// Returns the id of the element.
+    pub fn id(&self) -> i64 {
+        match self {
+            OwnedElement::Node(node) => node.id,
+            OwnedElement::Way(way) => way.id,
+            OwnedElement::Relation(relation) => relation.id,
+        }
+    }
+
+    /// Returns the tags of the element.
+    pub fn tags(&self) -> &[(String, String)] {
+        match self {
+            OwnedElement::Node(node) => &node.tags,
+            OwnedElement::Way(way) => &way.tags,
+            OwnedElement::Relation(relation) => &relation.tags,
+        }
+    }
+
+    /// Returns the metadata of the element.
+    pub fn info(&self) -> &OwnedInfo {
+        match self {
+            OwnedElement::Node(node) => &node.info,
+            OwnedElement::Way(way) => &way.info,
+            OwnedElement::Relation(relation) => &relation.info,
+        }
+    }
+}
+
+impl From<OwnedNode> for OwnedElement {
+    fn from(node: OwnedNode) -> OwnedElement {
+        OwnedElement::Node(node)
+    }
+}
+
+impl From<OwnedWay> for OwnedElement {
+    fn from(way: OwnedWay) -> OwnedElement {
+        OwnedElement::Way(way)
+    }
+}
+
+impl From<OwnedRelation> for OwnedElement {
+    fn from(relation: OwnedRelation) -> OwnedElement {
+        OwnedElement::Relation(relation)
+    }
+}
+
+fn owned_tags<'a, I: Iterator<Item = (&'a str, &'a str)>>(tags: I) -> Vec<(String, String)> {
+    tags.map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+impl Node<'_> {
+    /// Copies this node into an [`OwnedNode`] that does not borrow the `PrimitiveBlock`.
+    ///
+    /// # Errors
+    /// Returns an error if the user name is not a valid entry of the stringtable.
+    pub fn to_owned(&self) -> Result<OwnedNode> {
+        Ok(OwnedNode {
+            id: self.id(),
+            nano_lat: self.nano_lat(),
+            nano_lon: self.nano_lon(),
+            tags: owned_tags(self.tags()),
+            info: OwnedInfo::try_from(&self.info())?,
+        })
+    }
+}
+
+impl DenseNode<'_> {
+    /// Copies this node into an [`OwnedNode`] that does not borrow the `PrimitiveBlock`.
+    ///
+    /// # Errors
+    /// Returns an error if the user name is not a valid entry of the stringtable.
+    pub fn to_owned(&self) -> Result<OwnedNode> {
+        Ok(OwnedNode {
+            id: self.id(),
+            nano_lat: self.nano_lat(),
+            nano_lon: self.nano_lon(),
+            tags: owned_tags(self.tags()),
+            info: match self.info() {
+                Some(info) => OwnedInfo::try_from(info)?,
+                None => OwnedInfo::default(),
+            },
+        })
+    }
+}
+
+impl Way<'_> {
+    /// Copies this way into an [`OwnedWay`] that does not borrow the `PrimitiveBlock`.
+    ///
+    /// # Errors
+    /// Returns an error if the user name is not a valid entry of the stringtable.
+    pub fn to_owned(&self) -> Result<OwnedWay> {
+        Ok(OwnedWay {
+            id: self.id(),
+            refs: self.refs().collect(),
+            node_locations: self.node_locations().collect(),
+            tags: owned_tags(self.tags()),
+            info: OwnedInfo::try_from(&self.info())?,
+        })
+    }
+}
+
+impl Relation<'_> {
+    /// Copies this relation into an [`OwnedRelation`] that does not borrow the `PrimitiveBlock`.
+    ///
+    /// # Errors
+    /// Returns an error if a role or the user name is not a valid entry of the stringtable.
+    pub fn to_owned(&self) -> Result<OwnedRelation> {
+        let members = self
+            .members()
+            .map(|member| {
+                Ok(OwnedRelMember {
+                    role: member.role()?.to_string(),
+                    member_type: member.member_type,
+                    member_id: member.member_id,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(OwnedRelation {
+            id: self.id(),
+            members,
+            tags: owned_tags(self.tags()),
+            info: OwnedInfo::try_from(&self.info())?,
+        })
+    }
+}
+
+impl Element<'_> {
+    /// Copies this element into an [`OwnedElement`] that does not borrow the `PrimitiveBlock`.
+    /// Dense nodes are converted to [`OwnedElement::Node`].
+    ///
+    /// # Errors
+    /// Returns an error if a role or the user name is not a valid entry of the stringtable.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    /// let mut ways = vec![];
+    ///
+    /// reader.for_each(|element| {
+    ///     if let Element::Way(_) = element {
+    ///         ways.push(element.to_owned());
+    ///     }
+    /// })?;
+    ///
+    /// let ways = ways.into_iter().collect::<Result<Vec<OwnedElement>>>()?;
+    /// # assert_eq!(ways.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn to_owned(&self) -> Result<OwnedElement> {
+        Ok(match self {
+            Element::Node(node) => OwnedElement::Node(node.to_owned()?),
+            Element::DenseNode(node) => OwnedElement::Node(node.to_owned()?),
+            Element::Way(way) => OwnedElement::Way(way.to_owned()?),
+            Element::Relation(relation) => OwnedElement::Relation(relation.to_owned()?),
+        })
+    }
+}
@@ -1,12 +1,22 @@
 //! High level reader interface
 
 use crate::blob::{BlobDecode, BlobReader};
-use crate::elements::Element;
-use crate::error::Result;
+use crate::block::{BlockElementsIter, ElementsPosition, HeaderBlock, PrimitiveBlock};
+use crate::elements::{Changeset, Element};
+use crate::error::{new_error, Error, ErrorKind, Result};
+use crate::filter::TagFilter;
+use crate::history::HistoryIter;
+use crate::validate::{find_sort_violation, SortViolation};
 use rayon::prelude::*;
+use std::any::Any;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread;
 
 /// A reader for PBF files that gives access to the stored elements: nodes, ways and relations.
 #[derive(Clone, Debug)]
@@ -41,7 +51,9 @@ impl<R: Read + Send> ElementReader<R> {
     /// Consider using `par_map_reduce` instead if you need better performance.
     ///
     /// # Errors
-    /// Returns the first Error encountered while parsing the PBF structure.
+    /// Returns the first Error encountered while parsing the PBF structure. Also returns an
+    /// [`ErrorKind::UnsupportedRequiredFeature`]
+    /// error if the header lists a required feature that is not supported.
     ///
     /// # Example
     /// ```
@@ -64,14 +76,65 @@ impl<R: Read + Send> ElementReader<R> {
     /// # }
     /// # foo().unwrap();
     /// ```
-    pub fn for_each<F>(self, mut f: F) -> Result<()>
+    pub fn for_each<F>(self, f: F) -> Result<()>
     where
         F: for<'a> FnMut(Element<'a>),
     {
-        //TODO do something useful with header blocks
+        self.for_each_with_header(|_| {}, f)
+    }
+
+    /// Just like [`for_each`](ElementReader::for_each), but also calls `header_f` on each
+    /// [`HeaderBlock`] before the elements that follow it. Usually there is only one header
+    /// block at the start of the file.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while parsing the PBF structure. Also returns an
+    /// [`ErrorKind::UnsupportedRequiredFeature`]
+    /// error if the header lists a required feature that is not supported. In this case
+    /// `header_f` is not called.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    /// let mut history = false;
+    /// let mut nodes = 0_u64;
+    ///
+    /// reader.for_each_with_header(
+    ///     |header| {
+    ///         history = header
+    ///             .required_features()
+    ///             .iter()
+    ///             .any(|feature| feature == "HistoricalInformation");
+    ///     },
+    ///     |element| {
+    ///         if let Element::Node(_) | Element::DenseNode(_) = element {
+    ///             nodes += 1;
+    ///         }
+    ///     },
+    /// )?;
+    ///
+    /// println!("history file: {history}, number of nodes: {nodes}");
+    /// # assert!(!history);
+    /// # assert_eq!(nodes, 3);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_with_header<H, F>(self, mut header_f: H, mut f: F) -> Result<()>
+    where
+        H: FnMut(&HeaderBlock),
+        F: for<'a> FnMut(Element<'a>),
+    {
         for blob in self.blob_iter {
             match blob?.decode() {
-                Ok(BlobDecode::OsmHeader(_)) | Ok(BlobDecode::Unknown(_)) => {}
+                Ok(BlobDecode::OsmHeader(header)) => {
+                    header.check_required_features()?;
+                    header_f(&header);
+                }
+                Ok(BlobDecode::Unknown(_)) => {}
                 Ok(BlobDecode::OsmData(block)) => {
                     block.for_each_element(&mut f);
                 }
@@ -82,6 +145,283 @@ impl<R: Read + Send> ElementReader<R> {
         Ok(())
     }
 
+    /// Decodes the PBF structure sequentially and calls the given closure on each element with
+    /// tags that match the filter. The filter is compiled once per block, so matching an element
+    /// does not decode any strings (see [`TagFilter`]).
+    ///
+    /// # Errors
+    /// Returns the same errors as [`for_each`](ElementReader::for_each).
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    /// let mut filter = TagFilter::any();
+    /// filter.key_value("building", "yes");
+    /// let mut buildings = 0_u64;
+    ///
+    /// reader.for_each_filtered(&filter, |_element| buildings += 1)?;
+    ///
+    /// println!("Number of buildings: {buildings}");
+    /// # assert_eq!(buildings, 1);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_filtered<F>(self, filter: &TagFilter, mut f: F) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+    {
+        for block in self.blocks() {
+            block?.for_each_element_filtered(filter, &mut f);
+        }
+        Ok(())
+    }
+
+    /// Just like [`for_each`](ElementReader::for_each), but also calls `changeset_f` on each
+    /// [`Changeset`]. Changesets are rarely stored in PBF files and are not part of [`Element`].
+    /// The changesets of a block are visited after its elements.
+    ///
+    /// # Errors
+    /// Returns the same errors as [`for_each`](ElementReader::for_each).
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    /// let mut changesets = vec![];
+    ///
+    /// reader.for_each_with_changesets(|_element| {}, |changeset| changesets.push(changeset.id()))?;
+    ///
+    /// println!("Changesets: {changesets:?}");
+    /// # assert!(changesets.is_empty());
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn for_each_with_changesets<F, C>(self, mut f: F, mut changeset_f: C) -> Result<()>
+    where
+        F: for<'a> FnMut(Element<'a>),
+        C: for<'a> FnMut(Changeset<'a>),
+    {
+        for block in self.blocks() {
+            let block = block?;
+            block.for_each_element(&mut f);
+            block.for_each_changeset(&mut changeset_f);
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the decoded [`PrimitiveBlock`]s. Header blocks and unknown blobs
+    /// are skipped, but a header with an unsupported required feature yields an
+    /// [`ErrorKind::UnsupportedRequiredFeature`]
+    /// error. The iteration stops after the first error.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// for block in reader.blocks() {
+    ///     let block = block?;
+    ///     println!("Number of groups: {}", block.groups().len());
+    /// }
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn blocks(self) -> BlockIter<R> {
+        BlockIter {
+            blob_iter: self.blob_iter,
+            finished: false,
+        }
+    }
+
+    /// Returns the underlying blob reader, e.g. to read the header block before the elements.
+    #[cfg_attr(not(feature = "xml"), allow(dead_code))]
+    pub(crate) fn into_blob_reader(self) -> BlobReader<R> {
+        self.blob_iter
+    }
+
+    /// Returns an iterator over the histories of all elements of a full-history file. The
+    /// versions of each element are grouped into an
+    /// [`ElementHistory`](crate::history::ElementHistory). See the
+    /// [`history`](crate::history) module for details.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/deleted_nodes.osh.pbf")?;
+    ///
+    /// for history in reader.histories() {
+    ///     let history = history?;
+    ///     println!(
+    ///         "element {} has {} versions, deleted: {}",
+    ///         history.id(),
+    ///         history.versions().len(),
+    ///         history.is_deleted()
+    ///     );
+    /// }
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn histories(self) -> HistoryIter<R> {
+        HistoryIter::new(self.blocks())
+    }
+
+    /// Decodes the PBF structure sequentially and returns the first element that is not sorted
+    /// by type, then by ID, or `None` if the whole file is sorted. Equal IDs are only allowed if
+    /// the file contains historical information. See the [`validate`](crate::validate) module.
+    ///
+    /// # Errors
+    /// Returns the same errors as [`for_each`](ElementReader::for_each).
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// match reader.check_sort_order()? {
+    ///     Some(violation) => println!(
+    ///         "{:?} {} is out of order ({:?})",
+    ///         violation.element_type, violation.id, violation.kind
+    ///     ),
+    ///     None => println!("sorted by type, then by ID"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn check_sort_order(self) -> Result<Option<SortViolation>> {
+        find_sort_violation(self.blob_iter)
+    }
+
+    /// Returns a cursor over all elements. Unlike an [`Iterator`], each returned element borrows
+    /// the cursor, so it has to be dropped (or converted with `to_owned`) before the next one is
+    /// requested. This allows early exit and interleaving the iteration of multiple files without
+    /// callbacks.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    /// let mut elements = reader.elements();
+    ///
+    /// // Find the first way and stop reading.
+    /// while let Some(element) = elements.next_element() {
+    ///     if let Element::Way(way) = element? {
+    ///         println!("First way: {}", way.id());
+    ///         break;
+    ///     }
+    /// }
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn elements(self) -> ElementCursor<R> {
+        ElementCursor {
+            blocks: self.blocks(),
+            block: None,
+            position: ElementsPosition::default(),
+        }
+    }
+
+    /// Returns an iterator over the decoded [`PrimitiveBlock`]s that decodes blobs in parallel on
+    /// the rayon thread pool but still yields the blocks strictly in file order. See
+    /// [`par_map_blocks`](ElementReader::par_map_blocks) for details.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// for block in reader.par_blocks() {
+    ///     for element in block?.elements() {
+    ///         // Elements are visited in file order
+    ///     }
+    /// }
+    ///
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn par_blocks(self) -> ParBlockIter<R, PrimitiveBlock>
+    where
+        R: 'static,
+    {
+        self.par_map_blocks(|block| block)
+    }
+
+    /// Returns an iterator over the results of calling `map_op` on each [`PrimitiveBlock`]. The
+    /// blobs are decoded and `map_op` is called in parallel on the rayon thread pool, but the
+    /// results are yielded strictly in file order. Header blocks and unknown blobs are skipped,
+    /// but just like for [`blocks`](ElementReader::blocks) unsupported required features are
+    /// reported as an error. The iteration stops after the first error.
+    ///
+    /// Blobs are read on a dedicated thread that is started by the first call of `next`. At most
+    /// [`queue_depth`](ParBlockIter::with_queue_depth) blobs are read ahead of the last yielded
+    /// result, which bounds the memory usage if the consumer is slower than the decoding.
+    ///
+    /// If `map_op` panics, the panic is caught and yielded as an [`ErrorKind::Panicked`] error
+    /// in place of the result of that block.
+    ///
+    /// Do not drive the iterator from inside the rayon thread pool, as waiting for the results
+    /// could block a worker thread that is needed for decoding.
+    ///
+    /// # Example
+    /// ```
+    /// use osmpbf::*;
+    ///
+    /// # fn foo() -> Result<()> {
+    /// let reader = ElementReader::from_path("tests/test.osm.pbf")?;
+    ///
+    /// // Count the elements of each block in parallel.
+    /// let counts = reader
+    ///     .par_map_blocks(|block| block.elements().count())
+    ///     .collect::<Result<Vec<usize>>>()?;
+    ///
+    /// # assert_eq!(counts, [5]);
+    /// # Ok(())
+    /// # }
+    /// # foo().unwrap();
+    /// ```
+    pub fn par_map_blocks<F, T>(self, map_op: F) -> ParBlockIter<R, T>
+    where
+        R: 'static,
+        F: Fn(PrimitiveBlock) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        ParBlockIter {
+            blob_iter: Some(self.blob_iter),
+            map_op: Arc::new(map_op),
+            tokens: None,
+            receiver: None,
+            queue_depth: 2 * rayon::current_num_threads(),
+            reorder_buffer: BTreeMap::new(),
+            next_yield_index: 0,
+            blob_count: None,
+            finished: false,
+        }
+    }
+
     /// Parallel map/reduce. Decodes the PBF structure in parallel, calls the closure `map_op` on
     /// each element and then reduces the number of results to one item with the closure
     /// `reduce_op`. Similarly to the `init` argument in the `fold` method on iterators, the
@@ -126,7 +466,11 @@ impl<R: Read + Send> ElementReader<R> {
         self.blob_iter
             .par_bridge()
             .map(|blob| match blob?.decode() {
-                Ok(BlobDecode::OsmHeader(_)) | Ok(BlobDecode::Unknown(_)) => Ok(identity()),
+                Ok(BlobDecode::OsmHeader(header)) => {
+                    header.check_required_features()?;
+                    Ok(identity())
+                }
+                Ok(BlobDecode::Unknown(_)) => Ok(identity()),
                 Ok(BlobDecode::OsmData(block)) => {
                     Ok(block.elements().map(&map_op).fold(identity(), &reduce_op))
                 }
@@ -142,6 +486,273 @@ impl<R: Read + Send> ElementReader<R> {
     }
 }
 
+/// An iterator over the decoded [`PrimitiveBlock`]s of a PBF file.
+///
+/// This struct is created by [`ElementReader::blocks`].
+#[derive(Clone, Debug)]
+pub struct BlockIter<R: Read + Send> {
+    blob_iter: BlobReader<R>,
+    finished: bool,
+}
+
+impl<R: Read + Send> Iterator for BlockIter<R> {
+    type Item = Result<PrimitiveBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        for blob in self.blob_iter.by_ref() {
+            let blob = match blob {
+                Ok(blob) => blob,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            };
+            match blob.decode() {
+                Ok(BlobDecode::OsmHeader(header)) => {
+                    if let Err(e) = header.check_required_features() {
+                        self.finished = true;
+                        return Some(Err(e));
+                    }
+                }
+                Ok(BlobDecode::Unknown(_)) => {}
+                Ok(BlobDecode::OsmData(block)) => return Some(Ok(block)),
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        self.finished = true;
+        None
+    }
+}
+
+/// A cursor over the elements of a PBF file. Call
+/// [`next_element`](ElementCursor::next_element) to advance.
+///
+/// This struct is created by [`ElementReader::elements`].
+#[derive(Clone, Debug)]
+pub struct ElementCursor<R: Read + Send> {
+    blocks: BlockIter<R>,
+    block: Option<PrimitiveBlock>,
+    position: ElementsPosition,
+}
+
+impl<R: Read + Send> ElementCursor<R> {
+    /// Returns the next element or [`None`] at the end of the file. After an error is returned,
+    /// the cursor only returns [`None`].
+    pub fn next_element(&mut self) -> Option<Result<Element<'_>>> {
+        // Make sure that the current block has at least one remaining element.
+        loop {
+            if let Some(block) = &self.block {
+                if !BlockElementsIter::at_position(block.as_proto(), &self.position).is_exhausted()
+                {
+                    break;
+                }
+            }
+
+            match self.blocks.next()? {
+                Ok(block) => {
+                    self.block = Some(block);
+                    self.position = ElementsPosition::default();
+                }
+                Err(e) => {
+                    self.block = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let block = self.block.as_ref()?;
+        let mut iter = BlockElementsIter::at_position(block.as_proto(), &self.position);
+        let element = iter.next();
+        self.position = iter.position();
+        element.map(Ok)
+    }
+}
+
+/// The result of decoding a blob. `None` for header blocks and unknown blobs.
+type DecodeResult<T> = Result<Option<T>>;
+
+/// A message from the reader thread of a [`ParBlockIter`] or one of its decoding tasks.
+enum ParMessage<T> {
+    /// The result of the blob with the given index.
+    Decoded(usize, DecodeResult<T>),
+    /// The reader thread stopped after reading the given number of blobs.
+    Finished(usize),
+}
+
+/// An iterator that decodes blocks in parallel and yields the results in file order.
+///
+/// This struct is created by [`ElementReader::par_blocks`] and
+/// [`ElementReader::par_map_blocks`].
+pub struct ParBlockIter<R: Read + Send, T> {
+    /// The blob reader until it is moved to the reader thread by the first call of `next`.
+    blob_iter: Option<BlobReader<R>>,
+    map_op: Arc<dyn Fn(PrimitiveBlock) -> T + Send + Sync>,
+    /// Each token allows the reader thread to read one more blob.
+    tokens: Option<SyncSender<()>>,
+    receiver: Option<Receiver<ParMessage<T>>>,
+    /// Maximum number of blobs that are read but not yet yielded.
+    queue_depth: usize,
+    /// Finished results that wait for their predecessors, keyed by blob index.
+    reorder_buffer: BTreeMap<usize, DecodeResult<T>>,
+    next_yield_index: usize,
+    /// Number of blobs, known once the reader thread has finished.
+    blob_count: Option<usize>,
+    finished: bool,
+}
+
+impl<R: Read + Send + 'static, T: Send + 'static> ParBlockIter<R, T> {
+    /// Sets the maximum number of blobs that are read ahead of the last yielded result. The
+    /// default is twice the number of threads in the rayon thread pool.
+    ///
+    /// # Panics
+    /// Panics if `queue_depth` is zero.
+    pub fn with_queue_depth(mut self, queue_depth: usize) -> Self {
+        assert!(queue_depth > 0, "queue depth must be positive");
+        self.queue_depth = queue_depth;
+        self
+    }
+
+    /// Starts the reader thread unless it is already running.
+    fn start(&mut self) -> Result<()> {
+        let Some(blob_iter) = self.blob_iter.take() else {
+            return Ok(());
+        };
+
+        let (tokens, token_receiver) = sync_channel(self.queue_depth);
+        for _ in 0..self.queue_depth {
+            // Does not block, the channel has room for all initial tokens.
+            let _ = tokens.send(());
+        }
+        let (sender, receiver) = channel();
+        let map_op = Arc::clone(&self.map_op);
+        thread::Builder::new()
+            .name("osmpbf-reader".to_string())
+            .spawn(move || read_blobs(blob_iter, map_op, token_receiver, sender))?;
+
+        self.tokens = Some(tokens);
+        self.receiver = Some(receiver);
+        Ok(())
+    }
+}
+
+impl<R: Read + Send + 'static, T: Send + 'static> Iterator for ParBlockIter<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if let Err(e) = self.start() {
+            self.finished = true;
+            return Some(Err(e));
+        }
+
+        loop {
+            if let Some(result) = self.reorder_buffer.remove(&self.next_yield_index) {
+                self.next_yield_index += 1;
+                if let Some(tokens) = &self.tokens {
+                    // Never blocks, because at most `queue_depth` tokens are in flight. The
+                    // receiver is gone if the reader thread has already finished.
+                    let _ = tokens.send(());
+                }
+                match result {
+                    Ok(Some(value)) => return Some(Ok(value)),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        self.finished = true;
+                        return Some(Err(e));
+                    }
+                }
+            }
+
+            if self.blob_count == Some(self.next_yield_index) {
+                self.finished = true;
+                return None;
+            }
+
+            // The reader thread and the decoding tasks catch panics, so the channel is only
+            // disconnected after all messages have been received.
+            match self.receiver.as_ref()?.recv() {
+                Ok(ParMessage::Decoded(index, result)) => {
+                    self.reorder_buffer.insert(index, result);
+                }
+                Ok(ParMessage::Finished(count)) => self.blob_count = Some(count),
+                Err(_) => {
+                    self.finished = true;
+                    return Some(Err(new_error(ErrorKind::Panicked {
+                        message: "reader thread stopped unexpectedly".to_string(),
+                    })));
+                }
+            }
+        }
+    }
+}
+
+/// Runs on the reader thread of a [`ParBlockIter`]. Reads a blob for each token and spawns a
+/// decoding task on the rayon thread pool. Stops after the first error or when the iterator is
+/// dropped.
+fn read_blobs<R: Read + Send, T: Send + 'static>(
+    mut blob_iter: BlobReader<R>,
+    map_op: Arc<dyn Fn(PrimitiveBlock) -> T + Send + Sync>,
+    tokens: Receiver<()>,
+    sender: Sender<ParMessage<T>>,
+) {
+    let mut index = 0;
+    // The token sender is gone if the iterator was dropped early.
+    while tokens.recv().is_ok() {
+        let blob = match panic::catch_unwind(AssertUnwindSafe(|| blob_iter.next())) {
+            Ok(Some(Ok(blob))) => blob,
+            Ok(Some(Err(e))) => {
+                let _ = sender.send(ParMessage::Decoded(index, Err(e)));
+                index += 1;
+                break;
+            }
+            Ok(None) => break,
+            Err(payload) => {
+                let _ = sender.send(ParMessage::Decoded(index, Err(panic_error(payload))));
+                index += 1;
+                break;
+            }
+        };
+
+        let map_op = Arc::clone(&map_op);
+        let sender = sender.clone();
+        rayon::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| match blob.decode() {
+                Ok(BlobDecode::OsmHeader(header)) => header.check_required_features().map(|_| None),
+                Ok(BlobDecode::Unknown(_)) => Ok(None),
+                Ok(BlobDecode::OsmData(block)) => Ok(Some(map_op(block))),
+                Err(e) => Err(e),
+            }))
+            .unwrap_or_else(|payload| Err(panic_error(payload)));
+            // The receiver is gone if the iterator was dropped early.
+            let _ = sender.send(ParMessage::Decoded(index, result));
+        });
+        index += 1;
+    }
+    let _ = sender.send(ParMessage::Finished(index));
+}
+
+/// Converts the payload of a caught panic to an error.
+fn panic_error(payload: Box<dyn Any + Send>) -> Error {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    };
+    new_error(ErrorKind::Panicked { message })
+}
+
 impl ElementReader<BufReader<File>> {
     /// Tries to open the file at the given path and constructs an `ElementReader` from this.
     ///
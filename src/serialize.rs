@@ -0,0 +1,365 @@
+//! Implementations of the `serde` traits
+//!
+//! The element views serialize to the same structure as the corresponding owned types in
+//! [`owned`](crate::owned), so serialized views can be deserialized as owned types. Tags are
+//! serialized as a map and coordinates in degrees.
+
+use crate::block::HeaderBlock;
+use crate::dense::{DenseNode, DenseNodeInfo};
+use crate::elements::{Element, Info, Node, RelMember, Relation, Way, WayNodeLocation};
+use serde::ser::{Error as _, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Converts nanodegrees to degrees.
+fn to_degrees(nano: i64) -> f64 {
+    1e-9 * nano as f64
+}
+
+/// Converts degrees to nanodegrees.
+fn to_nano(degrees: f64) -> i64 {
+    (degrees * 1e9).round() as i64
+}
+
+/// (De)serialize a coordinate in nanodegrees as degrees.
+pub(crate) mod degrees {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(nano: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(to_degrees(*nano))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        f64::deserialize(deserializer).map(to_nano)
+    }
+}
+
+/// (De)serialize a list of key-value pairs as a map.
+pub(crate) mod tags {
+    use super::*;
+    use serde::de::{MapAccess, Visitor};
+    use std::fmt;
+
+    pub(crate) fn serialize<S: Serializer>(
+        tags: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(tags.iter().map(|(k, v)| (k, v)))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        struct TagVisitor;
+
+        impl<'de> Visitor<'de> for TagVisitor {
+            type Value = Vec<(String, String)>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of tags")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut tags = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(tag) = map.next_entry()? {
+                    tags.push(tag);
+                }
+                Ok(tags)
+            }
+        }
+
+        deserializer.deserialize_map(TagVisitor)
+    }
+}
+
+/// Serializes an iterator of tags as a map.
+struct TagMap<I>(I);
+
+impl<'a, I> Serialize for TagMap<I>
+where
+    I: Iterator<Item = (&'a str, &'a str)> + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.clone())
+    }
+}
+
+/// Serializes an iterator as a sequence.
+struct Seq<I>(I);
+
+impl<I> Serialize for Seq<I>
+where
+    I: Iterator + Clone,
+    I::Item: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.clone())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename = "WayNodeLocation")]
+struct Location {
+    lat: f64,
+    lon: f64,
+}
+
+impl Serialize for WayNodeLocation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Location {
+            lat: self.lat(),
+            lon: self.lon(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WayNodeLocation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let location = Location::deserialize(deserializer)?;
+        Ok(WayNodeLocation::new(
+            to_nano(location.lat),
+            to_nano(location.lon),
+        ))
+    }
+}
+
+/// Serializes the fields of a node. If `element_type` is given, it is added as the field `type`
+/// just like for the internally tagged `OwnedElement`.
+fn serialize_node<'a, S, T, I>(
+    serializer: S,
+    element_type: Option<&'static str>,
+    id: i64,
+    nano_lat: i64,
+    nano_lon: i64,
+    tags: T,
+    info: Option<&I>,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Iterator<Item = (&'a str, &'a str)> + Clone,
+    I: Serialize,
+{
+    let mut state = serializer.serialize_struct("Node", 6)?;
+    if let Some(element_type) = element_type {
+        state.serialize_field("type", element_type)?;
+    }
+    state.serialize_field("id", &id)?;
+    state.serialize_field("lat", &to_degrees(nano_lat))?;
+    state.serialize_field("lon", &to_degrees(nano_lon))?;
+    state.serialize_field("tags", &TagMap(tags))?;
+    match info {
+        Some(info) => state.serialize_field("info", info)?,
+        None => state.skip_field("info")?,
+    }
+    state.end()
+}
+
+fn serialize_way<S: Serializer>(
+    way: &Way,
+    element_type: Option<&'static str>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("Way", 6)?;
+    if let Some(element_type) = element_type {
+        state.serialize_field("type", element_type)?;
+    }
+    state.serialize_field("id", &way.id())?;
+    state.serialize_field("refs", &Seq(way.refs()))?;
+    let node_locations = way.node_locations();
+    if node_locations.len() > 0 {
+        state.serialize_field("node_locations", &Seq(node_locations))?;
+    } else {
+        state.skip_field("node_locations")?;
+    }
+    state.serialize_field("tags", &TagMap(way.tags()))?;
+    state.serialize_field("info", &way.info())?;
+    state.end()
+}
+
+fn serialize_relation<S: Serializer>(
+    relation: &Relation,
+    element_type: Option<&'static str>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("Relation", 5)?;
+    if let Some(element_type) = element_type {
+        state.serialize_field("type", element_type)?;
+    }
+    state.serialize_field("id", &relation.id())?;
+    state.serialize_field("members", &Seq(relation.members()))?;
+    state.serialize_field("tags", &TagMap(relation.tags()))?;
+    state.serialize_field("info", &relation.info())?;
+    state.end()
+}
+
+impl Serialize for Node<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_node(
+            serializer,
+            None,
+            self.id(),
+            self.nano_lat(),
+            self.nano_lon(),
+            self.tags(),
+            Some(&self.info()),
+        )
+    }
+}
+
+impl Serialize for DenseNode<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_node(
+            serializer,
+            None,
+            self.id(),
+            self.nano_lat(),
+            self.nano_lon(),
+            self.tags(),
+            self.info(),
+        )
+    }
+}
+
+impl Serialize for Way<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_way(self, None, serializer)
+    }
+}
+
+impl Serialize for Relation<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_relation(self, None, serializer)
+    }
+}
+
+/// Serializes like the internally tagged `OwnedElement`, e.g. `{"type": "node", "id": 1, ...}`.
+/// Dense nodes are serialized just like nodes.
+impl Serialize for Element<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Element::Node(node) => serialize_node(
+                serializer,
+                Some("node"),
+                node.id(),
+                node.nano_lat(),
+                node.nano_lon(),
+                node.tags(),
+                Some(&node.info()),
+            ),
+            Element::DenseNode(node) => serialize_node(
+                serializer,
+                Some("node"),
+                node.id(),
+                node.nano_lat(),
+                node.nano_lon(),
+                node.tags(),
+                node.info(),
+            ),
+            Element::Way(way) => serialize_way(way, Some("way"), serializer),
+            Element::Relation(relation) => {
+                serialize_relation(relation, Some("relation"), serializer)
+            }
+        }
+    }
+}
+
+impl Serialize for RelMember<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("RelMember", 3)?;
+        state.serialize_field("member_type", &self.member_type)?;
+        state.serialize_field("member_id", &self.member_id)?;
+        state.serialize_field("role", self.role().map_err(S::Error::custom)?)?;
+        state.end()
+    }
+}
+
+/// Serializes the fields that are present and skips the others, just like `OwnedInfo`.
+fn serialize_optional_field<T, S>(
+    state: &mut S,
+    key: &'static str,
+    value: Option<T>,
+) -> Result<(), S::Error>
+where
+    T: Serialize,
+    S: SerializeStruct,
+{
+    match value {
+        Some(value) => state.serialize_field(key, &value),
+        None => state.skip_field(key),
+    }
+}
+
+impl Serialize for Info<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let user = self.user().transpose().map_err(S::Error::custom)?;
+
+        let mut state = serializer.serialize_struct("Info", 6)?;
+        serialize_optional_field(&mut state, "version", self.version())?;
+        serialize_optional_field(&mut state, "milli_timestamp", self.milli_timestamp())?;
+        serialize_optional_field(&mut state, "changeset", self.changeset())?;
+        serialize_optional_field(&mut state, "uid", self.uid())?;
+        serialize_optional_field(&mut state, "user", user)?;
+        serialize_optional_field(
+            &mut state,
+            "visible",
+            self.has_visible().then(|| self.visible()),
+        )?;
+        state.end()
+    }
+}
+
+impl Serialize for DenseNodeInfo<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Info", 6)?;
+        state.serialize_field("version", &self.version())?;
+        state.serialize_field("milli_timestamp", &self.milli_timestamp())?;
+        state.serialize_field("changeset", &self.changeset())?;
+        state.serialize_field("uid", &self.uid())?;
+        state.serialize_field("user", self.user().map_err(S::Error::custom)?)?;
+        serialize_optional_field(
+            &mut state,
+            "visible",
+            self.has_visible().then(|| self.visible()),
+        )?;
+        state.end()
+    }
+}
+
+impl Serialize for HeaderBlock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("HeaderBlock", 8)?;
+        serialize_optional_field(&mut state, "bbox", self.bbox())?;
+        state.serialize_field("required_features", self.required_features())?;
+        state.serialize_field("optional_features", self.optional_features())?;
+        serialize_optional_field(&mut state, "writing_program", self.writing_program())?;
+        serialize_optional_field(&mut state, "source", self.source())?;
+        serialize_optional_field(
+            &mut state,
+            "osmosis_replication_timestamp",
+            self.osmosis_replication_timestamp(),
+        )?;
+        serialize_optional_field(
+            &mut state,
+            "osmosis_replication_sequence_number",
+            self.osmosis_replication_sequence_number(),
+        )?;
+        serialize_optional_field(
+            &mut state,
+            "osmosis_replication_base_url",
+            self.osmosis_replication_base_url(),
+        )?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degrees_roundtrip() {
+        for nano in [0, 1, -1, 52_122_403_100, -179_999_999_999, 90_000_000_000] {
+            assert_eq!(to_nano(to_degrees(nano)), nano);
+        }
+    }
+}
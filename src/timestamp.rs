@@ -0,0 +1,113 @@
+//! Conversion of time stamps to and from the ISO 8601 format of OSM XML and OPL files
+
+/// Returns the number of days since 1970-01-01 for the given date of the proleptic Gregorian
+/// calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parses a timestamp like `2003-04-05T06:07:08Z` to milliseconds since the epoch.
+pub(crate) fn parse_timestamp(value: &str) -> Option<i64> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || bytes[10] != b'T'
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+        || bytes[19] != b'Z'
+    {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = &value[range];
+        if digits.bytes().all(|b| b.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    Some(((days * 24 + hour) * 60 + minute) * 60_000 + second * 1000)
+}
+
+/// Returns the date of the proleptic Gregorian calendar for the given number of days since
+/// 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats milliseconds since the epoch like `2003-04-05T06:07:08Z`. Milliseconds are truncated.
+pub(crate) fn format_timestamp(milli_timestamp: i64) -> String {
+    let seconds = milli_timestamp.div_euclid(1000);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let seconds_of_day = seconds.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2020-09-13T12:27:00Z"),
+            Some(1_600_000_020_000)
+        );
+        assert_eq!(parse_timestamp("1969-12-31T23:59:59Z"), Some(-1000));
+        assert_eq!(
+            parse_timestamp("2020-02-29T00:00:00Z"),
+            Some(1_582_934_400_000)
+        );
+        assert_eq!(parse_timestamp("2020-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2020-01-01 00:00:00"), None);
+        assert_eq!(parse_timestamp("2020-01-01T00:00:00+01:00"), None);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        for timestamp in [
+            "1970-01-01T00:00:00Z",
+            "2003-04-05T06:07:08Z",
+            "2020-02-29T23:59:59Z",
+            "1969-12-31T23:59:59Z",
+            "2100-03-01T00:00:00Z",
+        ] {
+            let milli_timestamp = parse_timestamp(timestamp).unwrap();
+            assert_eq!(format_timestamp(milli_timestamp), timestamp);
+        }
+        assert_eq!(format_timestamp(1_600_000_020_999), "2020-09-13T12:27:00Z");
+    }
+}
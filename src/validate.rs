@@ -0,0 +1,231 @@
+//! Check that a file satisfies the assumptions of other algorithms
+//!
+//! Merge joins and ID lookups (e.g. [`apply_changes`](crate::change::apply_changes) or the binary
+//! search of [`IndexedReader::get_node`](crate::indexed::IndexedReader::get_node)) require that
+//! the elements are sorted by type, then by ID. Files declare this with the optional feature
+//! `Sort.Type_then_ID` (see [`HeaderBlock::is_sorted_by_type_then_id`]), but the claim is not
+//! verified while reading. [`ElementReader::check_sort_order`] verifies it for every element,
+//! [`IndexedReader::check_blob_sort_order`] only compares the ID ranges of consecutive blobs.
+//!
+//! Extracts often contain ways and relations that reference elements which are not part of the
+//! file. [`IndexedReader::for_each_missing_reference`] reports each of these references as a
+//! [`MissingReference`].
+//!
+//! [`ElementReader::check_sort_order`]: crate::reader::ElementReader::check_sort_order
+//! [`IndexedReader::check_blob_sort_order`]: crate::indexed::IndexedReader::check_blob_sort_order
+//! [`IndexedReader::for_each_missing_reference`]: crate::indexed::IndexedReader::for_each_missing_reference
+
+use crate::blob::{BlobDecode, BlobReader, ByteOffset};
+use crate::block::HeaderBlock;
+use crate::elements::ElementType;
+use crate::error::Result;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Number of bits of an ID that select the bit inside of an [`IdSet`] chunk.
+const ID_SET_CHUNK_BITS: u32 = 16;
+
+/// Number of words of an [`IdSet`] chunk.
+const ID_SET_CHUNK_WORDS: usize = (1 << ID_SET_CHUNK_BITS) / 64;
+
+/// The way in which an element violates the order by type, then by ID.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortViolationKind {
+    /// The element type comes before the type of the previous element, e.g. a node after a way.
+    TypeOrder,
+    /// The ID is smaller than the ID of the previous element of the same type.
+    DescendingId,
+    /// The ID is equal to the ID of the previous element of the same type. This is allowed in
+    /// files with historical information, where each version of an element is stored
+    /// separately.
+    DuplicateId,
+}
+
+/// An element that is not sorted by type, then by ID.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SortViolation {
+    /// The kind of violation.
+    pub kind: SortViolationKind,
+    /// Offset of the blob that contains the element, or `None` if the reader does not track
+    /// offsets (see [`BlobReader::new`]).
+    pub blob_offset: Option<ByteOffset>,
+    /// Type of the unsorted element.
+    pub element_type: ElementType,
+    /// ID of the unsorted element.
+    pub id: i64,
+    /// Type of the element that comes before the unsorted element.
+    pub previous_type: ElementType,
+    /// ID of the element that comes before the unsorted element.
+    pub previous_id: i64,
+}
+
+/// A reference from a way or relation to an element that is not part of the file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissingReference {
+    /// Type of the missing element.
+    pub element_type: ElementType,
+    /// ID of the missing element.
+    pub id: i64,
+    /// Type of the way or relation that references the missing element.
+    pub referrer_type: ElementType,
+    /// ID of the way or relation that references the missing element.
+    pub referrer_id: i64,
+    /// The role of the relation member or `None` for a node of a way.
+    pub role: Option<String>,
+}
+
+/// A set of element IDs stored as a bitmap.
+///
+/// The ID space is split into chunks of 65536 IDs and only chunks that contain at least one ID
+/// are allocated, each taking 8 KiB. So the memory usage is bounded by one bit per ID between
+/// the smallest and the largest ID, but stays small for sparse IDs. E.g. the node IDs of a planet
+/// file take about 1.6 GB.
+#[derive(Clone, Debug, Default)]
+pub struct IdSet {
+    chunks: HashMap<i64, Box<[u64]>>,
+    len: usize,
+}
+
+impl IdSet {
+    /// Creates a new empty `IdSet`.
+    pub fn new() -> IdSet {
+        IdSet::default()
+    }
+
+    /// Adds an ID to the set. Returns true if the ID was not present before.
+    pub fn insert(&mut self, id: i64) -> bool {
+        let (chunk, word, mask) = Self::position(id);
+        let words = self
+            .chunks
+            .entry(chunk)
+            .or_insert_with(|| vec![0; ID_SET_CHUNK_WORDS].into_boxed_slice());
+        let inserted = words[word] & mask == 0;
+        words[word] |= mask;
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// Returns true if the set contains the ID.
+    pub fn contains(&self, id: i64) -> bool {
+        let (chunk, word, mask) = Self::position(id);
+        self.chunks
+            .get(&chunk)
+            .is_some_and(|words| words[word] & mask != 0)
+    }
+
+    /// Returns the number of IDs in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the set contains no IDs.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the chunk, the word inside of the chunk and the bit mask of an ID.
+    fn position(id: i64) -> (i64, usize, u64) {
+        let chunk = id >> ID_SET_CHUNK_BITS;
+        let bit = (id & ((1 << ID_SET_CHUNK_BITS) - 1)) as usize;
+        (chunk, bit / 64, 1 << (bit % 64))
+    }
+}
+
+/// Returns true if the file may contain several versions of the same element.
+pub(crate) fn allows_duplicate_ids(header: &HeaderBlock) -> bool {
+    header
+        .required_features()
+        .iter()
+        .any(|feature| feature == "HistoricalInformation")
+}
+
+/// Compares two consecutive elements (type and ID) and returns the kind of violation if they are
+/// not in order.
+pub(crate) fn sort_violation_kind(
+    previous: (ElementType, i64),
+    current: (ElementType, i64),
+    allow_duplicates: bool,
+) -> Option<SortViolationKind> {
+    if current.0 < previous.0 {
+        Some(SortViolationKind::TypeOrder)
+    } else if current.0 > previous.0 {
+        None
+    } else if current.1 < previous.1 {
+        Some(SortViolationKind::DescendingId)
+    } else if current.1 == previous.1 && !allow_duplicates {
+        Some(SortViolationKind::DuplicateId)
+    } else {
+        None
+    }
+}
+
+/// Reads all blobs and returns the first element that is not sorted by type, then by ID.
+pub(crate) fn find_sort_violation<R: Read + Send>(
+    blobs: BlobReader<R>,
+) -> Result<Option<SortViolation>> {
+    let mut allow_duplicates = false;
+    let mut previous = None;
+
+    for blob in blobs {
+        let blob = blob?;
+        match blob.decode()? {
+            BlobDecode::OsmHeader(header) => {
+                header.check_required_features()?;
+                allow_duplicates = allows_duplicate_ids(&header);
+            }
+            BlobDecode::OsmData(block) => {
+                for element in block.elements() {
+                    let current = (element.element_type(), element.id());
+                    if let Some(previous) = previous {
+                        if let Some(kind) = sort_violation_kind(previous, current, allow_duplicates)
+                        {
+                            return Ok(Some(SortViolation {
+                                kind,
+                                blob_offset: blob.offset(),
+                                element_type: current.0,
+                                id: current.1,
+                                previous_type: previous.0,
+                                previous_id: previous.1,
+                            }));
+                        }
+                    }
+                    previous = Some(current);
+                }
+            }
+            BlobDecode::Unknown(_) => {}
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_violation_kind() {
+        use ElementType::*;
+        assert_eq!(sort_violation_kind((Node, 5), (Node, 6), false), None);
+        assert_eq!(sort_violation_kind((Node, 5), (Way, 1), false), None);
+        assert_eq!(sort_violation_kind((Node, -1), (Node, 1), false), None);
+        assert_eq!(
+            sort_violation_kind((Way, 1), (Node, 5), false),
+            Some(SortViolationKind::TypeOrder)
+        );
+        assert_eq!(
+            sort_violation_kind((Way, 7), (Way, 6), true),
+            Some(SortViolationKind::DescendingId)
+        );
+        assert_eq!(
+            sort_violation_kind((Relation, 3), (Relation, 3), false),
+            Some(SortViolationKind::DuplicateId)
+        );
+        assert_eq!(
+            sort_violation_kind((Relation, 3), (Relation, 3), true),
+            None
+        );
+    }
+}
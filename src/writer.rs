@@ -0,0 +1,724 @@
+//! Encode blocks and write PBF files
+
+use crate::blob::{check_header_size, BlobType, MAX_BLOB_MESSAGE_SIZE};
+use crate::block::{HeaderBBox, HeaderBlock, PrimitiveBlock};
+use crate::elements::RelMemberType;
+use crate::error::{new_blob_error, new_protobuf_error, BlobError, Result};
+use crate::owned::{OwnedElement, OwnedInfo};
+use crate::proto::{fileformat, osmformat};
+use byteorder::WriteBytesExt;
+use flate2::write::ZlibEncoder;
+use osmformat::relation::MemberType;
+use protobuf::{EnumOrUnknown, Message, MessageField};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The compression method that is used for writing blobs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// Store the blob content without compression.
+    None,
+    /// Compress the blob content with zlib using the given level (0-9).
+    Zlib(u32),
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::Zlib(6)
+    }
+}
+
+/// Builds a [`HeaderBlock`] that can be written with a [`PbfWriter`].
+#[derive(Clone, Debug)]
+pub struct HeaderBuilder {
+    header: osmformat::HeaderBlock,
+}
+
+impl HeaderBuilder {
+    /// Creates a new `HeaderBuilder` that requires the features "OsmSchema-V0.6" and
+    /// "DenseNodes" and names `osmpbf` as the writing program.
+    pub fn new() -> HeaderBuilder {
+        let mut header = osmformat::HeaderBlock::new();
+        header.required_features = vec!["OsmSchema-V0.6".into(), "DenseNodes".into()];
+        header.set_writingprogram(format!("osmpbf/{}", env!("CARGO_PKG_VERSION")));
+        HeaderBuilder { header }
+    }
+
+    /// Creates a new `HeaderBuilder` that starts with a copy of the given [`HeaderBlock`].
+    pub fn from_header(header: &HeaderBlock) -> HeaderBuilder {
+        HeaderBuilder {
+            header: header.as_proto().clone(),
+        }
+    }
+
+    /// Sets the bounding box of the included features.
+    pub fn set_bbox(&mut self, bbox: &HeaderBBox) -> &mut Self {
+        let to_nano = |degrees: f64| (degrees * 1.0_e9).round() as i64;
+        let mut header_bbox = osmformat::HeaderBBox::new();
+        header_bbox.set_left(to_nano(bbox.left));
+        header_bbox.set_right(to_nano(bbox.right));
+        header_bbox.set_top(to_nano(bbox.top));
+        header_bbox.set_bottom(to_nano(bbox.bottom));
+        self.header.bbox = MessageField::some(header_bbox);
+        self
+    }
+
+    /// Adds a required feature unless it is already present.
+    pub fn add_required_feature(&mut self, feature: &str) -> &mut Self {
+        if !self.header.required_features.iter().any(|f| f == feature) {
+            self.header.required_features.push(feature.to_string());
+        }
+        self
+    }
+
+    /// Adds an optional feature unless it is already present.
+    pub fn add_optional_feature(&mut self, feature: &str) -> &mut Self {
+        if !self.header.optional_features.iter().any(|f| f == feature) {
+            self.header.optional_features.push(feature.to_string());
+        }
+        self
+    }
+
+    /// Sets the name of the program that generated the file.
+    pub fn set_writing_program(&mut self, program: &str) -> &mut Self {
+        self.header.set_writingprogram(program.to_string());
+        self
+    }
+
+    /// Sets the source of the `bbox` field.
+    pub fn set_source(&mut self, source: &str) -> &mut Self {
+        self.header.set_source(source.to_string());
+        self
+    }
+
+    /// Sets the replication timestamp in seconds since the UNIX epoch.
+    pub fn set_osmosis_replication_timestamp(&mut self, timestamp: i64) -> &mut Self {
+        self.header.set_osmosis_replication_timestamp(timestamp);
+        self
+    }
+
+    /// Sets the replication sequence number.
+    pub fn set_osmosis_replication_sequence_number(&mut self, sequence_number: i64) -> &mut Self {
+        self.header
+            .set_osmosis_replication_sequence_number(sequence_number);
+        self
+    }
+
+    /// Sets the replication base URL.
+    pub fn set_osmosis_replication_base_url(&mut self, url: &str) -> &mut Self {
+        self.header
+            .set_osmosis_replication_base_url(url.to_string());
+        self
+    }
+
+    /// Returns the resulting [`HeaderBlock`].
+    pub fn build(&self) -> HeaderBlock {
+        HeaderBlock::new(self.header.clone())
+    }
+}
+
+impl Default for HeaderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collects the strings of a block and assigns an index to each unique string.
+#[derive(Clone, Debug)]
+struct StringTableBuilder {
+    strings: Vec<Vec<u8>>,
+    indices: HashMap<Vec<u8>, u32>,
+}
+
+impl StringTableBuilder {
+    fn new() -> StringTableBuilder {
+        // Index 0 is reserved as a delimiter and always holds an empty string.
+        StringTableBuilder {
+            strings: vec![vec![]],
+            indices: HashMap::new(),
+        }
+    }
+
+    fn index(&mut self, s: &str) -> u32 {
+        if let Some(&index) = self.indices.get(s.as_bytes()) {
+            return index;
+        }
+        let index = self.strings.len() as u32;
+        self.strings.push(s.as_bytes().to_vec());
+        self.indices.insert(s.as_bytes().to_vec(), index);
+        index
+    }
+}
+
+/// Collects dense nodes column by column. This is the inverse of
+/// [`DenseNodeIter`](crate::dense::DenseNodeIter) and
+/// [`DenseNodeInfoIter`](crate::dense::DenseNodeInfoIter): all values are stored as absolute
+/// values and delta coded when the [`osmformat::DenseNodes`] message is built.
+#[derive(Clone, Debug, Default)]
+struct DenseNodesEncoder {
+    ids: Vec<i64>,
+    lats: Vec<i64>,
+    lons: Vec<i64>,
+    keys_vals: Vec<i32>,
+    has_tags: bool,
+    versions: Vec<i32>,
+    timestamps: Vec<i64>,
+    changesets: Vec<i64>,
+    uids: Vec<i32>,
+    user_sids: Vec<i32>,
+    visible: Vec<bool>,
+    has_info: bool,
+    has_visible: bool,
+}
+
+impl DenseNodesEncoder {
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn build(self) -> osmformat::DenseNodes {
+        let mut dense = osmformat::DenseNodes::new();
+        dense.id = delta_encode(self.ids);
+        dense.lat = delta_encode(self.lats);
+        dense.lon = delta_encode(self.lons);
+        if self.has_tags {
+            dense.keys_vals = self.keys_vals;
+        }
+        if self.has_info {
+            let mut info = osmformat::DenseInfo::new();
+            info.version = self.versions;
+            info.timestamp = delta_encode(self.timestamps);
+            info.changeset = delta_encode(self.changesets);
+            info.uid = delta_encode(self.uids.into_iter().map(i64::from))
+                .into_iter()
+                .map(|d| d as i32)
+                .collect();
+            info.user_sid = delta_encode(self.user_sids.into_iter().map(i64::from))
+                .into_iter()
+                .map(|d| d as i32)
+                .collect();
+            if self.has_visible {
+                info.visible = self.visible;
+            }
+            dense.denseinfo = MessageField::some(info);
+        }
+        dense
+    }
+}
+
+/// Converts a value to the given granularity and rounds to the nearest integer.
+fn encode_scaled(value: i64, offset: i64, granularity: i32) -> i64 {
+    let granularity = i64::from(granularity);
+    (value - offset + granularity / 2).div_euclid(granularity)
+}
+
+/// Builds a [`PrimitiveBlock`] from nodes, dense nodes, ways and relations.
+///
+/// Strings are collected in a common stringtable, coordinates are converted to the granularity
+/// of the block and the ids of way references and relation members are delta coded. Each element
+/// type is stored in its own [`PrimitiveGroup`](crate::block::PrimitiveGroup).
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// let mut builder = BlockBuilder::new();
+/// builder.add_node(1, 52_000_000_000, 11_000_000_000, [("name", "a")], None);
+/// builder.add_node(2, 52_100_000_000, 11_100_000_000, [("name", "b")], None);
+/// builder.add_way(3, [1, 2], [("highway", "path")], None);
+/// let block = builder.build();
+///
+/// assert_eq!(block.elements().count(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlockBuilder {
+    granularity: i32,
+    lat_offset: i64,
+    lon_offset: i64,
+    date_granularity: i32,
+    stringtable: StringTableBuilder,
+    nodes: Vec<osmformat::Node>,
+    dense: DenseNodesEncoder,
+    ways: Vec<osmformat::Way>,
+    relations: Vec<osmformat::Relation>,
+    changesets: Vec<osmformat::ChangeSet>,
+}
+
+impl BlockBuilder {
+    /// Creates a new `BlockBuilder` with the default granularity of 100 nanodegrees, no
+    /// coordinate offsets and a date granularity of 1000 milliseconds.
+    pub fn new() -> BlockBuilder {
+        Self::with_granularity(100, 0, 0, 1000)
+    }
+
+    /// Creates a new `BlockBuilder` that encodes coordinates in units of `granularity`
+    /// nanodegrees relative to the given offsets (also in nanodegrees) and timestamps in units of
+    /// `date_granularity` milliseconds.
+    ///
+    /// # Panics
+    /// Panics if `granularity` or `date_granularity` is not positive.
+    pub fn with_granularity(
+        granularity: i32,
+        lat_offset: i64,
+        lon_offset: i64,
+        date_granularity: i32,
+    ) -> BlockBuilder {
+        assert!(granularity > 0, "granularity must be positive");
+        assert!(date_granularity > 0, "date_granularity must be positive");
+        BlockBuilder {
+            granularity,
+            lat_offset,
+            lon_offset,
+            date_granularity,
+            stringtable: StringTableBuilder::new(),
+            nodes: vec![],
+            dense: DenseNodesEncoder::default(),
+            ways: vec![],
+            relations: vec![],
+            changesets: vec![],
+        }
+    }
+
+    /// Returns the number of elements (including changesets) that were added to this builder.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+            + self.dense.len()
+            + self.ways.len()
+            + self.relations.len()
+            + self.changesets.len()
+    }
+
+    /// Returns true if no elements or changesets were added to this builder.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a node with coordinates given in nanodegrees (10⁻⁹).
+    pub fn add_node<T, K, V>(
+        &mut self,
+        id: i64,
+        nano_lat: i64,
+        nano_lon: i64,
+        tags: T,
+        info: Option<&OwnedInfo>,
+    ) where
+        T: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut node = osmformat::Node::new();
+        node.set_id(id);
+        node.set_lat(encode_scaled(nano_lat, self.lat_offset, self.granularity));
+        node.set_lon(encode_scaled(nano_lon, self.lon_offset, self.granularity));
+        (node.keys, node.vals) = self.encode_tags(tags);
+        node.info = self.encode_info(info);
+        self.nodes.push(node);
+    }
+
+    /// Adds a node with coordinates given in nanodegrees (10⁻⁹) to the dense nodes of this block.
+    /// Dense nodes are stored column-wise, which takes much less space than
+    /// [`add_node`](BlockBuilder::add_node).
+    ///
+    /// Metadata is stored for all dense nodes of a block as soon as one of them has metadata.
+    /// Missing values are filled with defaults in this case.
+    pub fn add_dense_node<T, K, V>(
+        &mut self,
+        id: i64,
+        nano_lat: i64,
+        nano_lon: i64,
+        tags: T,
+        info: Option<&OwnedInfo>,
+    ) where
+        T: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let lat = encode_scaled(nano_lat, self.lat_offset, self.granularity);
+        let lon = encode_scaled(nano_lon, self.lon_offset, self.granularity);
+        let (keys, vals) = self.encode_tags(tags);
+
+        let dense = &mut self.dense;
+        dense.ids.push(id);
+        dense.lats.push(lat);
+        dense.lons.push(lon);
+        for (key, val) in keys.into_iter().zip(vals) {
+            dense.keys_vals.push(key as i32);
+            dense.keys_vals.push(val as i32);
+            dense.has_tags = true;
+        }
+        dense.keys_vals.push(0);
+
+        let info = info.cloned().unwrap_or_default();
+        dense.has_info |= info != OwnedInfo::default();
+        dense.has_visible |= info.visible.is_some();
+        dense.versions.push(info.version.unwrap_or(-1));
+        dense.timestamps.push(
+            info.milli_timestamp
+                .map_or(0, |t| encode_scaled(t, 0, self.date_granularity)),
+        );
+        dense.changesets.push(info.changeset.unwrap_or(0));
+        dense.uids.push(info.uid.unwrap_or(0));
+        dense.user_sids.push(
+            info.user
+                .as_deref()
+                .map_or(0, |u| self.stringtable.index(u) as i32),
+        );
+        dense.visible.push(info.visible.unwrap_or(true));
+    }
+
+    /// Adds a way that references the given node ids.
+    pub fn add_way<I, T, K, V>(&mut self, id: i64, refs: I, tags: T, info: Option<&OwnedInfo>)
+    where
+        I: IntoIterator<Item = i64>,
+        T: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut way = osmformat::Way::new();
+        way.set_id(id);
+        way.refs = delta_encode(refs);
+        (way.keys, way.vals) = self.encode_tags(tags);
+        way.info = self.encode_info(info);
+        self.ways.push(way);
+    }
+
+    /// Adds a relation with the given members. Each member is a tuple of member type, member id
+    /// and role.
+    pub fn add_relation<I, R, T, K, V>(
+        &mut self,
+        id: i64,
+        members: I,
+        tags: T,
+        info: Option<&OwnedInfo>,
+    ) where
+        I: IntoIterator<Item = (RelMemberType, i64, R)>,
+        R: AsRef<str>,
+        T: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut relation = osmformat::Relation::new();
+        relation.set_id(id);
+        let mut member_ids = vec![];
+        for (member_type, member_id, role) in members {
+            relation
+                .roles_sid
+                .push(self.stringtable.index(role.as_ref()) as i32);
+            relation.types.push(EnumOrUnknown::new(match member_type {
+                RelMemberType::Node => MemberType::NODE,
+                RelMemberType::Way => MemberType::WAY,
+                RelMemberType::Relation => MemberType::RELATION,
+            }));
+            member_ids.push(member_id);
+        }
+        relation.memids = delta_encode(member_ids);
+        (relation.keys, relation.vals) = self.encode_tags(tags);
+        relation.info = self.encode_info(info);
+        self.relations.push(relation);
+    }
+
+    /// Adds a changeset with the given id (see [`Changeset`](crate::elements::Changeset)).
+    pub fn add_changeset(&mut self, id: i64) {
+        let mut changeset = osmformat::ChangeSet::new();
+        changeset.set_id(id);
+        self.changesets.push(changeset);
+    }
+
+    /// Adds an owned element. Nodes are added as dense nodes. Node locations of ways are not
+    /// written.
+    pub fn add_element(&mut self, element: &OwnedElement) {
+        match element {
+            OwnedElement::Node(node) => self.add_dense_node(
+                node.id,
+                node.nano_lat,
+                node.nano_lon,
+                node.tags.iter().map(|(k, v)| (k, v)),
+                Some(&node.info),
+            ),
+            OwnedElement::Way(way) => self.add_way(
+                way.id,
+                way.refs.iter().copied(),
+                way.tags.iter().map(|(k, v)| (k, v)),
+                Some(&way.info),
+            ),
+            OwnedElement::Relation(relation) => self.add_relation(
+                relation.id,
+                relation
+                    .members
+                    .iter()
+                    .map(|m| (m.member_type.clone(), m.member_id, m.role.as_str())),
+                relation.tags.iter().map(|(k, v)| (k, v)),
+                Some(&relation.info),
+            ),
+        }
+    }
+
+    /// Consumes the builder and returns the encoded [`PrimitiveBlock`].
+    pub fn build(self) -> PrimitiveBlock {
+        let mut block = osmformat::PrimitiveBlock::new();
+        block.set_granularity(self.granularity);
+        block.set_lat_offset(self.lat_offset);
+        block.set_lon_offset(self.lon_offset);
+        block.set_date_granularity(self.date_granularity);
+
+        let mut stringtable = osmformat::StringTable::new();
+        stringtable.s = self.stringtable.strings;
+        block.stringtable = MessageField::some(stringtable);
+
+        if !self.nodes.is_empty() {
+            let mut group = osmformat::PrimitiveGroup::new();
+            group.nodes = self.nodes;
+            block.primitivegroup.push(group);
+        }
+        if self.dense.len() > 0 {
+            let mut group = osmformat::PrimitiveGroup::new();
+            group.dense = MessageField::some(self.dense.build());
+            block.primitivegroup.push(group);
+        }
+        if !self.ways.is_empty() {
+            let mut group = osmformat::PrimitiveGroup::new();
+            group.ways = self.ways;
+            block.primitivegroup.push(group);
+        }
+        if !self.relations.is_empty() {
+            let mut group = osmformat::PrimitiveGroup::new();
+            group.relations = self.relations;
+            block.primitivegroup.push(group);
+        }
+        if !self.changesets.is_empty() {
+            let mut group = osmformat::PrimitiveGroup::new();
+            group.changesets = self.changesets;
+            block.primitivegroup.push(group);
+        }
+
+        PrimitiveBlock::new(block)
+    }
+
+    fn encode_tags<T, K, V>(&mut self, tags: T) -> (Vec<u32>, Vec<u32>)
+    where
+        T: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        tags.into_iter()
+            .map(|(k, v)| {
+                (
+                    self.stringtable.index(k.as_ref()),
+                    self.stringtable.index(v.as_ref()),
+                )
+            })
+            .unzip()
+    }
+
+    fn encode_info(&mut self, info: Option<&OwnedInfo>) -> MessageField<osmformat::Info> {
+        let Some(info) = info else {
+            return MessageField::none();
+        };
+        let mut osminfo = osmformat::Info::new();
+        osminfo.version = info.version;
+        osminfo.timestamp = info
+            .milli_timestamp
+            .map(|t| encode_scaled(t, 0, self.date_granularity));
+        osminfo.changeset = info.changeset;
+        osminfo.uid = info.uid;
+        osminfo.user_sid = info.user.as_deref().map(|u| self.stringtable.index(u));
+        osminfo.visible = info.visible;
+        MessageField::some(osminfo)
+    }
+}
+
+impl Default for BlockBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn delta_encode<I: IntoIterator<Item = i64>>(values: I) -> Vec<i64> {
+    let mut prev = 0;
+    values
+        .into_iter()
+        .map(|v| {
+            let delta = v - prev;
+            prev = v;
+            delta
+        })
+        .collect()
+}
+
+/// The maximum number of elements per block that [`PbfWriter::write_elements`] writes. This is
+/// the recommended limit of the PBF format.
+pub const ELEMENTS_PER_BLOCK: usize = 8000;
+
+/// A writer for PBF files. It writes a [`HeaderBlock`] followed by a sequence of
+/// [`PrimitiveBlock`]s.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let header = HeaderBuilder::new().build();
+/// let mut writer = PbfWriter::new(Vec::new(), &header)?;
+///
+/// let mut builder = BlockBuilder::new();
+/// builder.add_node(1, 52_000_000_000, 11_000_000_000, [("name", "a")], None);
+/// writer.write_block(&builder.build())?;
+///
+/// let bytes = writer.into_inner()?;
+/// let reader = ElementReader::new(bytes.as_slice());
+/// let mut nodes = 0;
+/// reader.for_each(|_| nodes += 1)?;
+/// assert_eq!(nodes, 1);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PbfWriter<W: Write> {
+    writer: W,
+    compression: Compression,
+}
+
+impl<W: Write> PbfWriter<W> {
+    /// Creates a new `PbfWriter` with the default compression and writes the given header.
+    pub fn new(writer: W, header: &HeaderBlock) -> Result<PbfWriter<W>> {
+        Self::with_compression(writer, header, Compression::default())
+    }
+
+    /// Creates a new `PbfWriter` that compresses blobs with the given method and writes the given
+    /// header.
+    pub fn with_compression(
+        writer: W,
+        header: &HeaderBlock,
+        compression: Compression,
+    ) -> Result<PbfWriter<W>> {
+        let mut pbf_writer = PbfWriter {
+            writer,
+            compression,
+        };
+        let data = header
+            .as_proto()
+            .write_to_bytes()
+            .map_err(|e| new_protobuf_error(e, "header block"))?;
+        pbf_writer.write_blob(BlobType::OsmHeader, &data)?;
+        Ok(pbf_writer)
+    }
+
+    /// Encodes and writes a [`PrimitiveBlock`] as a blob.
+    pub fn write_block(&mut self, block: &PrimitiveBlock) -> Result<()> {
+        let data = block
+            .as_proto()
+            .write_to_bytes()
+            .map_err(|e| new_protobuf_error(e, "primitive block"))?;
+        self.write_blob(BlobType::OsmData, &data)
+    }
+
+    /// Encodes and writes the given elements in blocks of at most [`ELEMENTS_PER_BLOCK`]
+    /// elements. A new block is started whenever the element type changes, so that each block
+    /// contains only one type of elements.
+    pub fn write_elements<I, E>(&mut self, elements: I) -> Result<()>
+    where
+        I: IntoIterator<Item = E>,
+        E: std::borrow::Borrow<OwnedElement>,
+    {
+        let element_type = |element: &OwnedElement| match element {
+            OwnedElement::Node(_) => 0,
+            OwnedElement::Way(_) => 1,
+            OwnedElement::Relation(_) => 2,
+        };
+
+        let mut builder = BlockBuilder::new();
+        let mut current_type = None;
+        for element in elements {
+            let element = element.borrow();
+            let next_type = Some(element_type(element));
+            if builder.len() >= ELEMENTS_PER_BLOCK
+                || (current_type != next_type && !builder.is_empty())
+            {
+                self.write_block(&std::mem::take(&mut builder).build())?;
+            }
+            current_type = next_type;
+            builder.add_element(element);
+        }
+        if !builder.is_empty() {
+            self.write_block(&builder.build())?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the underlying writer and returns it.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_blob(&mut self, blob_type: BlobType, data: &[u8]) -> Result<()> {
+        let size = data.len() as u64;
+        if size >= MAX_BLOB_MESSAGE_SIZE {
+            return Err(new_blob_error(BlobError::MessageTooBig { size }));
+        }
+
+        let mut blob = fileformat::Blob::new();
+        match self.compression {
+            Compression::None => blob.set_raw(data.to_vec()),
+            Compression::Zlib(level) => {
+                let mut encoder = ZlibEncoder::new(vec![], flate2::Compression::new(level));
+                encoder.write_all(data)?;
+                blob.set_zlib_data(encoder.finish()?);
+                blob.set_raw_size(data.len() as i32);
+            }
+        }
+        let blob_bytes = blob
+            .write_to_bytes()
+            .map_err(|e| new_protobuf_error(e, "blob content"))?;
+
+        let mut header = fileformat::BlobHeader::new();
+        header.set_type(blob_type.as_str().to_string());
+        header.set_datasize(blob_bytes.len() as i32);
+        let header_bytes = header
+            .write_to_bytes()
+            .map_err(|e| new_protobuf_error(e, "blob header"))?;
+        let header_size = header_bytes.len() as u64;
+        check_header_size(header_size)?;
+
+        self.writer
+            .write_u32::<byteorder::BigEndian>(header_size as u32)?;
+        self.writer.write_all(&header_bytes)?;
+        self.writer.write_all(&blob_bytes)?;
+        Ok(())
+    }
+}
+
+impl PbfWriter<BufWriter<File>> {
+    /// Creates a file at the given path (truncating an existing one) and writes the given header.
+    ///
+    /// # Errors
+    /// Returns the same errors that `std::fs::File::create` returns.
+    pub fn from_path<P: AsRef<Path>>(path: P, header: &HeaderBlock) -> Result<Self> {
+        let f = File::create(path)?;
+        Self::new(BufWriter::new(f), header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_scaled() {
+        assert_eq!(encode_scaled(52_119_923_500, 0, 100), 521_199_235);
+        assert_eq!(encode_scaled(149, 0, 100), 1);
+        assert_eq!(encode_scaled(150, 0, 100), 2);
+        assert_eq!(encode_scaled(-149, 0, 100), -1);
+        assert_eq!(encode_scaled(-151, 0, 100), -2);
+        assert_eq!(encode_scaled(1_000, 500, 100), 5);
+    }
+
+    #[test]
+    fn test_delta_encode() {
+        assert_eq!(delta_encode([105, 106, 108, 105]), [105, 1, 2, -3]);
+        assert!(delta_encode([]).is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+//! Read OSM XML files
+//!
+//! OSM XML (`.osm`, often compressed as `.osm.gz`) is the format of the OpenStreetMap API and of
+//! editors like JOSM. [`XmlReader`] reads such files into the same
+//! [owned elements](crate::owned) that can be created from PBF elements, so code that works on
+//! [`OwnedElement`]s can process both formats.
+//!
+//! This module is only available with the `xml` feature.
+
+use crate::elements::RelMemberType;
+use crate::error::{new_error, Error, ErrorKind, Result};
+use crate::owned::{OwnedElement, OwnedInfo, OwnedNode, OwnedRelMember, OwnedRelation, OwnedWay};
+use crate::timestamp::parse_timestamp;
+use flate2::read::MultiGzDecoder;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// A reader for OSM XML files. It is an iterator over the nodes, ways and relations in file
+/// order. Other XML elements like `<bounds>` are skipped.
+///
+/// The iteration stops after the first error.
+///
+/// # Example
+/// ```
+/// use osmpbf::*;
+///
+/// # fn foo() -> Result<()> {
+/// let reader = XmlReader::from_path("tests/test.osm")?;
+/// let mut ways = 0_u64;
+///
+/// // Increment the counter by one for each way.
+/// reader.for_each(|element| {
+///     if let OwnedElement::Way(_) = element {
+///         ways += 1;
+///     }
+/// })?;
+///
+/// println!("Number of ways: {ways}");
+/// # assert_eq!(ways, 1);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+pub struct XmlReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    has_root: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> XmlReader<R> {
+    /// Creates a new `XmlReader` that reads uncompressed XML.
+    pub fn new(reader: R) -> XmlReader<R> {
+        XmlReader {
+            reader: new_reader(reader),
+            buf: vec![],
+            has_root: false,
+            finished: false,
+        }
+    }
+
+    /// Reads the file and calls the given closure on each element. This is the counterpart of
+    /// [`ElementReader::for_each`](crate::reader::ElementReader::for_each) for XML files.
+    ///
+    /// # Errors
+    /// Returns the first Error encountered while reading or parsing the XML file.
+    pub fn for_each<F>(self, mut f: F) -> Result<()>
+    where
+        F: FnMut(OwnedElement),
+    {
+        for element in self {
+            f(element?);
+        }
+        Ok(())
+    }
+
+    fn next_element(&mut self) -> Result<Option<OwnedElement>> {
+        loop {
+            self.buf.clear();
+            let (start, is_empty) = match read_event(&mut self.reader, &mut self.buf)? {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+                Event::Eof => return Ok(None),
+                _ => continue,
+            };
+
+            if !self.has_root {
+                if start.name().as_ref() != b"osm" {
+                    return Err(xml_error(&self.reader, "expected <osm> root element"));
+                }
+                self.has_root = true;
+                continue;
+            }
+
+            if let Some(element) = parse_element(&mut self.reader, &start, is_empty)? {
+                return Ok(Some(element));
+            }
+        }
+    }
+}
+
+impl XmlReader<BufReader<Box<dyn Read + Send>>> {
+    /// Opens the OSM XML file at the given path. Files that are compressed with gzip (like
+    /// `.osm.gz`) are detected by their content and decompressed.
+    ///
+    /// # Errors
+    /// Returns the same errors that `std::fs::File::open` returns.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(XmlReader::new(open_xml_file(path)?))
+    }
+}
+
+impl<R: BufRead> Iterator for XmlReader<R> {
+    type Item = Result<OwnedElement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_element().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.finished = true;
+        }
+        result
+    }
+}
+
+/// Opens a file and decompresses it if it starts with the magic bytes of gzip.
+pub(crate) fn open_xml_file<P: AsRef<Path>>(path: P) -> Result<BufReader<Box<dyn Read + Send>>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let inner: Box<dyn Read + Send> = if file.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(BufReader::new(inner))
+}
+
+/// Creates an [`ErrorKind::InvalidXml`] error at the current position of the reader.
+pub(crate) fn xml_error<R>(reader: &Reader<R>, reason: impl ToString) -> Error {
+    new_error(ErrorKind::InvalidXml {
+        position: reader.buffer_position(),
+        reason: reason.to_string(),
+    })
+}
+
+/// Reads the next event and converts errors.
+pub(crate) fn read_event<'b, R: BufRead>(
+    reader: &mut Reader<R>,
+    buf: &'b mut Vec<u8>,
+) -> Result<Event<'b>> {
+    reader.read_event_into(buf).map_err(|e| {
+        new_error(ErrorKind::InvalidXml {
+            position: reader.error_position(),
+            reason: e.to_string(),
+        })
+    })
+}
+
+/// Creates a reader that skips whitespace between elements.
+pub(crate) fn new_reader<R: BufRead>(reader: R) -> Reader<R> {
+    let mut reader = Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+    reader
+}
+
+/// Returns the attributes of an XML element as pairs of name and unescaped value.
+fn attributes<R>(reader: &Reader<R>, start: &BytesStart) -> Result<Vec<(Vec<u8>, String)>> {
+    start
+        .attributes()
+        .map(|attr| {
+            let attr = attr.map_err(|e| xml_error(reader, e))?;
+            let value = attr.unescape_value().map_err(|e| xml_error(reader, e))?;
+            Ok((attr.key.as_ref().to_vec(), value.into_owned()))
+        })
+        .collect()
+}
+
+fn parse<R, T: FromStr>(reader: &Reader<R>, name: &str, value: &str) -> Result<T> {
+    value.parse().map_err(|_| {
+        xml_error(
+            reader,
+            format!("invalid value of attribute {name}: {value:?}"),
+        )
+    })
+}
+
+/// Parses a coordinate in degrees to nanodegrees.
+fn parse_coordinate<R>(reader: &Reader<R>, name: &str, value: &str) -> Result<i64> {
+    let degrees: f64 = parse(reader, name, value)?;
+    Ok((degrees * 1e9).round() as i64)
+}
+
+/// The attributes that are shared by all elements.
+#[derive(Default)]
+struct CommonAttributes {
+    id: Option<i64>,
+    lat: Option<i64>,
+    lon: Option<i64>,
+    info: OwnedInfo,
+}
+
+fn common_attributes<R>(reader: &Reader<R>, start: &BytesStart) -> Result<CommonAttributes> {
+    let mut common = CommonAttributes::default();
+    for (key, value) in attributes(reader, start)? {
+        match key.as_slice() {
+            b"id" => common.id = Some(parse(reader, "id", &value)?),
+            b"lat" => common.lat = Some(parse_coordinate(reader, "lat", &value)?),
+            b"lon" => common.lon = Some(parse_coordinate(reader, "lon", &value)?),
+            b"version" => common.info.version = Some(parse(reader, "version", &value)?),
+            b"changeset" => common.info.changeset = Some(parse(reader, "changeset", &value)?),
+            b"uid" => common.info.uid = Some(parse(reader, "uid", &value)?),
+            b"user" => common.info.user = Some(value),
+            b"visible" => common.info.visible = Some(parse(reader, "visible", &value)?),
+            b"timestamp" => {
+                common.info.milli_timestamp =
+                    Some(parse_timestamp(&value).ok_or_else(|| {
+                        xml_error(reader, format!("invalid timestamp: {value:?}"))
+                    })?)
+            }
+            _ => {}
+        }
+    }
+    Ok(common)
+}
+
+/// Parses a node, way or relation that starts with the given XML element. Reads the child
+/// elements unless the element is empty. Returns `None` for other XML elements, which are not
+/// consumed.
+pub(crate) fn parse_element<R: BufRead>(
+    reader: &mut Reader<R>,
+    start: &BytesStart,
+    is_empty: bool,
+) -> Result<Option<OwnedElement>> {
+    let name = start.name();
+    let name = name.as_ref();
+    if !matches!(name, b"node" | b"way" | b"relation") {
+        return Ok(None);
+    }
+    let common = common_attributes(reader, start)?;
+    let id = common
+        .id
+        .ok_or_else(|| xml_error(reader, "missing attribute id"))?;
+
+    let mut tags = vec![];
+    let mut refs = vec![];
+    let mut members = vec![];
+    let mut buf = vec![];
+    let mut depth = 0;
+    if !is_empty {
+        loop {
+            let event = read_event(reader, &mut buf)?;
+            match event {
+                Event::Start(ref child) | Event::Empty(ref child) if depth == 0 => {
+                    let attrs = attributes(reader, child)?;
+                    let get = |key: &[u8]| {
+                        attrs
+                            .iter()
+                            .find(|(k, _)| k == key)
+                            .map(|(_, v)| v.as_str())
+                    };
+                    match child.name().as_ref() {
+                        b"tag" => tags.push((
+                            get(b"k").unwrap_or_default().to_string(),
+                            get(b"v").unwrap_or_default().to_string(),
+                        )),
+                        b"nd" => {
+                            let node_ref = get(b"ref")
+                                .ok_or_else(|| xml_error(reader, "missing attribute ref"))?;
+                            refs.push(parse(reader, "ref", node_ref)?);
+                        }
+                        b"member" => {
+                            let member_type = match get(b"type") {
+                                Some("node") => RelMemberType::Node,
+                                Some("way") => RelMemberType::Way,
+                                Some("relation") => RelMemberType::Relation,
+                                other => {
+                                    return Err(xml_error(
+                                        reader,
+                                        format!("invalid member type: {other:?}"),
+                                    ))
+                                }
+                            };
+                            let member_ref = get(b"ref")
+                                .ok_or_else(|| xml_error(reader, "missing attribute ref"))?;
+                            members.push(OwnedRelMember {
+                                member_type,
+                                member_id: parse(reader, "ref", member_ref)?,
+                                role: get(b"role").unwrap_or_default().to_string(),
+                            });
+                        }
+                        _ => {}
+                    }
+                    if matches!(event, Event::Start(_)) {
+                        depth += 1;
+                    }
+                }
+                Event::Start(_) => depth += 1,
+                Event::End(_) if depth > 0 => depth -= 1,
+                Event::End(_) => break,
+                Event::Eof => return Err(xml_error(reader, "unexpected end of file")),
+                _ => {}
+            }
+            buf.clear();
+        }
+    }
+
+    let element = match name {
+        b"node" => OwnedElement::Node(OwnedNode {
+            id,
+            nano_lat: common.lat.unwrap_or_default(),
+            nano_lon: common.lon.unwrap_or_default(),
+            tags,
+            info: common.info,
+        }),
+        b"way" => OwnedElement::Way(OwnedWay {
+            id,
+            refs,
+            node_locations: vec![],
+            tags,
+            info: common.info,
+        }),
+        _ => OwnedElement::Relation(OwnedRelation {
+            id,
+            members,
+            tags,
+            info: common.info,
+        }),
+    };
+    Ok(Some(element))
+}
@@ -0,0 +1,262 @@
+use osmpbf::*;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+fn nano(degrees: f64) -> i64 {
+    (degrees * 1e9).round() as i64
+}
+
+/// Nodes on a grid: ID `10 * x + y` is at longitude `x` and latitude `y` for x, y in 0..10.
+fn areas_file() -> Vec<u8> {
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+
+    let no_tags: [(&str, &str); 0] = [];
+    let mut block = BlockBuilder::new();
+    for x in 0..10 {
+        for y in 0..10 {
+            block.add_dense_node(10 * x + y, nano(y as f64), nano(x as f64), no_tags, None);
+        }
+    }
+    writer.write_block(&block.build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    // A closed building, a closed way with area=no and an open way
+    block.add_way(1, [0, 1, 11, 10, 0], [("building", "yes")], None);
+    block.add_way(
+        2,
+        [0, 10, 11, 0],
+        [("barrier", "fence"), ("area", "no")],
+        None,
+    );
+    block.add_way(3, [0, 10, 11], [("highway", "path")], None);
+    // Outer ring of the multipolygon, split into two ways
+    block.add_way(4, [22, 28, 88], no_tags, None);
+    block.add_way(5, [88, 82, 22], no_tags, None);
+    // Inner ring with a closed way and an island in it
+    block.add_way(6, [44, 46, 66, 64, 44], no_tags, None);
+    block.add_way(7, [55, 56, 65, 55], no_tags, None);
+    // Two ways that do not form a closed ring
+    block.add_way(8, [90, 91, 92], no_tags, None);
+    block.add_way(9, [92, 93], no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    block.add_relation(
+        100,
+        [
+            (RelMemberType::Way, 4, "outer"),
+            (RelMemberType::Way, 5, ""),
+            (RelMemberType::Way, 6, "inner"),
+            (RelMemberType::Way, 7, "inner"),
+            (RelMemberType::Node, 55, "label"),
+        ],
+        [("type", "multipolygon"), ("landuse", "forest")],
+        None,
+    );
+    block.add_relation(
+        101,
+        [
+            (RelMemberType::Way, 8, "outer"),
+            (RelMemberType::Way, 9, "outer"),
+        ],
+        [("type", "boundary")],
+        None,
+    );
+    block.add_relation(
+        102,
+        [
+            (RelMemberType::Way, 4, "outer"),
+            (RelMemberType::Way, 1000, "outer"),
+            (RelMemberType::Relation, 100, "subarea"),
+        ],
+        [("type", "boundary")],
+        None,
+    );
+    block.add_relation(
+        103,
+        [(RelMemberType::Way, 1, "outer")],
+        [("type", "route")],
+        None,
+    );
+    writer.write_block(&block.build()).unwrap();
+
+    writer.into_inner().unwrap()
+}
+
+fn read_areas() -> HashMap<AreaSource, Area> {
+    let mut reader = IndexedReader::new(Cursor::new(areas_file())).unwrap();
+    let mut areas = HashMap::new();
+    reader
+        .for_each_area(&mut DenseLocationStore::new(), |area| {
+            areas.insert(area.source, area);
+        })
+        .unwrap();
+    areas
+}
+
+fn ring_degrees(ring: &[WayNodeLocation]) -> Vec<(f64, f64)> {
+    ring.iter()
+        .map(|l| (l.lon().round(), l.lat().round()))
+        .collect()
+}
+
+#[test]
+fn assemble_areas() {
+    let areas = read_areas();
+    let mut sources: Vec<AreaSource> = areas.keys().copied().collect();
+    sources.sort_by_key(|source| format!("{source:?}"));
+    assert_eq!(
+        sources,
+        [
+            AreaSource::Relation(100),
+            AreaSource::Relation(101),
+            AreaSource::Relation(102),
+            AreaSource::Way(1),
+        ]
+    );
+
+    let building = &areas[&AreaSource::Way(1)];
+    assert!(building.is_valid());
+    assert_eq!(building.tags, [("building".to_string(), "yes".to_string())]);
+    assert_eq!(building.polygons.len(), 1);
+    // The way is clockwise, the outer ring is counterclockwise.
+    assert_eq!(
+        ring_degrees(&building.polygons[0].outer),
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)]
+    );
+}
+
+#[test]
+fn assemble_multipolygon() {
+    let areas = read_areas();
+    let forest = &areas[&AreaSource::Relation(100)];
+
+    // Way 7 is an island inside the hole, so its role should be "outer".
+    assert_eq!(
+        forest.problems,
+        [AreaProblem::RoleMismatch {
+            way_id: 7,
+            role: "inner".to_string(),
+            expected: "outer",
+        }]
+    );
+    assert!(forest
+        .tags
+        .contains(&("landuse".to_string(), "forest".to_string())));
+
+    let mut polygons = forest.polygons.clone();
+    polygons.sort_by_key(|p| p.outer.len());
+    assert_eq!(polygons.len(), 2);
+
+    let island = &polygons[0];
+    assert_eq!(island.outer.len(), 4);
+    assert!(island.inners.is_empty());
+
+    let outer = &polygons[1];
+    assert_eq!(outer.outer.len(), 5);
+    assert_eq!(outer.outer.first(), outer.outer.last());
+    assert_eq!(outer.inners.len(), 1);
+    // Inner rings are clockwise.
+    assert_eq!(
+        ring_degrees(&outer.inners[0]),
+        [(4.0, 4.0), (4.0, 6.0), (6.0, 6.0), (6.0, 4.0), (4.0, 4.0)]
+    );
+}
+
+#[test]
+fn assemble_broken_relations() {
+    let areas = read_areas();
+
+    let open = &areas[&AreaSource::Relation(101)];
+    assert!(open.polygons.is_empty());
+    assert_eq!(
+        open.problems,
+        [AreaProblem::OpenRing {
+            start_node_id: 90,
+            end_node_id: 93,
+        }]
+    );
+
+    let missing = &areas[&AreaSource::Relation(102)];
+    assert!(missing.polygons.is_empty());
+    assert_eq!(
+        missing.problems[0],
+        AreaProblem::MissingWay { way_id: 1000 }
+    );
+}
+
+#[test]
+fn assemble_missing_location() {
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 0, 0, [("a", "b")], None);
+    block.add_dense_node(2, 0, nano(1.0), [("a", "b")], None);
+    block.add_way(10, [1, 2, 3, 1], [("building", "yes")], None);
+    writer.write_block(&block.build()).unwrap();
+    let file = writer.into_inner().unwrap();
+
+    let mut reader = IndexedReader::new(Cursor::new(file)).unwrap();
+    let mut areas = vec![];
+    reader
+        .for_each_area(&mut SparseLocationStore::new(), |area| areas.push(area))
+        .unwrap();
+
+    assert_eq!(areas.len(), 1);
+    assert!(areas[0].polygons.is_empty());
+    assert_eq!(
+        areas[0].problems,
+        [AreaProblem::MissingNodeLocation {
+            way_id: 10,
+            node_id: 3,
+        }]
+    );
+}
+
+#[test]
+fn assemble_crossing_rings() {
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let no_tags: [(&str, &str); 0] = [];
+    let mut block = BlockBuilder::new();
+    for x in 0..10 {
+        for y in 0..10 {
+            block.add_dense_node(10 * x + y, nano(y as f64), nano(x as f64), no_tags, None);
+        }
+    }
+    // Two overlapping squares, each starting at a node inside of the other one
+    block.add_way(1, [22, 2, 0, 20, 22], no_tags, None);
+    block.add_way(2, [11, 31, 33, 13, 11], no_tags, None);
+    // A separate square
+    block.add_way(3, [55, 65, 66, 56, 55], no_tags, None);
+    block.add_relation(
+        10,
+        [
+            (RelMemberType::Way, 1, "outer"),
+            (RelMemberType::Way, 2, "outer"),
+            (RelMemberType::Way, 3, "outer"),
+        ],
+        [("type", "multipolygon")],
+        None,
+    );
+    writer.write_block(&block.build()).unwrap();
+    let file = writer.into_inner().unwrap();
+
+    let mut reader = IndexedReader::new(Cursor::new(file)).unwrap();
+    let mut areas = vec![];
+    reader
+        .for_each_area(&mut DenseLocationStore::new(), |area| areas.push(area))
+        .unwrap();
+
+    assert_eq!(areas.len(), 1);
+    assert_eq!(
+        areas[0].problems,
+        [
+            AreaProblem::CrossingRing { way_ids: vec![1] },
+            AreaProblem::CrossingRing { way_ids: vec![2] },
+        ]
+    );
+    assert_eq!(areas[0].polygons.len(), 1);
+    assert_eq!(
+        ring_degrees(&areas[0].polygons[0].outer),
+        [(5.0, 5.0), (6.0, 5.0), (6.0, 6.0), (5.0, 6.0), (5.0, 5.0)]
+    );
+}
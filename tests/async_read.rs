@@ -0,0 +1,95 @@
+#![cfg(feature = "async")]
+
+use futures_util::StreamExt;
+use osmpbf::*;
+
+static TEST_FILE_PATHS: &[&str] = &[
+    "tests/test.osm.pbf",
+    "tests/test_nozlib.osm.pbf",
+    "tests/test_nozlib_nodense.osm.pbf",
+];
+
+#[tokio::test]
+async fn async_read_blobs() {
+    for path in TEST_FILE_PATHS {
+        let reader = AsyncBlobReader::from_path(path).await.unwrap();
+        let blobs = reader
+            .into_stream()
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        // Compare with the blocking reader
+        let sync_blobs = BlobReader::from_path(path)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].get_type(), BlobType::OsmHeader);
+        assert_eq!(blobs[1].get_type(), BlobType::OsmData);
+        assert_eq!(blobs[0].offset(), Some(ByteOffset(0)));
+        assert_eq!(blobs[1].offset(), sync_blobs[1].offset());
+    }
+}
+
+#[tokio::test]
+async fn async_read_elements() {
+    for path in TEST_FILE_PATHS {
+        let reader = AsyncElementReader::from_path(path).await.unwrap();
+        let mut elements = 0_usize;
+        reader.for_each(|_element| elements += 1).await.unwrap();
+        assert_eq!(elements, 5);
+
+        let f = tokio::fs::File::open(path).await.unwrap();
+        let reader = AsyncElementReader::new(tokio::io::BufReader::new(f));
+        let blocks = reader.into_block_stream().collect::<Vec<_>>().await;
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].as_ref().unwrap().elements().count(), 5);
+    }
+}
+
+#[tokio::test]
+async fn async_read_truncated() {
+    let bytes = std::fs::read(TEST_FILE_PATHS[0]).unwrap();
+    let truncated = &bytes[..bytes.len() - 10];
+
+    let mut reader = AsyncBlobReader::new(truncated);
+    assert!(reader.next_blob().await.unwrap().is_ok());
+    assert!(reader.next_blob().await.unwrap().is_err());
+    assert!(reader.next_blob().await.is_none());
+}
+
+#[tokio::test]
+async fn async_read_unsupported_feature() {
+    let mut header = HeaderBuilder::new();
+    header
+        .add_required_feature("OsmSchema-V0.6")
+        .add_required_feature("DenseNodes")
+        .add_required_feature("Fancy-Feature");
+    let mut writer = PbfWriter::new(Vec::new(), &header.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 0, 0, [("key", "value")], None);
+    writer.write_block(&block.build()).unwrap();
+    let file = writer.into_inner().unwrap();
+
+    let assert_unsupported_feature = |err: &Error| match err.kind() {
+        ErrorKind::UnsupportedRequiredFeature { feature } => assert_eq!(feature, "Fancy-Feature"),
+        kind => panic!("unexpected error: {kind:?}"),
+    };
+
+    let err = AsyncElementReader::new(file.as_slice())
+        .for_each(|_| panic!("no elements expected"))
+        .await
+        .unwrap_err();
+    assert_unsupported_feature(&err);
+
+    let blocks = AsyncElementReader::new(file.as_slice())
+        .into_block_stream()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(blocks.len(), 1);
+    assert_unsupported_feature(blocks[0].as_ref().unwrap_err());
+}
@@ -0,0 +1,256 @@
+#![cfg(feature = "xml")]
+
+use osmpbf::*;
+use std::io::Write;
+
+static TEST_FILE_PATH: &str = "tests/test.osm.pbf";
+
+static TEST_CHANGE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osmChange version="0.6" generator="test">
+  <create>
+    <node id="200" version="1" timestamp="2020-09-13T12:27:00Z" uid="18" user="other &amp; user" changeset="5" lat="52.12" lon="11.62">
+      <tag k="amenity" v="bench"/>
+    </node>
+  </create>
+  <modify>
+    <way id="107" version="2" timestamp="2020-09-13T12:26:00Z" uid="18" user="other" changeset="5">
+      <nd ref="105"/>
+      <nd ref="200"/>
+      <nd ref="108"/>
+      <nd ref="105"/>
+      <tag k="building" v="no"/>
+    </way>
+  </modify>
+  <delete>
+    <node id="106" version="2" timestamp="2020-09-13T12:25:00Z" uid="18" user="other" changeset="5" visible="false"/>
+    <relation id="120" version="2" timestamp="2020-09-13T12:25:00Z" changeset="5"/>
+  </delete>
+</osmChange>
+"#;
+
+/// Returns the type and ID of all elements in file order and the header.
+fn read_pbf(bytes: &[u8]) -> (HeaderBlock, Vec<OwnedElement>) {
+    let mut header = None;
+    let mut elements = vec![];
+    ElementReader::new(bytes)
+        .for_each_with_header(
+            |h| header = Some(h.clone()),
+            |element| elements.push(element.to_owned().unwrap()),
+        )
+        .unwrap();
+    (header.unwrap(), elements)
+}
+
+#[test]
+fn read_osm_change() {
+    let changes: Vec<Change> = OsmChangeReader::new(TEST_CHANGE.as_bytes())
+        .collect::<Result<_>>()
+        .unwrap();
+
+    let actions: Vec<(ChangeAction, i64)> =
+        changes.iter().map(|c| (c.action, c.element.id())).collect();
+    assert_eq!(
+        actions,
+        [
+            (ChangeAction::Create, 200),
+            (ChangeAction::Modify, 107),
+            (ChangeAction::Delete, 106),
+            (ChangeAction::Delete, 120),
+        ]
+    );
+
+    match &changes[0].element {
+        OwnedElement::Node(node) => {
+            assert_eq!(node.nano_lat, 52_120_000_000);
+            assert_eq!(node.nano_lon, 11_620_000_000);
+            assert_eq!(node.tags, [("amenity".to_string(), "bench".to_string())]);
+            assert_eq!(node.info.user.as_deref(), Some("other & user"));
+            assert_eq!(node.info.milli_timestamp, Some(1_600_000_020_000));
+            assert_eq!(node.info.version, Some(1));
+            assert_eq!(node.info.changeset, Some(5));
+            assert_eq!(node.info.uid, Some(18));
+        }
+        other => panic!("expected node, got {other:?}"),
+    }
+    match &changes[1].element {
+        OwnedElement::Way(way) => assert_eq!(way.refs, [105, 200, 108, 105]),
+        other => panic!("expected way, got {other:?}"),
+    }
+    assert_eq!(changes[2].element.info().visible, Some(false));
+    assert!(matches!(changes[3].element, OwnedElement::Relation(_)));
+}
+
+#[test]
+fn read_osm_change_gzip() {
+    let path = std::env::temp_dir().join("osmpbf-test-change.osc.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(TEST_CHANGE.as_bytes()).unwrap();
+    std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+    let changes: Vec<Change> = OsmChangeReader::from_path(&path)
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(changes.len(), 4);
+}
+
+#[test]
+fn read_invalid_osm_change() {
+    let errors = [
+        r#"<osm><node id="1"/></osm>"#,
+        r#"<osmChange><node id="1"/></osmChange>"#,
+        r#"<osmChange><create><node id="x"/></create></osmChange>"#,
+        r#"<osmChange><create><node id="1" timestamp="yesterday"/></create></osmChange>"#,
+        r#"<osmChange><create><way id="1"><nd ref="1"/>"#,
+    ];
+    for xml in errors {
+        let result: Result<Vec<Change>> = OsmChangeReader::new(xml.as_bytes()).collect();
+        match result.unwrap_err().kind() {
+            ErrorKind::InvalidXml { .. } => {}
+            other => panic!("expected InvalidXml error for {xml}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn apply_osm_change() {
+    let reader = ElementReader::from_path(TEST_FILE_PATH).unwrap();
+    let changes = OsmChangeReader::new(TEST_CHANGE.as_bytes());
+    let bytes = apply_changes(reader, changes, Vec::new()).unwrap();
+    let (header, elements) = read_pbf(&bytes);
+
+    let ids: Vec<i64> = elements.iter().map(|e| e.id()).collect();
+    assert_eq!(ids, [105, 108, 200, 107]);
+    assert_eq!(elements[1].info().user.as_deref(), Some("testuser"));
+    assert_eq!(
+        elements[3].tags(),
+        [("building".to_string(), "no".to_string())]
+    );
+
+    assert!(header
+        .optional_features()
+        .contains(&"Sort.Type_then_ID".to_string()));
+    assert_eq!(header.osmosis_replication_sequence_number(), None);
+    assert_eq!(header.osmosis_replication_timestamp(), Some(1_600_000_020));
+}
+
+#[test]
+fn apply_osm_change_replication_header() {
+    let mut header = HeaderBuilder::new();
+    header
+        .set_osmosis_replication_sequence_number(41)
+        .set_osmosis_replication_timestamp(1_700_000_000)
+        .set_osmosis_replication_base_url("https://example.com/replication");
+    let mut writer = PbfWriter::new(Vec::new(), &header.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    let info = OwnedInfo {
+        version: Some(3),
+        ..Default::default()
+    };
+    block.add_dense_node(106, 0, 0, [("name", "kept")], Some(&info));
+    writer.write_block(&block.build()).unwrap();
+    let input = writer.into_inner().unwrap();
+
+    // Deleting version 2 of node 106 is older than the input and does not apply.
+    let changes = OsmChangeReader::new(TEST_CHANGE.as_bytes());
+    let bytes = apply_changes(ElementReader::new(input.as_slice()), changes, Vec::new()).unwrap();
+    let (header, elements) = read_pbf(&bytes);
+
+    let ids: Vec<i64> = elements.iter().map(|e| e.id()).collect();
+    assert_eq!(ids, [106, 200, 107]);
+    assert_eq!(header.osmosis_replication_sequence_number(), Some(42));
+    assert_eq!(header.osmosis_replication_timestamp(), Some(1_700_000_000));
+    assert_eq!(
+        header.osmosis_replication_base_url(),
+        Some("https://example.com/replication")
+    );
+
+    let changes = OsmChangeReader::new(TEST_CHANGE.as_bytes());
+    let bytes = apply_changes_with_sequence_number(
+        ElementReader::new(input.as_slice()),
+        changes,
+        45,
+        Vec::new(),
+    )
+    .unwrap();
+    let (header, _) = read_pbf(&bytes);
+    assert_eq!(header.osmosis_replication_sequence_number(), Some(45));
+}
+
+#[test]
+fn apply_osm_change_highest_version() {
+    let osc = r#"<osmChange>
+      <modify><node id="1" version="3" lat="3" lon="0"/></modify>
+      <modify><node id="1" version="2" lat="2" lon="0"/></modify>
+      <create><node id="1" version="1" lat="1" lon="0"/></create>
+    </osmChange>"#;
+    let input = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build())
+        .unwrap()
+        .into_inner()
+        .unwrap();
+    let bytes = apply_changes(
+        ElementReader::new(input.as_slice()),
+        OsmChangeReader::new(osc.as_bytes()),
+        Vec::new(),
+    )
+    .unwrap();
+    let (_, elements) = read_pbf(&bytes);
+
+    assert_eq!(elements.len(), 1);
+    assert_eq!(elements[0].info().version, Some(3));
+}
+
+#[test]
+fn apply_osm_change_without_version() {
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    let info = OwnedInfo {
+        version: Some(3),
+        ..Default::default()
+    };
+    block.add_dense_node(1, 0, 0, [("name", "old")], Some(&info));
+    block.add_dense_node(2, 0, 0, [("name", "old")], None);
+    writer.write_block(&block.build()).unwrap();
+    let input = writer.into_inner().unwrap();
+
+    // Changes without a version replace the input, no matter its version.
+    let osc = r#"<osmChange>
+      <modify><node id="1" lat="1" lon="0"><tag k="name" v="new"/></node></modify>
+      <modify><node id="2" version="1" lat="1" lon="0"><tag k="name" v="new"/></node></modify>
+    </osmChange>"#;
+    let bytes = apply_changes(
+        ElementReader::new(input.as_slice()),
+        OsmChangeReader::new(osc.as_bytes()),
+        Vec::new(),
+    )
+    .unwrap();
+    let (_, elements) = read_pbf(&bytes);
+
+    assert_eq!(elements.len(), 2);
+    for element in &elements {
+        assert_eq!(element.tags(), [("name".to_string(), "new".to_string())]);
+    }
+}
+
+#[test]
+fn apply_osm_change_unsorted() {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(2, 0, 0, no_tags, None);
+    block.add_dense_node(1, 0, 0, no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+    let input = writer.into_inner().unwrap();
+
+    let changes: [Result<Change>; 0] = [];
+    let error =
+        apply_changes(ElementReader::new(input.as_slice()), changes, Vec::new()).unwrap_err();
+    match error.kind() {
+        ErrorKind::UnsortedInput { element_type, id } => {
+            assert_eq!(*element_type, "node");
+            assert_eq!(*id, 1);
+        }
+        other => panic!("expected UnsortedInput error, got {other:?}"),
+    }
+}
@@ -0,0 +1,247 @@
+use osmpbf::*;
+use std::io::Cursor;
+
+static TEST_FILE_PATH: &str = "tests/test.osm.pbf";
+
+/// Returns the type and ID of all elements in file order and the header of the extract.
+fn read_extract(bytes: &[u8]) -> (HeaderBlock, Vec<(char, i64)>) {
+    let mut header = None;
+    let mut elements = vec![];
+    ElementReader::new(bytes)
+        .for_each_with_header(
+            |h| header = Some(h.clone()),
+            |element| {
+                elements.push(match element.to_owned().unwrap() {
+                    OwnedElement::Node(node) => ('n', node.id),
+                    OwnedElement::Way(way) => ('w', way.id),
+                    OwnedElement::Relation(relation) => ('r', relation.id),
+                })
+            },
+        )
+        .unwrap();
+    (header.unwrap(), elements)
+}
+
+fn nano(degrees: f64) -> i64 {
+    (degrees * 1e9).round() as i64
+}
+
+/// Nodes 1 to 5 on the equator at longitudes 1° to 5°, ways and relations referencing them.
+fn line_file() -> Vec<u8> {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    for id in 1..=5 {
+        block.add_dense_node(id, 0, nano(id as f64), [("name", "n")], None);
+    }
+    writer.write_block(&block.build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    block.add_way(10, [1, 2], no_tags, None);
+    block.add_way(11, [2, 3, 4], [("highway", "path")], None);
+    block.add_way(12, [4, 5], no_tags, None);
+    block.add_relation(20, [(RelMemberType::Way, 12, "")], no_tags, None);
+    block.add_relation(21, [(RelMemberType::Node, 3, "")], no_tags, None);
+    block.add_relation(22, [(RelMemberType::Relation, 21, "")], no_tags, None);
+    block.add_relation(23, [(RelMemberType::Relation, 22, "")], no_tags, None);
+    block.add_relation(24, [(RelMemberType::Relation, 20, "")], no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+
+    writer.into_inner().unwrap()
+}
+
+#[test]
+fn extract_bbox_complete_ways() {
+    let mut reader = IndexedReader::from_path(TEST_FILE_PATH).unwrap();
+    let bbox = HeaderBBox {
+        left: 11.627,
+        right: 11.63,
+        top: 52.123,
+        bottom: 52.121,
+    };
+    let bytes = extract_bbox(&mut reader, &bbox, Vec::new()).unwrap();
+    let (header, elements) = read_extract(&bytes);
+
+    assert_eq!(
+        elements,
+        [('n', 105), ('n', 106), ('n', 108), ('w', 107), ('r', 120)]
+    );
+    assert!(header
+        .optional_features()
+        .contains(&"Sort.Type_then_ID".to_string()));
+    let header_bbox = header.bbox().unwrap();
+    assert!((header_bbox.left - 11.627).abs() < 1e-9);
+    assert!((header_bbox.top - 52.123).abs() < 1e-9);
+
+    // Tags and metadata are copied
+    let mut way = None;
+    ElementReader::new(bytes.as_slice())
+        .for_each(|element| {
+            if let Element::Way(w) = element {
+                way = Some(w.to_owned().unwrap());
+            }
+        })
+        .unwrap();
+    let way = way.unwrap();
+    assert_eq!(way.refs, [105, 106, 108, 105]);
+    assert!(way
+        .tags
+        .contains(&("name".to_string(), "triangle".to_string())));
+    assert_eq!(way.info.user.as_deref(), Some("testuser"));
+}
+
+#[test]
+fn extract_bbox_empty() {
+    let mut reader = IndexedReader::from_path(TEST_FILE_PATH).unwrap();
+    let bbox = HeaderBBox {
+        left: 0.0,
+        right: 1.0,
+        top: 1.0,
+        bottom: 0.0,
+    };
+    let bytes = extract_bbox(&mut reader, &bbox, Vec::new()).unwrap();
+    let (_, elements) = read_extract(&bytes);
+    assert!(elements.is_empty());
+}
+
+#[test]
+fn extract_polygon_relations() {
+    let mut reader = IndexedReader::new(Cursor::new(line_file())).unwrap();
+
+    // A triangle around node 3 only
+    let polygon = Polygon::new(vec![(2.5, -1.0), (3.5, -1.0), (3.0, 1.0)]);
+    let bytes = extract_polygon(&mut reader, &polygon, Vec::new()).unwrap();
+    let (_, elements) = read_extract(&bytes);
+
+    // Way 11 is complete, relation 21 has node 3 as member and 22 and 23 are its parents.
+    assert_eq!(
+        elements,
+        [
+            ('n', 2),
+            ('n', 3),
+            ('n', 4),
+            ('w', 11),
+            ('r', 21),
+            ('r', 22),
+            ('r', 23)
+        ]
+    );
+
+    // A polygon with a hole around node 5. Relation 21 is not included, because node 3 is only
+    // included as a node of way 11.
+    let polygon = Polygon::with_rings(vec![
+        vec![(3.5, -1.0), (5.5, -1.0), (5.5, 1.0), (3.5, 1.0)],
+        vec![(4.8, -0.5), (5.2, -0.5), (5.2, 0.5), (4.8, 0.5)],
+    ]);
+    let bytes = extract_polygon(&mut reader, &polygon, Vec::new()).unwrap();
+    let (_, elements) = read_extract(&bytes);
+
+    assert_eq!(
+        elements,
+        [
+            ('n', 2),
+            ('n', 3),
+            ('n', 4),
+            ('n', 5),
+            ('w', 11),
+            ('w', 12),
+            ('r', 20),
+            ('r', 24)
+        ]
+    );
+}
+
+#[test]
+fn extract_empty_polygon() {
+    let mut reader = IndexedReader::new(Cursor::new(line_file())).unwrap();
+    let polygon = Polygon::with_rings(vec![]);
+    let bytes = extract_polygon(&mut reader, &polygon, Vec::new()).unwrap();
+    let (header, elements) = read_extract(&bytes);
+
+    // The infinite bounding box of the polygon is not stored.
+    assert!(elements.is_empty());
+    assert!(header.bbox().is_none());
+}
+
+#[test]
+fn extract_is_sorted() {
+    let mut reader = IndexedReader::new(Cursor::new(line_file())).unwrap();
+    let bbox = HeaderBBox {
+        left: 0.0,
+        right: 10.0,
+        top: 1.0,
+        bottom: -1.0,
+    };
+    let bytes = extract_bbox(&mut reader, &bbox, Vec::new()).unwrap();
+    let (_, elements) = read_extract(&bytes);
+
+    assert_eq!(elements.len(), 13);
+    let mut sorted = elements.clone();
+    sorted.sort_by_key(|&(t, id)| (['n', 'w', 'r'].iter().position(|&x| x == t), id));
+    assert_eq!(elements, sorted);
+
+    // Each block contains only one type of elements.
+    for block in ElementReader::new(bytes.as_slice()).blocks() {
+        let block = block.unwrap();
+        let groups = block.groups().count();
+        assert_eq!(groups, 1);
+    }
+}
+
+#[test]
+fn extract_keeps_header() {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut header = HeaderBuilder::new();
+    header
+        .add_required_feature("HistoricalInformation")
+        .set_writing_program("test");
+    let mut writer = PbfWriter::new(Vec::new(), &header.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 0, nano(1.0), no_tags, None);
+    block.add_dense_node(1, 0, nano(1.1), no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+    let mut reader = IndexedReader::new(Cursor::new(writer.into_inner().unwrap())).unwrap();
+
+    let bbox = HeaderBBox {
+        left: 0.0,
+        right: 2.0,
+        top: 1.0,
+        bottom: -1.0,
+    };
+    let bytes = extract_bbox(&mut reader, &bbox, Vec::new()).unwrap();
+    let (header, elements) = read_extract(&bytes);
+
+    // Both versions of node 1 are kept
+    assert_eq!(elements, [('n', 1), ('n', 1)]);
+    assert!(header
+        .required_features()
+        .contains(&"HistoricalInformation".to_string()));
+    assert_eq!(header.writing_program(), Some("test"));
+    assert!(header.bbox().is_some());
+}
+
+#[test]
+fn extract_unsorted_input() {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(2, 0, nano(1.0), no_tags, None);
+    block.add_dense_node(1, 0, nano(1.0), no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+    let mut reader = IndexedReader::new(Cursor::new(writer.into_inner().unwrap())).unwrap();
+
+    let bbox = HeaderBBox {
+        left: 0.0,
+        right: 2.0,
+        top: 1.0,
+        bottom: -1.0,
+    };
+    let err = extract_bbox(&mut reader, &bbox, Vec::new()).unwrap_err();
+    match err.kind() {
+        ErrorKind::UnsortedInput { element_type, id } => {
+            assert_eq!((*element_type, *id), ("node", 1))
+        }
+        kind => panic!("unexpected error: {kind:?}"),
+    }
+}
@@ -0,0 +1,269 @@
+use osmpbf::*;
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+static TEST_FILE_PATH: &str = "tests/test.osm.pbf";
+static LOC_ON_WAYS_FILE_PATH: &str = "tests/loc_on_ways.osm.pbf";
+
+fn nano(degrees: f64) -> i64 {
+    (degrees * 1e9).round() as i64
+}
+
+/// A tagged node, an open way, a closed area way and a multipolygon with two outer rings.
+fn features_file() -> Vec<u8> {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    for x in 0..4 {
+        for y in 0..4 {
+            block.add_dense_node(10 * x + y, nano(y as f64), nano(x as f64), no_tags, None);
+        }
+    }
+    block.add_dense_node(100, nano(0.5), nano(0.25), [("name", "say \"hi\"")], None);
+    writer.write_block(&block.build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    block.add_way(1, [0, 1, 2], [("highway", "path")], None);
+    block.add_way(2, [0, 10, 11, 1, 0], [("building", "yes")], None);
+    block.add_way(3, [22, 23, 33, 32, 22], no_tags, None);
+    block.add_way(4, [2, 3, 13, 12, 2], no_tags, None);
+    block.add_way(5, [0, 1, 1000], [("highway", "path")], None);
+    writer.write_block(&block.build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    block.add_relation(
+        20,
+        [
+            (RelMemberType::Way, 3, "outer"),
+            (RelMemberType::Way, 4, "outer"),
+        ],
+        [("type", "multipolygon"), ("natural", "water")],
+        None,
+    );
+    writer.write_block(&block.build()).unwrap();
+
+    writer.into_inner().unwrap()
+}
+
+fn parse_sequence(bytes: &[u8]) -> Vec<Value> {
+    std::str::from_utf8(bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn export_feature_collection() {
+    let reader = ElementReader::from_path(TEST_FILE_PATH).unwrap();
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::FeatureCollection);
+    let bytes = export_geojson(reader, &mut SparseLocationStore::new(), writer).unwrap();
+    let collection: Value = serde_json::from_slice(&bytes).unwrap();
+
+    assert_eq!(
+        collection,
+        json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "id": "w107",
+                "properties": {"building": "yes", "name": "triangle"},
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[
+                        [11.6284017, 52.1224031],
+                        [11.6256446, 52.1199235],
+                        [11.6310192, 52.1198991],
+                        [11.6284017, 52.1224031]
+                    ]]
+                }
+            }]
+        })
+    );
+}
+
+#[test]
+fn export_empty_feature_collection() {
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::FeatureCollection);
+    let collection: Value = serde_json::from_slice(&writer.finish().unwrap()).unwrap();
+    assert_eq!(
+        collection,
+        json!({"type": "FeatureCollection", "features": []})
+    );
+}
+
+#[test]
+fn export_sequence_locations_on_ways() {
+    let reader = ElementReader::from_path(LOC_ON_WAYS_FILE_PATH).unwrap();
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let bytes = export_geojson(reader, &mut SparseLocationStore::new(), writer).unwrap();
+    let features = parse_sequence(&bytes);
+
+    assert_eq!(features.len(), 1);
+    assert_eq!(features[0]["id"], "w107");
+    assert_eq!(features[0]["geometry"]["type"], "Polygon");
+}
+
+#[test]
+fn export_indexed_with_multipolygons() {
+    let mut reader = IndexedReader::new(Cursor::new(features_file())).unwrap();
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let bytes =
+        export_geojson_indexed(&mut reader, &mut SortedLocationStore::new(), writer, true).unwrap();
+    let features = parse_sequence(&bytes);
+
+    let ids: Vec<&str> = features.iter().map(|f| f["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["n100", "w1", "w2", "r20"]);
+
+    assert_eq!(
+        features[0]["geometry"],
+        json!({"type": "Point", "coordinates": [0.25, 0.5]})
+    );
+    assert_eq!(features[0]["properties"]["name"], "say \"hi\"");
+    assert_eq!(
+        features[1]["geometry"],
+        json!({"type": "LineString", "coordinates": [[0, 0], [0, 1], [0, 2]]})
+    );
+    assert_eq!(
+        features[2]["geometry"],
+        json!({"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]]})
+    );
+    assert_eq!(features[3]["geometry"]["type"], "MultiPolygon");
+    assert_eq!(
+        features[3]["geometry"]["coordinates"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(features[3]["properties"]["natural"], "water");
+}
+
+#[test]
+fn export_indexed_without_multipolygons() {
+    let mut reader = IndexedReader::new(Cursor::new(features_file())).unwrap();
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let bytes =
+        export_geojson_indexed(&mut reader, &mut DenseLocationStore::new(), writer, false).unwrap();
+    let ids: Vec<String> = parse_sequence(&bytes)
+        .iter()
+        .map(|f| f["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(ids, ["n100", "w1", "w2"]);
+
+    // The single pass export gives the same result.
+    let reader = ElementReader::new(Cursor::new(features_file()));
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let single_pass = export_geojson(reader, &mut DenseLocationStore::new(), writer).unwrap();
+    assert_eq!(single_pass, bytes);
+}
+
+#[test]
+fn export_nodes_after_ways() {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(5, 0, nano(1.0), no_tags, None);
+    block.add_dense_node(6, 0, nano(2.0), no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_way(10, [5, 6], [("highway", "path")], None);
+    writer.write_block(&block.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 0, 0, no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_way(11, [1, 5], [("highway", "path")], None);
+    writer.write_block(&block.build()).unwrap();
+    let file = writer.into_inner().unwrap();
+
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let bytes = export_geojson(
+        ElementReader::new(file.as_slice()),
+        &mut SortedLocationStore::new(),
+        writer,
+    )
+    .unwrap();
+    let features = parse_sequence(&bytes);
+
+    assert_eq!(features.len(), 2);
+    assert_eq!(features[1]["id"], "w11");
+    assert_eq!(
+        features[1]["geometry"],
+        json!({"type": "LineString", "coordinates": [[0, 0], [1, 0]]})
+    );
+}
+
+#[test]
+fn export_closed_linear_ways() {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    for (id, x, y) in [(1, 0.0, 0.0), (2, 1.0, 0.0), (3, 1.0, 1.0)] {
+        block.add_dense_node(id, nano(y), nano(x), no_tags, None);
+    }
+    writer.write_block(&block.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_way(
+        10,
+        [1, 2, 3, 1],
+        [("highway", "residential"), ("junction", "roundabout")],
+        None,
+    );
+    block.add_way(11, [1, 2, 3, 1], [("barrier", "fence")], None);
+    block.add_way(
+        12,
+        [1, 2, 3, 1],
+        [("highway", "pedestrian"), ("area", "yes")],
+        None,
+    );
+    block.add_way(13, [1, 2, 3, 1], [("natural", "coastline")], None);
+    block.add_way(14, [1, 2, 3, 1], [("natural", "wood")], None);
+    writer.write_block(&block.build()).unwrap();
+    let file = writer.into_inner().unwrap();
+
+    let expected = [
+        ("w10", "LineString"),
+        ("w11", "LineString"),
+        ("w12", "Polygon"),
+        ("w13", "LineString"),
+        ("w14", "Polygon"),
+    ];
+    let types = |bytes: Vec<u8>| -> Vec<(String, String)> {
+        parse_sequence(&bytes)
+            .iter()
+            .map(|f| {
+                (
+                    f["id"].as_str().unwrap().to_string(),
+                    f["geometry"]["type"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    };
+
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let bytes = export_geojson(
+        ElementReader::new(file.as_slice()),
+        &mut SparseLocationStore::new(),
+        writer,
+    )
+    .unwrap();
+    let mut features = types(bytes);
+    features.sort();
+    assert_eq!(
+        features,
+        expected.map(|(id, t)| (id.to_string(), t.to_string()))
+    );
+
+    let mut reader = IndexedReader::new(Cursor::new(file)).unwrap();
+    let writer = GeoJsonWriter::new(Vec::new(), GeoJsonFormat::Sequence);
+    let bytes =
+        export_geojson_indexed(&mut reader, &mut SparseLocationStore::new(), writer, true).unwrap();
+    let mut features = types(bytes);
+    features.sort();
+    assert_eq!(
+        features,
+        expected.map(|(id, t)| (id.to_string(), t.to_string()))
+    );
+}
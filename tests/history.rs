@@ -0,0 +1,144 @@
+use osmpbf::*;
+
+static HISTORY_FILE_PATH: &str = "tests/deleted_nodes.osh.pbf";
+
+fn version(element: OwnedElement, version: i32, seconds: i64, visible: bool) -> OwnedElement {
+    let mut element = element;
+    let info = OwnedInfo {
+        version: Some(version),
+        milli_timestamp: Some(seconds * 1000),
+        visible: Some(visible),
+        ..Default::default()
+    };
+    match &mut element {
+        OwnedElement::Node(node) => node.info = info,
+        OwnedElement::Way(way) => way.info = info,
+        OwnedElement::Relation(relation) => relation.info = info,
+    }
+    element
+}
+
+fn node(id: i64, version_number: i32, seconds: i64, visible: bool) -> OwnedElement {
+    let node = OwnedNode {
+        id,
+        nano_lat: i64::from(version_number) * 1_000_000_000,
+        ..Default::default()
+    };
+    version(node.into(), version_number, seconds, visible)
+}
+
+/// Node 1 has two versions, the versions of node 2 are split across two blocks and the last one
+/// is deleted, node 3 is created later. Way 1 has the same ID as node 1.
+fn history_file() -> Vec<u8> {
+    let mut header = HeaderBuilder::new();
+    header.add_required_feature("HistoricalInformation");
+    let mut writer = PbfWriter::new(Vec::new(), &header.build()).unwrap();
+
+    let blocks = [
+        vec![
+            node(1, 1, 1000, true),
+            node(1, 2, 2000, true),
+            node(2, 1, 1000, true),
+        ],
+        vec![node(2, 2, 3000, false), node(3, 1, 5000, true)],
+        vec![version(
+            OwnedWay {
+                id: 1,
+                refs: vec![1, 2],
+                ..Default::default()
+            }
+            .into(),
+            1,
+            1500,
+            true,
+        )],
+    ];
+    for elements in blocks {
+        let mut block = BlockBuilder::new();
+        for element in &elements {
+            block.add_element(element);
+        }
+        writer.write_block(&block.build()).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+/// Returns type, ID and version of the snapshot.
+fn snapshot(bytes: &[u8], seconds: i64) -> Vec<(char, i64, i32)> {
+    ElementReader::new(bytes)
+        .histories()
+        .as_of(seconds * 1000)
+        .map(|element| {
+            let element = element.unwrap();
+            let element_type = match element {
+                OwnedElement::Node(_) => 'n',
+                OwnedElement::Way(_) => 'w',
+                OwnedElement::Relation(_) => 'r',
+            };
+            (element_type, element.id(), element.info().version.unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn read_histories() {
+    let bytes = history_file();
+    let histories: Vec<ElementHistory> = ElementReader::new(bytes.as_slice())
+        .histories()
+        .collect::<Result<_>>()
+        .unwrap();
+
+    let summary: Vec<(i64, usize, bool)> = histories
+        .iter()
+        .map(|h| (h.id(), h.versions().len(), h.is_deleted()))
+        .collect();
+    assert_eq!(
+        summary,
+        [(1, 2, false), (2, 2, true), (3, 1, false), (1, 1, false)]
+    );
+    assert!(matches!(histories[3].latest(), OwnedElement::Way(_)));
+    assert_eq!(histories[0].latest().info().version, Some(2));
+
+    let node_2 = &histories[1];
+    assert_eq!(node_2.at(999_999), None);
+    assert_eq!(node_2.at(1_000_000).unwrap().info().version, Some(1));
+    assert_eq!(node_2.at(2_999_999).unwrap().info().version, Some(1));
+    assert_eq!(node_2.at(3_000_000), None);
+}
+
+#[test]
+fn read_snapshots() {
+    let bytes = history_file();
+    assert_eq!(snapshot(&bytes, 500), []);
+    assert_eq!(
+        snapshot(&bytes, 1500),
+        [('n', 1, 1), ('n', 2, 1), ('w', 1, 1)]
+    );
+    assert_eq!(
+        snapshot(&bytes, 2500),
+        [('n', 1, 2), ('n', 2, 1), ('w', 1, 1)]
+    );
+    assert_eq!(snapshot(&bytes, 3500), [('n', 1, 2), ('w', 1, 1)]);
+    assert_eq!(
+        snapshot(&bytes, 6000),
+        [('n', 1, 2), ('n', 3, 1), ('w', 1, 1)]
+    );
+}
+
+#[test]
+fn read_history_file() {
+    let reader = ElementReader::from_path(HISTORY_FILE_PATH).unwrap();
+    let histories: Vec<ElementHistory> = reader.histories().collect::<Result<_>>().unwrap();
+
+    assert_eq!(histories.len(), 2);
+    assert!(histories[0].is_deleted());
+    assert!(!histories[1].is_deleted());
+
+    let reader = ElementReader::from_path(HISTORY_FILE_PATH).unwrap();
+    let ids: Vec<i64> = reader
+        .histories()
+        .as_of(i64::MAX)
+        .map(|element| element.unwrap().id())
+        .collect();
+    assert_eq!(ids, [2]);
+}
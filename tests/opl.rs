@@ -0,0 +1,114 @@
+use osmpbf::*;
+
+static TEST_FILE_PATH: &str = "tests/test.osm.pbf";
+static LOC_ON_WAYS_FILE_PATH: &str = "tests/loc_on_ways.osm.pbf";
+
+static TEST_FILE_OPL: &str = "\
+n105 v1 c0 t2003-04-05T06:07:08Z i17 utestuser T x11.6284017 y52.1224031
+n106 v1 c0 t2003-04-05T06:07:09Z i17 utestuser T x11.6256446 y52.1199235
+n108 v1 c0 t2003-04-05T06:07:10Z i17 utestuser T x11.6310192 y52.1198991
+w107 v1 c0 t2003-04-05T06:07:11Z i17 utestuser Tbuilding=yes,name=triangle Nn105,n106,n108,n105
+r120 v1 c0 t2003-04-05T06:07:12Z i17 utestuser Trel_key=rel_value Mw107@test_role
+";
+
+fn write_opl(path: &str) -> String {
+    let reader = ElementReader::from_path(path).unwrap();
+    let mut writer = OplWriter::new(Vec::new());
+    reader
+        .for_each(|element| writer.write_element(&element).unwrap())
+        .unwrap();
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+fn read_owned(path: &str) -> Vec<OwnedElement> {
+    let mut elements = vec![];
+    ElementReader::from_path(path)
+        .unwrap()
+        .for_each(|element| elements.push(element.to_owned().unwrap()))
+        .unwrap();
+    elements
+}
+
+#[test]
+fn write_opl_test_file() {
+    assert_eq!(write_opl(TEST_FILE_PATH), TEST_FILE_OPL);
+}
+
+#[test]
+fn read_opl_test_file() {
+    let elements: Vec<OwnedElement> = OplReader::new(TEST_FILE_OPL.as_bytes())
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(elements, read_owned(TEST_FILE_PATH));
+}
+
+#[test]
+fn roundtrip_opl_locations_on_ways() {
+    let text = write_opl(LOC_ON_WAYS_FILE_PATH);
+    assert!(text.contains(" Nn105x11.6284017y52.1224031,n106x"));
+
+    let elements: Vec<OwnedElement> = OplReader::new(text.as_bytes())
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(elements, read_owned(LOC_ON_WAYS_FILE_PATH));
+}
+
+#[test]
+fn opl_fixture_to_pbf() {
+    let fixture = "\
+# A hand-written fixture
+n1 Tname=Caf%e9%%20%Europa,amenity=cafe x-0.5 y51.25
+
+w10 v3 dD uJane%20%Doe T Nn1,n2
+r20 T Mn1@,w10@outer%20%ring,r21@
+";
+    let elements: Vec<OwnedElement> = OplReader::new(fixture.as_bytes())
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(elements.len(), 3);
+    assert_eq!(
+        elements[0].tags()[0],
+        ("name".to_string(), "Café Europa".to_string())
+    );
+    assert_eq!(elements[1].info().user.as_deref(), Some("Jane Doe"));
+    assert_eq!(elements[1].info().visible, Some(false));
+
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    writer.write_elements(&elements).unwrap();
+    let bytes = writer.into_inner().unwrap();
+
+    let mut lines = vec![];
+    ElementReader::new(bytes.as_slice())
+        .for_each(|element| lines.push(to_opl(&element.to_owned().unwrap())))
+        .unwrap();
+    assert_eq!(
+        lines,
+        [
+            "n1 Tname=Café%20%Europa,amenity=cafe x-0.5 y51.25",
+            "w10 v3 dD uJane%20%Doe T Nn1,n2",
+            "r20 T Mn1@,w10@outer%20%ring,r21@",
+        ]
+    );
+}
+
+#[test]
+fn read_invalid_opl() {
+    let errors = [
+        ("x1 T", 1),
+        ("n1 T\nn T", 2),
+        ("n1 T\n\n# comment\nn2 v1.5", 4),
+        ("w1 Nw2", 1),
+        ("r1 Mx2@", 1),
+        ("n1 Tkey", 1),
+        ("n1 Tk=v%41", 1),
+        ("n1 t2003-04-05", 1),
+        ("n1 q", 1),
+    ];
+    for (opl, expected_line) in errors {
+        let result: Result<Vec<OwnedElement>> = OplReader::new(opl.as_bytes()).collect();
+        match result.unwrap_err().kind() {
+            ErrorKind::InvalidOpl { line, .. } => assert_eq!(*line, expected_line, "{opl}"),
+            other => panic!("expected InvalidOpl error for {opl}, got {other:?}"),
+        }
+    }
+}
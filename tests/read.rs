@@ -30,6 +30,13 @@ static TEST_FILE_PATHS: &[TestFile; 3] = &[
     },
 ];
 
+// These files were written from tests/test.osm.pbf with zstd, LZ4 and LZMA compressed blobs.
+static COMPRESSED_FILE_PATHS: &[(&str, bool)] = &[
+    ("tests/test_zstd.osm.pbf", cfg!(feature = "zstd")),
+    ("tests/test_lz4.osm.pbf", cfg!(feature = "lz4")),
+    ("tests/test_lzma.osm.pbf", cfg!(feature = "lzma")),
+];
+
 // This file was taken from the libosmium test suite.
 // https://osmcode.org/libosmium/
 static HISTORY_FILE_PATH: TestFile = TestFile {
@@ -221,6 +228,27 @@ fn read_blobs() {
     }
 }
 
+#[test]
+fn read_compressed_blobs() {
+    for &(path, supported) in COMPRESSED_FILE_PATHS {
+        let reader = BlobReader::from_path(path).unwrap();
+        let blobs = reader.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(blobs.len(), 2);
+
+        if supported {
+            let header = blobs[0].to_headerblock().unwrap();
+            check_header_block_content(&header, &TEST_FILE_PATHS[0]);
+            check_primitive_block_content(&blobs[1].to_primitiveblock().unwrap());
+        } else {
+            let err = blobs[1].to_primitiveblock().unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                ErrorKind::Blob(BlobError::UnsupportedCompression { .. })
+            ));
+        }
+    }
+}
+
 #[test]
 fn read_mmap_blobs() {
     for test_file in TEST_FILE_PATHS {
@@ -266,6 +294,75 @@ fn decode_blob() {
     }
 }
 
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn push_bytes_field(out: &mut Vec<u8>, field: u8, value: &[u8]) {
+    out.push(field << 3 | 2);
+    push_varint(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+/// A file with a single uncompressed data blob, encoded by hand, that contains a primitive group
+/// with the changesets 42 and 43.
+fn changesets_file() -> Vec<u8> {
+    let mut group = vec![];
+    for id in [42, 43] {
+        let mut changeset = vec![0x08]; // id
+        push_varint(&mut changeset, id);
+        push_bytes_field(&mut group, 5, &changeset); // changesets
+    }
+    let mut block = vec![];
+    push_bytes_field(&mut block, 1, &[]); // stringtable
+    push_bytes_field(&mut block, 2, &group); // primitivegroup
+
+    let mut blob = vec![];
+    push_bytes_field(&mut blob, 1, &block); // raw
+    blob.push(0x10); // raw_size
+    push_varint(&mut blob, block.len() as u64);
+
+    let mut blob_header = vec![];
+    push_bytes_field(&mut blob_header, 1, b"OSMData"); // type
+    blob_header.push(0x18); // datasize
+    push_varint(&mut blob_header, blob.len() as u64);
+
+    let mut file = (blob_header.len() as u32).to_be_bytes().to_vec();
+    file.extend(blob_header);
+    file.extend(blob);
+    file
+}
+
+#[test]
+fn read_changesets() {
+    let file = changesets_file();
+    let blocks = ElementReader::new(file.as_slice())
+        .blocks()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].elements().count(), 0);
+    let ids: Vec<i64> = blocks[0]
+        .groups()
+        .flat_map(|group| group.changesets())
+        .map(|changeset| changeset.id())
+        .collect();
+    assert_eq!(ids, [42, 43]);
+
+    let mut ids = vec![];
+    ElementReader::new(file.as_slice())
+        .for_each_with_changesets(
+            |_| panic!("no elements expected"),
+            |changeset| ids.push(changeset.id()),
+        )
+        .unwrap();
+    assert_eq!(ids, [42, 43]);
+}
+
 #[test]
 fn read_elements() {
     for test_file in TEST_FILE_PATHS {
@@ -318,6 +415,246 @@ fn read_ways_and_deps() {
     }
 }
 
+#[test]
+fn read_relations_and_deps() {
+    for test_file in TEST_FILE_PATHS {
+        let mut reader = IndexedReader::from_path(test_file.path).unwrap();
+
+        let mut relations = 0;
+        let mut ways = 0;
+        let mut nodes = 0;
+
+        reader
+            .read_relations_and_deps(
+                |relation| {
+                    relation
+                        .tags()
+                        .any(|key_value| key_value == ("rel_key", "rel_value"))
+                },
+                |element| match element {
+                    Element::Relation(_) => relations += 1,
+                    Element::Way(_) => ways += 1,
+                    Element::Node(_) => nodes += 1,
+                    Element::DenseNode(_) => nodes += 1,
+                },
+            )
+            .unwrap();
+
+        assert_eq!(relations, 1);
+        assert_eq!(ways, 1);
+        assert_eq!(nodes, 3);
+    }
+}
+
+/// Writes a file with two blocks: nodes and ways in the first, relations in the second.
+/// Relations 20 and 21 reference each other.
+fn nested_relations_file() -> Vec<u8> {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut first = BlockBuilder::new();
+    for id in 1..=4 {
+        first.add_dense_node(id, id * 1_000_000, id * 1_000_000, no_tags, None);
+    }
+    first.add_way(10, [1, 2], no_tags, None);
+    first.add_way(11, [3, 4], no_tags, None);
+
+    let mut second = BlockBuilder::new();
+    second.add_relation(
+        20,
+        [
+            (RelMemberType::Way, 10, "outer"),
+            (RelMemberType::Relation, 21, ""),
+        ],
+        no_tags,
+        None,
+    );
+    second.add_relation(
+        21,
+        [
+            (RelMemberType::Node, 4, "label"),
+            (RelMemberType::Relation, 20, ""),
+        ],
+        no_tags,
+        None,
+    );
+    second.add_relation(22, [(RelMemberType::Way, 11, "")], no_tags, None);
+
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    writer.write_block(&first.build()).unwrap();
+    writer.write_block(&second.build()).unwrap();
+    writer.into_inner().unwrap()
+}
+
+fn collect_relations_and_deps(recursive: bool) -> (Vec<i64>, Vec<i64>, Vec<i64>) {
+    let mut reader = IndexedReader::new(std::io::Cursor::new(nested_relations_file())).unwrap();
+
+    let mut relations = vec![];
+    let mut ways = vec![];
+    let mut nodes = vec![];
+    let callback = |element: &Element| match element {
+        Element::Relation(relation) => relations.push(relation.id()),
+        Element::Way(way) => ways.push(way.id()),
+        Element::Node(node) => nodes.push(node.id()),
+        Element::DenseNode(node) => nodes.push(node.id()),
+    };
+
+    if recursive {
+        reader.read_relations_and_deps_recursive(|relation| relation.id() == 20, callback)
+    } else {
+        reader.read_relations_and_deps(|relation| relation.id() == 20, callback)
+    }
+    .unwrap();
+
+    (relations, ways, nodes)
+}
+
+#[test]
+fn read_relations_and_deps_nested() {
+    assert_eq!(
+        collect_relations_and_deps(false),
+        (vec![20], vec![10], vec![1, 2])
+    );
+    assert_eq!(
+        collect_relations_and_deps(true),
+        (vec![20, 21], vec![10], vec![1, 2, 4])
+    );
+}
+
+/// Writes a file with several blocks of nodes, one block of ways and one block of relations.
+fn multi_block_file(sorted: bool) -> Vec<u8> {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut header = HeaderBuilder::new();
+    if sorted {
+        header.add_optional_feature("Sort.Type_then_ID");
+    }
+    let mut writer = PbfWriter::new(Vec::new(), &header.build()).unwrap();
+
+    for first_id in [1, 11, 21] {
+        let mut block = BlockBuilder::new();
+        for id in first_id..first_id + 10 {
+            block.add_dense_node(id, id * 1_000_000, 0, no_tags, None);
+        }
+        writer.write_block(&block.build()).unwrap();
+    }
+
+    let mut block = BlockBuilder::new();
+    block.add_way(100, [1, 2], no_tags, None);
+    block.add_way(101, [12, 25], no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+
+    let mut block = BlockBuilder::new();
+    block.add_relation(200, [(RelMemberType::Way, 100, "")], no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+
+    writer.into_inner().unwrap()
+}
+
+fn element_id(element: &Element) -> i64 {
+    match element {
+        Element::Node(node) => node.id(),
+        Element::DenseNode(node) => node.id(),
+        Element::Way(way) => way.id(),
+        Element::Relation(relation) => relation.id(),
+    }
+}
+
+#[test]
+fn get_elements_by_id() {
+    for sorted in [true, false] {
+        let file = std::io::Cursor::new(multi_block_file(sorted));
+        let mut reader = IndexedReader::new(file).unwrap();
+
+        assert_eq!(reader.get_node(15, element_id).unwrap(), Some(15));
+        assert_eq!(reader.get_node(31, element_id).unwrap(), None);
+        assert_eq!(reader.get_node(100, element_id).unwrap(), None);
+        assert_eq!(reader.get_way(101, element_id).unwrap(), Some(101));
+        assert_eq!(reader.get_way(1, element_id).unwrap(), None);
+        assert_eq!(reader.get_relation(200, element_id).unwrap(), Some(200));
+
+        let mut ids = vec![];
+        reader
+            .get_nodes([25, 1, 99, 12, 0], |element| ids.push(element_id(element)))
+            .unwrap();
+        assert_eq!(ids, [1, 12, 25]);
+
+        let mut ids = vec![];
+        reader
+            .get_ways([101, 100], |element| ids.push(element_id(element)))
+            .unwrap();
+        assert_eq!(ids, [100, 101]);
+
+        let mut ids = vec![];
+        reader
+            .get_relations([201], |element| ids.push(element_id(element)))
+            .unwrap();
+        assert!(ids.is_empty());
+    }
+
+    for test_file in TEST_FILE_PATHS {
+        let mut reader = IndexedReader::from_path(test_file.path).unwrap();
+        assert_eq!(reader.get_node(106, element_id).unwrap(), Some(106));
+        assert_eq!(reader.get_way(107, element_id).unwrap(), Some(107));
+        assert_eq!(reader.get_relation(120, element_id).unwrap(), Some(120));
+        assert_eq!(reader.get_node(107, element_id).unwrap(), None);
+    }
+}
+
+#[test]
+fn save_and_load_index() {
+    let dir = std::env::temp_dir().join(format!("osmpbf_index_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let pbf_path = dir.join("test.osm.pbf");
+    let index_path = dir.join("test.osm.pbf.idx");
+    std::fs::copy(TEST_FILE_PATHS[0].path, &pbf_path).unwrap();
+
+    // Decode some blocks before saving, so that ID ranges are part of the index.
+    let mut reader = IndexedReader::from_path(&pbf_path).unwrap();
+    reader.read_ways_and_deps(|_| true, |_| {}).unwrap();
+    reader.save_index(&index_path).unwrap();
+
+    let mut reader = IndexedReader::from_path_with_index(&pbf_path, &index_path).unwrap();
+    let mut ids = vec![];
+    reader
+        .read_relations_and_deps(
+            |_| true,
+            |element| match element {
+                Element::Relation(relation) => ids.push(relation.id()),
+                Element::Way(way) => ids.push(way.id()),
+                Element::Node(node) => ids.push(node.id()),
+                Element::DenseNode(node) => ids.push(node.id()),
+            },
+        )
+        .unwrap();
+    assert_eq!(ids, [120, 107, 105, 106, 108]);
+
+    // A truncated index is rejected.
+    let truncated_path = dir.join("truncated.osm.pbf.idx");
+    let bytes = std::fs::read(&index_path).unwrap();
+    std::fs::write(&truncated_path, &bytes[..bytes.len() - 4]).unwrap();
+    let err = IndexedReader::from_path_with_index(&pbf_path, &truncated_path)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::InvalidIndex {
+            reason: "truncated index"
+        }
+    ));
+
+    // The index is stale after the PBF file changed.
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&pbf_path)
+        .unwrap();
+    std::io::Write::write_all(&mut file, &[0]).unwrap();
+    drop(file);
+    let err = IndexedReader::from_path_with_index(&pbf_path, &index_path)
+        .err()
+        .unwrap();
+    assert!(matches!(err.kind(), ErrorKind::InvalidIndex { .. }));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[test]
 fn read_history_file() {
     let reader = BlobReader::from_path(HISTORY_FILE_PATH.path).unwrap();
@@ -418,3 +755,592 @@ fn read_loc_on_ways_file() {
         assert_eq!(members[0].role().unwrap(), "test_role");
     }
 }
+
+#[test]
+fn read_owned_elements() {
+    for test_file in TEST_FILE_PATHS {
+        let reader = ElementReader::from_path(test_file.path).unwrap();
+        let mut elements = vec![];
+        reader
+            .for_each(|element| elements.push(element.to_owned().unwrap()))
+            .unwrap();
+
+        // Owned elements can be moved to another thread.
+        let elements = std::thread::spawn(move || elements).join().unwrap();
+        assert_eq!(elements.len(), 5);
+
+        let OwnedElement::Node(node) = &elements[0] else {
+            panic!("expected node");
+        };
+        assert_eq!(node.id, 105);
+        assert_eq!(node.nano_lat, 52122403100);
+        assert_eq!(node.info.uid, Some(17));
+        assert_eq!(node.info.user.as_deref(), Some("testuser"));
+
+        let OwnedElement::Way(way) = &elements[3] else {
+            panic!("expected way");
+        };
+        assert_eq!(way.id, 107);
+        assert_eq!(way.refs, [105, 106, 108, 105]);
+        assert!(way
+            .tags
+            .contains(&("name".to_string(), "triangle".to_string())));
+        assert!(way.node_locations.is_empty());
+
+        let OwnedElement::Relation(relation) = &elements[4] else {
+            panic!("expected relation");
+        };
+        assert_eq!(relation.id, 120);
+        assert_eq!(
+            relation.members,
+            [OwnedRelMember {
+                member_type: RelMemberType::Way,
+                member_id: 107,
+                role: "test_role".to_string(),
+            }]
+        );
+        assert_eq!(
+            relation.tags,
+            [("rel_key".to_string(), "rel_value".to_string())]
+        );
+    }
+
+    let reader = ElementReader::from_path(LOC_ON_WAYS_FILE_PATH.path).unwrap();
+    let mut ways = vec![];
+    reader
+        .for_each(|element| {
+            if let Element::Way(way) = element {
+                ways.push(way.to_owned().unwrap());
+            }
+        })
+        .unwrap();
+    assert_eq!(ways.len(), 1);
+    assert_eq!(ways[0].node_locations.len(), 4);
+    assert_eq!(ways[0].node_locations[0].nano_lat(), 52122403100);
+    assert_eq!(ways[0].node_locations[0], ways[0].node_locations[3]);
+}
+
+fn owned_elements_with_for_each<R: std::io::Read + Send>(
+    reader: ElementReader<R>,
+) -> Vec<OwnedElement> {
+    let mut elements = vec![];
+    reader
+        .for_each(|element| elements.push(element.to_owned().unwrap()))
+        .unwrap();
+    elements
+}
+
+fn owned_elements_with_cursor<R: std::io::Read + Send>(
+    reader: ElementReader<R>,
+) -> Vec<OwnedElement> {
+    let mut cursor = reader.elements();
+    let mut elements = vec![];
+    while let Some(element) = cursor.next_element() {
+        elements.push(element.unwrap().to_owned().unwrap());
+    }
+    assert!(cursor.next_element().is_none());
+    elements
+}
+
+/// A block with all kinds of groups: nodes, dense nodes (with metadata), ways and relations.
+fn mixed_block_file() -> Vec<u8> {
+    let info = OwnedInfo {
+        version: Some(2),
+        milli_timestamp: Some(1_000_000),
+        changeset: Some(7),
+        uid: Some(3),
+        user: Some("user".to_string()),
+        visible: Some(true),
+    };
+    let mut block = BlockBuilder::new();
+    block.add_node(1, 10, 20, [("a", "b")], Some(&info));
+    for id in 2..6 {
+        block.add_dense_node(id, id * 100, id * 200, [("k", "v")], Some(&info));
+    }
+    block.add_way(10, [1, 2, 3], [("highway", "path")], Some(&info));
+    block.add_relation(
+        20,
+        [(RelMemberType::Way, 10, "outer")],
+        [("type", "route")],
+        None,
+    );
+
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let block = block.build();
+    writer.write_block(&block).unwrap();
+    writer.write_block(&BlockBuilder::new().build()).unwrap();
+    writer.write_block(&block).unwrap();
+    writer.into_inner().unwrap()
+}
+
+#[test]
+fn read_elements_cursor() {
+    let paths = TEST_FILE_PATHS
+        .iter()
+        .chain([&HISTORY_FILE_PATH, &LOC_ON_WAYS_FILE_PATH])
+        .map(|test_file| test_file.path);
+    for path in paths {
+        let expected = owned_elements_with_for_each(ElementReader::from_path(path).unwrap());
+        let elements = owned_elements_with_cursor(ElementReader::from_path(path).unwrap());
+        assert_eq!(elements, expected);
+    }
+
+    for file in [mixed_block_file(), multi_block_file(true)] {
+        let expected = owned_elements_with_for_each(ElementReader::new(file.as_slice()));
+        let elements = owned_elements_with_cursor(ElementReader::new(file.as_slice()));
+        assert!(!elements.is_empty());
+        assert_eq!(elements, expected);
+    }
+}
+
+#[test]
+fn read_elements_cursor_error() {
+    let file = mixed_block_file();
+    let truncated = &file[..file.len() - 10];
+    let mut cursor = ElementReader::new(truncated).elements();
+
+    let mut elements = 0;
+    let mut errors = 0;
+    while let Some(element) = cursor.next_element() {
+        match element {
+            Ok(_) => elements += 1,
+            Err(_) => errors += 1,
+        }
+    }
+    assert_eq!(elements, 7);
+    assert_eq!(errors, 1);
+    assert!(cursor.next_element().is_none());
+}
+
+#[test]
+fn read_blocks() {
+    for test_file in TEST_FILE_PATHS {
+        let reader = ElementReader::from_path(test_file.path).unwrap();
+        let blocks = reader.blocks().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(blocks.len(), 1);
+        check_primitive_block_content(&blocks[0]);
+    }
+
+    // Iterator adaptors allow early exit.
+    let file = multi_block_file(false);
+    let reader = ElementReader::new(file.as_slice());
+    let first_way = reader
+        .blocks()
+        .map(|block| block.unwrap())
+        .find_map(|block| {
+            block.elements().find_map(|element| match element {
+                Element::Way(way) => Some(way.id()),
+                _ => None,
+            })
+        });
+    assert_eq!(first_way, Some(100));
+}
+
+fn block_ids(block: &PrimitiveBlock) -> Vec<i64> {
+    block
+        .elements()
+        .map(|element| element_id(&element))
+        .collect()
+}
+
+#[test]
+fn par_read_blocks_in_order() {
+    let file = multi_block_file(true);
+    let expected: Vec<Vec<i64>> = ElementReader::new(file.as_slice())
+        .blocks()
+        .map(|block| block_ids(&block.unwrap()))
+        .collect();
+    assert_eq!(expected.len(), 5);
+
+    for queue_depth in [1, 2, 3, 64] {
+        let ids = ElementReader::new(std::io::Cursor::new(file.clone()))
+            .par_map_blocks(|block| block_ids(&block))
+            .with_queue_depth(queue_depth)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(ids, expected);
+    }
+
+    for test_file in TEST_FILE_PATHS {
+        let reader = ElementReader::from_path(test_file.path).unwrap();
+        let blocks = reader.par_blocks().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(blocks.len(), 1);
+        check_primitive_block_content(&blocks[0]);
+    }
+}
+
+#[test]
+fn par_read_blocks_error() {
+    let file = multi_block_file(false);
+    let truncated = file[..file.len() - 10].to_vec();
+    let results: Vec<_> = ElementReader::new(std::io::Cursor::new(truncated))
+        .par_map_blocks(|block| block_ids(&block))
+        .collect();
+
+    assert_eq!(results.len(), 5);
+    assert!(results[..4].iter().all(|result| result.is_ok()));
+    assert!(results[4].is_err());
+}
+
+#[test]
+fn par_read_blocks_panic() {
+    let file = multi_block_file(false);
+    let results: Vec<_> = ElementReader::new(std::io::Cursor::new(file))
+        .par_map_blocks(|block| {
+            let ids = block_ids(&block);
+            assert!(!ids.contains(&21), "unexpected block");
+            ids
+        })
+        .collect();
+
+    assert_eq!(results.len(), 3);
+    assert!(results[..2].iter().all(|result| result.is_ok()));
+    match results[2].as_ref().unwrap_err().kind() {
+        ErrorKind::Panicked { message } => assert_eq!(message, "unexpected block"),
+        kind => panic!("unexpected error: {kind:?}"),
+    }
+}
+
+fn unsupported_feature_file() -> Vec<u8> {
+    let mut header = HeaderBuilder::new();
+    header
+        .add_required_feature("OsmSchema-V0.6")
+        .add_required_feature("DenseNodes")
+        .add_required_feature("Fancy-Feature");
+    let mut writer = PbfWriter::new(Vec::new(), &header.build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 0, 0, [("key", "value")], None);
+    writer.write_block(&block.build()).unwrap();
+    writer.into_inner().unwrap()
+}
+
+fn assert_unsupported_feature(err: &Error) {
+    match err.kind() {
+        ErrorKind::UnsupportedRequiredFeature { feature } => assert_eq!(feature, "Fancy-Feature"),
+        kind => panic!("unexpected error: {kind:?}"),
+    }
+}
+
+#[test]
+fn read_header_before_elements() {
+    let reader = ElementReader::from_path("tests/test.osm.pbf").unwrap();
+    let events = std::cell::RefCell::new(vec![]);
+    reader
+        .for_each_with_header(
+            |header| {
+                assert!(header
+                    .required_features()
+                    .iter()
+                    .any(|feature| feature == "OsmSchema-V0.6"));
+                events.borrow_mut().push("header");
+            },
+            |_| events.borrow_mut().push("element"),
+        )
+        .unwrap();
+
+    let events = events.into_inner();
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[0], "header");
+    assert!(events[1..].iter().all(|event| *event == "element"));
+}
+
+#[test]
+fn read_unsupported_required_feature() {
+    let file = unsupported_feature_file();
+    let cursor = || std::io::Cursor::new(file.clone());
+
+    let called = std::cell::Cell::new(false);
+    let err = ElementReader::new(cursor())
+        .for_each_with_header(|_| called.set(true), |_| called.set(true))
+        .unwrap_err();
+    assert_unsupported_feature(&err);
+    assert!(!called.get());
+
+    let err = ElementReader::new(cursor())
+        .for_each(|_| panic!("no elements expected"))
+        .unwrap_err();
+    assert_unsupported_feature(&err);
+
+    let results: Vec<_> = ElementReader::new(cursor()).blocks().collect();
+    assert_eq!(results.len(), 1);
+    assert_unsupported_feature(results[0].as_ref().unwrap_err());
+
+    let results: Vec<_> = ElementReader::new(cursor()).par_blocks().collect();
+    assert_eq!(results.len(), 1);
+    assert_unsupported_feature(results[0].as_ref().unwrap_err());
+
+    let err = ElementReader::new(cursor())
+        .par_map_reduce(|_| 1, || 0, |a, b| a + b)
+        .unwrap_err();
+    assert_unsupported_feature(&err);
+}
+
+#[test]
+fn read_default_granularity() {
+    let reader = ElementReader::from_path("tests/test.osm.pbf").unwrap();
+    for block in reader.blocks() {
+        let block = block.unwrap();
+        assert_eq!(block.granularity(), 100);
+        assert_eq!(block.lat_offset(), 0);
+        assert_eq!(block.lon_offset(), 0);
+        assert_eq!(block.date_granularity(), 1000);
+    }
+}
+
+#[test]
+fn read_custom_granularity() {
+    // granularity.osm.pbf uses a granularity of 1000 nanodegrees, a latitude offset of 1°,
+    // a longitude offset of -0.5° and a date granularity of one minute.
+    let reader = ElementReader::from_path("tests/granularity.osm.pbf").unwrap();
+    let blocks = reader.blocks().collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(blocks.len(), 2);
+
+    for block in &blocks {
+        assert_eq!(block.granularity(), 1000);
+        assert_eq!(block.lat_offset(), 1_000_000_000);
+        assert_eq!(block.lon_offset(), -500_000_000);
+        assert_eq!(block.date_granularity(), 60_000);
+    }
+
+    // Raw timestamp 26_666_667 * 60_000 ms
+    let timestamp = 1_600_000_020_000;
+
+    // Raw coordinates 51_122_403 and 13_901_234
+    let node = blocks[0].groups().flat_map(|g| g.nodes()).next().unwrap();
+    assert_eq!(node.id(), 1);
+    assert_eq!(node.nano_lat(), 1_000_000_000 + 1000 * 51_122_403);
+    assert_eq!(node.nano_lon(), -500_000_000 + 1000 * 13_901_234);
+    assert_approx_eq!(node.lat(), 52.122403, 1e-9);
+    assert_approx_eq!(node.lon(), 13.401234, 1e-9);
+    assert_eq!(node.info().milli_timestamp(), Some(timestamp));
+
+    let way = blocks[0].groups().flat_map(|g| g.ways()).next().unwrap();
+    assert_eq!(way.info().milli_timestamp(), Some(timestamp));
+
+    // Raw coordinates -34_856_789 and 151_715_296
+    let dense = blocks[1]
+        .groups()
+        .flat_map(|g| g.dense_nodes())
+        .next()
+        .unwrap();
+    assert_eq!(dense.id(), 2);
+    assert_eq!(dense.nano_lat(), 1_000_000_000 + 1000 * -34_856_789);
+    assert_eq!(dense.nano_lon(), -500_000_000 + 1000 * 151_715_296);
+    assert_approx_eq!(dense.lat(), -33.856789, 1e-9);
+    assert_approx_eq!(dense.lon(), 151.215296, 1e-9);
+    assert_eq!(dense.info().unwrap().milli_timestamp(), timestamp);
+}
+
+fn tagged_nodes_file() -> Vec<u8> {
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 0, 0, [("amenity", "cafe"), ("name", "A")], None);
+    block.add_dense_node(2, 0, 0, [("amenity", "bench")], None);
+    block.add_dense_node(3, 0, 0, [("shop", "bakery"), ("name", "B")], None);
+    block.add_node(4, 0, 0, [("amenity", "pub"), ("name", "C")], None);
+    block.add_dense_node(5, 0, 0, [("cafe", "amenity")], None);
+    writer.write_block(&block.build()).unwrap();
+
+    // A block without any of the keys above
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(6, 0, 0, [("highway", "crossing")], None);
+    writer.write_block(&block.build()).unwrap();
+    writer.into_inner().unwrap()
+}
+
+fn filtered_ids(file: &[u8], filter: &TagFilter) -> Vec<i64> {
+    let mut ids = vec![];
+    ElementReader::new(std::io::Cursor::new(file))
+        .for_each_filtered(filter, |element| ids.push(element_id(&element)))
+        .unwrap();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn read_tag_filtered() {
+    let file = tagged_nodes_file();
+
+    let mut filter = TagFilter::any();
+    filter.key_values("amenity", ["cafe", "pub"]);
+    assert_eq!(filtered_ids(&file, &filter), [1, 4]);
+
+    let mut filter = TagFilter::any();
+    filter.key("amenity").key_value("shop", "bakery");
+    assert_eq!(filtered_ids(&file, &filter), [1, 2, 3, 4]);
+
+    let mut filter = TagFilter::all();
+    filter
+        .key("name")
+        .key_values("amenity", ["cafe", "bench", "pub"]);
+    assert_eq!(filtered_ids(&file, &filter), [1, 4]);
+
+    let mut filter = TagFilter::all();
+    filter.key("name").key("missing");
+    assert_eq!(filtered_ids(&file, &filter), Vec::<i64>::new());
+
+    assert_eq!(filtered_ids(&file, &TagFilter::any()), Vec::<i64>::new());
+    assert_eq!(filtered_ids(&file, &TagFilter::all()), [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn read_tag_filtered_test_file() {
+    let file = std::fs::read("tests/test.osm.pbf").unwrap();
+
+    let mut filter = TagFilter::any();
+    filter.key_value("building", "yes").key("rel_key");
+    assert_eq!(filtered_ids(&file, &filter), [107, 120]);
+
+    let mut filter = TagFilter::all();
+    filter
+        .key_value("building", "yes")
+        .key_value("name", "square");
+    assert_eq!(filtered_ids(&file, &filter), Vec::<i64>::new());
+
+    let reader = ElementReader::from_path("tests/test.osm.pbf").unwrap();
+    let blocks = reader.blocks().collect::<Result<Vec<_>>>().unwrap();
+    let compiled = filter.compile(&blocks[0]);
+    assert!(!compiled.can_match());
+}
+
+#[test]
+fn read_ways_and_deps_tag_filtered() {
+    let collect = |filtered: bool| {
+        let mut reader = IndexedReader::from_path("tests/test.osm.pbf").unwrap();
+        let mut ids = vec![];
+        let callback = |element: &Element| ids.push(element_id(element));
+        if filtered {
+            let mut filter = TagFilter::any();
+            filter.key_value("building", "yes");
+            reader.read_ways_and_deps_filtered(&filter, callback)
+        } else {
+            reader.read_ways_and_deps(
+                |way| way.tags().any(|kv| kv == ("building", "yes")),
+                callback,
+            )
+        }
+        .unwrap();
+        ids.sort_unstable();
+        ids
+    };
+
+    assert_eq!(collect(true), [105, 106, 107, 108]);
+    assert_eq!(collect(true), collect(false));
+
+    let mut reader = IndexedReader::from_path("tests/test.osm.pbf").unwrap();
+    let mut filter = TagFilter::any();
+    filter.key_value("building", "no");
+    reader
+        .read_ways_and_deps_filtered(&filter, |_| panic!("no match expected"))
+        .unwrap();
+}
+
+fn way_locations<S: NodeLocationStore>(path: &str, store: &mut S) -> Vec<Option<(f64, f64)>> {
+    let mut reader = IndexedReader::from_path(path).unwrap();
+    let mut result = vec![];
+    reader
+        .for_each_way_with_locations(store, |way, locations| {
+            assert_eq!(way.id(), 107);
+            result.extend(
+                locations
+                    .iter()
+                    .map(|loc| loc.map(|loc| (loc.lat(), loc.lon()))),
+            );
+        })
+        .unwrap();
+    result
+}
+
+fn assert_triangle_locations(locations: &[Option<(f64, f64)>]) {
+    let expected = [
+        (52.1224031, 11.6284017),
+        (52.1199235, 11.6256446),
+        (52.1198991, 11.6310192),
+        (52.1224031, 11.6284017),
+    ];
+    assert_eq!(locations.len(), expected.len());
+    for (location, expected) in locations.iter().zip(expected) {
+        let (lat, lon) = location.unwrap();
+        assert_approx_eq!(lat, expected.0, 1e-7);
+        assert_approx_eq!(lon, expected.1, 1e-7);
+    }
+}
+
+#[test]
+fn read_ways_with_locations() {
+    let path = "tests/test.osm.pbf";
+    assert_triangle_locations(&way_locations(path, &mut SparseLocationStore::new()));
+    assert_triangle_locations(&way_locations(path, &mut SortedLocationStore::new()));
+    assert_triangle_locations(&way_locations(path, &mut DenseLocationStore::new()));
+
+    let store_path =
+        std::env::temp_dir().join(format!("osmpbf_locations_test_{}.bin", std::process::id()));
+    let mut store = unsafe { MmapLocationStore::create(&store_path).unwrap() };
+    assert_triangle_locations(&way_locations(path, &mut store));
+    drop(store);
+    std::fs::remove_file(store_path).unwrap();
+
+    // Locations on ways are used directly, the file has no nodes.
+    let mut store = SparseLocationStore::new();
+    assert_triangle_locations(&way_locations("tests/loc_on_ways.osm.pbf", &mut store));
+    assert!(store.is_empty());
+}
+
+#[test]
+fn read_ways_with_missing_locations() {
+    let no_tags: [(&str, &str); 0] = [];
+    let mut writer = PbfWriter::new(Vec::new(), &HeaderBuilder::new().build()).unwrap();
+    let mut block = BlockBuilder::new();
+    block.add_dense_node(1, 10_000_000_000, 20_000_000_000, no_tags, None);
+    block.add_way(2, [1, 3], no_tags, None);
+    writer.write_block(&block.build()).unwrap();
+    let file = writer.into_inner().unwrap();
+
+    let mut reader = IndexedReader::new(std::io::Cursor::new(file)).unwrap();
+    let mut result = vec![];
+    reader
+        .for_each_way_with_locations(&mut DenseLocationStore::new(), |_, locations| {
+            result.extend_from_slice(locations)
+        })
+        .unwrap();
+    assert_eq!(
+        result,
+        [
+            Some(WayNodeLocation::new(10_000_000_000, 20_000_000_000)),
+            None
+        ]
+    );
+}
+
+#[test]
+fn location_stores() {
+    let loc = |i: i64| WayNodeLocation::new(i * 1_000_000_000, -i * 1_000_000_000);
+
+    // Out of order with a duplicate ID: the last location wins.
+    let mut sorted = SortedLocationStore::new();
+    for (id, i) in [(5, 1), (2, 2), (9, 3), (2, 4)] {
+        sorted.set(id, loc(i)).unwrap();
+    }
+    // Without finish, the locations are still found.
+    assert_eq!(sorted.get(2), Some(loc(4)));
+    assert_eq!(sorted.get(3), None);
+    sorted.finish().unwrap();
+    assert_eq!(sorted.len(), 3);
+    assert_eq!(sorted.get(2), Some(loc(4)));
+    assert_eq!(sorted.get(9), Some(loc(3)));
+    assert_eq!(sorted.get(3), None);
+    assert_eq!(SortedLocationStore::default().get(1), None);
+
+    let mut dense = DenseLocationStore::with_max_id(10);
+    dense.set(0, WayNodeLocation::new(0, 0)).unwrap();
+    dense.set(1000, loc(7)).unwrap();
+    assert_eq!(dense.get(0), Some(WayNodeLocation::new(0, 0)));
+    assert_eq!(dense.get(1000), Some(loc(7)));
+    assert_eq!(dense.get(999), None);
+    assert_eq!(dense.get(-1), None);
+    assert!(matches!(
+        dense.set(-1, loc(1)).unwrap_err().kind(),
+        ErrorKind::InvalidNodeLocation { id: -1 }
+    ));
+    assert!(dense.set(1, loc(100)).is_err());
+}
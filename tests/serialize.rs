@@ -0,0 +1,82 @@
+#![cfg(feature = "serde")]
+
+use osmpbf::*;
+use serde_json::json;
+
+static TEST_FILE_PATHS: &[&str] = &[
+    "tests/test.osm.pbf",
+    "tests/test_nozlib.osm.pbf",
+    "tests/test_nozlib_nodense.osm.pbf",
+    "tests/loc_on_ways.osm.pbf",
+    "tests/deleted_nodes.osh.pbf",
+];
+
+#[test]
+fn serialize_elements_as_owned() {
+    for path in TEST_FILE_PATHS {
+        let reader = ElementReader::from_path(path).unwrap();
+        reader
+            .for_each(|element| {
+                let value = serde_json::to_value(&element).unwrap();
+                let owned: OwnedElement = serde_json::from_value(value.clone()).unwrap();
+                assert_eq!(owned, element.to_owned().unwrap());
+                assert_eq!(serde_json::to_value(&owned).unwrap(), value);
+            })
+            .unwrap();
+    }
+}
+
+#[test]
+fn serialize_element_structure() {
+    let reader = ElementReader::from_path("tests/test.osm.pbf").unwrap();
+    let mut values = vec![];
+    reader
+        .for_each(|element| values.push(serde_json::to_value(&element).unwrap()))
+        .unwrap();
+
+    let node = &values[0];
+    assert_eq!(node["type"], "node");
+    assert_eq!(node["id"], 105);
+    assert!((node["lat"].as_f64().unwrap() - 52.1224031).abs() < 1e-9);
+    assert!((node["lon"].as_f64().unwrap() - 11.6284017).abs() < 1e-9);
+    assert_eq!(node["info"]["uid"], 17);
+    assert_eq!(node["info"]["user"], "testuser");
+
+    assert_eq!(
+        values[3]["tags"],
+        json!({"building": "yes", "name": "triangle"})
+    );
+    assert_eq!(values[3]["refs"], json!([105, 106, 108, 105]));
+    assert_eq!(
+        values[4]["members"],
+        json!([{"member_type": "way", "member_id": 107, "role": "test_role"}])
+    );
+}
+
+#[test]
+fn serialize_header() {
+    let bbox = HeaderBBox {
+        left: 11.5,
+        right: 11.75,
+        top: 52.25,
+        bottom: 52.0,
+    };
+    let header = HeaderBuilder::new()
+        .set_bbox(&bbox)
+        .set_osmosis_replication_sequence_number(42)
+        .build();
+    let value = serde_json::to_value(&header).unwrap();
+
+    assert_eq!(
+        value["required_features"],
+        json!(["OsmSchema-V0.6", "DenseNodes"])
+    );
+    assert_eq!(value["osmosis_replication_sequence_number"], 42);
+    assert!(value.get("source").is_none());
+    assert_eq!(
+        value["bbox"],
+        json!({"left": 11.5, "right": 11.75, "top": 52.25, "bottom": 52.0})
+    );
+    let deserialized: HeaderBBox = serde_json::from_value(value["bbox"].clone()).unwrap();
+    assert_eq!(deserialized.top, bbox.top);
+}
@@ -0,0 +1,225 @@
+use osmpbf::*;
+use std::io::Cursor;
+
+static TEST_FILE_PATH: &str = "tests/test.osm.pbf";
+static HISTORY_FILE_PATH: &str = "tests/deleted_nodes.osh.pbf";
+
+/// An element of the given type without tags.
+enum Item {
+    Node(i64),
+    Way(i64),
+}
+
+/// Writes each inner slice as a separate block.
+fn write_blocks(header: &HeaderBuilder, blocks: &[&[Item]]) -> Vec<u8> {
+    let mut writer = PbfWriter::new(vec![], &header.build()).unwrap();
+    for items in blocks {
+        let mut builder = BlockBuilder::new();
+        for item in *items {
+            match *item {
+                Item::Node(id) => {
+                    builder.add_dense_node(id, 0, 0, Vec::<(&str, &str)>::new(), None)
+                }
+                Item::Way(id) => builder.add_way(id, [1], Vec::<(&str, &str)>::new(), None),
+            }
+        }
+        writer.write_block(&builder.build()).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+/// Offsets of all data blobs.
+fn data_blob_offsets(data: &[u8]) -> Vec<ByteOffset> {
+    BlobReader::new_seekable(Cursor::new(data))
+        .unwrap()
+        .map(|blob| blob.unwrap())
+        .filter(|blob| matches!(blob.get_type(), BlobType::OsmData))
+        .map(|blob| blob.offset().unwrap())
+        .collect()
+}
+
+/// Writes the data to a temporary file, so that the reader knows the blob offsets.
+fn check_sort_order(data: &[u8]) -> Option<SortViolation> {
+    let path = std::env::temp_dir().join(format!(
+        "osmpbf-validate-{}-{:?}.osm.pbf",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    std::fs::write(&path, data).unwrap();
+    let violation = ElementReader::from_path(&path)
+        .unwrap()
+        .check_sort_order()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    violation
+}
+
+fn check_blob_sort_order(data: &[u8]) -> Option<SortViolation> {
+    let mut reader = IndexedReader::new(Cursor::new(data)).unwrap();
+    reader.check_blob_sort_order().unwrap()
+}
+
+#[test]
+fn sorted_files() {
+    for path in [TEST_FILE_PATH, HISTORY_FILE_PATH] {
+        let reader = ElementReader::from_path(path).unwrap();
+        assert_eq!(reader.check_sort_order().unwrap(), None);
+
+        let mut reader = IndexedReader::from_path(path).unwrap();
+        assert_eq!(reader.check_blob_sort_order().unwrap(), None);
+    }
+
+    let data = write_blocks(
+        &HeaderBuilder::new(),
+        &[&[Item::Node(1), Item::Node(2)], &[Item::Way(1)]],
+    );
+    assert_eq!(check_sort_order(&data), None);
+    assert_eq!(check_blob_sort_order(&data), None);
+}
+
+#[test]
+fn descending_id_inside_block() {
+    let data = write_blocks(
+        &HeaderBuilder::new(),
+        &[&[Item::Node(1)], &[Item::Node(3), Item::Node(2)]],
+    );
+    let offsets = data_blob_offsets(&data);
+
+    assert_eq!(
+        check_sort_order(&data),
+        Some(SortViolation {
+            kind: SortViolationKind::DescendingId,
+            blob_offset: Some(offsets[1]),
+            element_type: ElementType::Node,
+            id: 2,
+            previous_type: ElementType::Node,
+            previous_id: 3,
+        })
+    );
+    // The blob-level check does not look inside of blobs
+    assert_eq!(check_blob_sort_order(&data), None);
+}
+
+#[test]
+fn type_order_across_blocks() {
+    let data = write_blocks(
+        &HeaderBuilder::new(),
+        &[&[Item::Way(5)], &[Item::Node(1), Item::Node(2)]],
+    );
+    let offsets = data_blob_offsets(&data);
+    let expected = Some(SortViolation {
+        kind: SortViolationKind::TypeOrder,
+        blob_offset: Some(offsets[1]),
+        element_type: ElementType::Node,
+        id: 1,
+        previous_type: ElementType::Way,
+        previous_id: 5,
+    });
+
+    assert_eq!(check_sort_order(&data), expected);
+    assert_eq!(check_blob_sort_order(&data), expected);
+}
+
+#[test]
+fn duplicate_ids() {
+    let blocks: &[&[Item]] = &[
+        &[Item::Node(1), Item::Node(2)],
+        &[Item::Node(2), Item::Node(3)],
+    ];
+    let data = write_blocks(&HeaderBuilder::new(), blocks);
+    let violation = check_sort_order(&data).unwrap();
+    assert_eq!(violation.kind, SortViolationKind::DuplicateId);
+    assert_eq!(violation.id, 2);
+    assert_eq!(violation, check_blob_sort_order(&data).unwrap());
+
+    // Several versions of the same element are allowed in history files
+    let mut header = HeaderBuilder::new();
+    header.add_required_feature("HistoricalInformation");
+    let data = write_blocks(&header, blocks);
+    assert_eq!(check_sort_order(&data), None);
+    assert_eq!(check_blob_sort_order(&data), None);
+}
+
+#[test]
+fn unknown_offset() {
+    let data = write_blocks(&HeaderBuilder::new(), &[&[Item::Node(2), Item::Node(1)]]);
+    let violation = ElementReader::new(data.as_slice())
+        .check_sort_order()
+        .unwrap()
+        .unwrap();
+    assert_eq!(violation.blob_offset, None);
+    assert_eq!(violation.id, 1);
+}
+
+#[test]
+fn header_declares_sort_order() {
+    let mut header = HeaderBuilder::new();
+    assert!(!header.build().is_sorted_by_type_then_id());
+    header.add_optional_feature("Sort.Type_then_ID");
+    assert!(header.build().is_sorted_by_type_then_id());
+}
+
+#[test]
+fn missing_references() {
+    let no_tags = Vec::<(&str, &str)>::new;
+    let mut builder = BlockBuilder::new();
+    builder.add_dense_node(1, 0, 0, no_tags(), None);
+    builder.add_dense_node(2, 0, 0, no_tags(), None);
+    builder.add_way(10, [1, 3, 2], no_tags(), None);
+    builder.add_relation(
+        20,
+        [
+            (RelMemberType::Node, 2, "label"),
+            (RelMemberType::Way, 11, "outer"),
+            (RelMemberType::Relation, 21, "subarea"),
+            (RelMemberType::Relation, 22, ""),
+        ],
+        no_tags(),
+        None,
+    );
+    builder.add_relation(21, [(RelMemberType::Way, 10, "outer")], no_tags(), None);
+    let mut writer = PbfWriter::new(vec![], &HeaderBuilder::new().build()).unwrap();
+    writer.write_block(&builder.build()).unwrap();
+    let data = writer.into_inner().unwrap();
+
+    let mut reader = IndexedReader::new(Cursor::new(data)).unwrap();
+    let mut missing = vec![];
+    reader
+        .for_each_missing_reference(|reference| missing.push(reference))
+        .unwrap();
+
+    assert_eq!(
+        missing,
+        [
+            MissingReference {
+                element_type: ElementType::Node,
+                id: 3,
+                referrer_type: ElementType::Way,
+                referrer_id: 10,
+                role: None,
+            },
+            MissingReference {
+                element_type: ElementType::Way,
+                id: 11,
+                referrer_type: ElementType::Relation,
+                referrer_id: 20,
+                role: Some("outer".to_string()),
+            },
+            MissingReference {
+                element_type: ElementType::Relation,
+                id: 22,
+                referrer_type: ElementType::Relation,
+                referrer_id: 20,
+                role: Some(String::new()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn no_missing_references() {
+    let mut reader = IndexedReader::from_path(TEST_FILE_PATH).unwrap();
+    let mut missing = 0;
+    reader.for_each_missing_reference(|_| missing += 1).unwrap();
+    assert_eq!(missing, 0);
+}
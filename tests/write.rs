@@ -0,0 +1,214 @@
+use osmpbf::*;
+
+static TEST_FILE_PATH: &str = "tests/test.osm.pbf";
+static HISTORY_FILE_PATH: &str = "tests/deleted_nodes.osh.pbf";
+
+/// Copy all elements of a block into a new `BlockBuilder`.
+fn copy_block(block: &PrimitiveBlock) -> BlockBuilder {
+    let mut builder = BlockBuilder::new();
+    for element in block.elements() {
+        match element {
+            Element::Node(node) => builder.add_node(
+                node.id(),
+                node.nano_lat(),
+                node.nano_lon(),
+                node.tags(),
+                Some(&OwnedInfo::try_from(&node.info()).unwrap()),
+            ),
+            Element::DenseNode(node) => builder.add_dense_node(
+                node.id(),
+                node.nano_lat(),
+                node.nano_lon(),
+                node.tags(),
+                node.info()
+                    .map(|info| OwnedInfo::try_from(info).unwrap())
+                    .as_ref(),
+            ),
+            Element::Way(way) => builder.add_way(
+                way.id(),
+                way.refs(),
+                way.tags(),
+                Some(&OwnedInfo::try_from(&way.info()).unwrap()),
+            ),
+            Element::Relation(rel) => {
+                let members: Vec<_> = rel
+                    .members()
+                    .map(|m| (m.member_type.clone(), m.member_id, m.role().unwrap()))
+                    .collect();
+                builder.add_relation(
+                    rel.id(),
+                    members,
+                    rel.tags(),
+                    Some(&OwnedInfo::try_from(&rel.info()).unwrap()),
+                )
+            }
+        }
+    }
+    builder
+}
+
+fn write_copy(path: &str, compression: Compression) -> Vec<u8> {
+    let mut reader = BlobReader::from_path(path).unwrap();
+    let header = reader.next().unwrap().unwrap().to_headerblock().unwrap();
+    let mut writer = PbfWriter::with_compression(
+        vec![],
+        &HeaderBuilder::from_header(&header).build(),
+        compression,
+    )
+    .unwrap();
+
+    for blob in reader {
+        let block = blob.unwrap().to_primitiveblock().unwrap();
+        writer.write_block(&copy_block(&block).build()).unwrap();
+    }
+
+    writer.into_inner().unwrap()
+}
+
+#[test]
+fn write_roundtrip() {
+    for compression in [Compression::None, Compression::Zlib(6)] {
+        let bytes = write_copy(TEST_FILE_PATH, compression);
+
+        let blobs = BlobReader::new(bytes.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].get_type(), BlobType::OsmHeader);
+        assert_eq!(blobs[1].get_type(), BlobType::OsmData);
+
+        let header = blobs[0].to_headerblock().unwrap();
+        assert!(header
+            .required_features()
+            .contains(&"OsmSchema-V0.6".to_string()));
+
+        let block = blobs[1].to_primitiveblock().unwrap();
+        let nodes: Vec<_> = block.groups().flat_map(|g| g.dense_nodes()).collect();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[1].id(), 106);
+        assert_eq!(nodes[1].nano_lat(), 52119923500);
+        assert_eq!(nodes[1].nano_lon(), 11625644600);
+        let info = nodes[1].info().unwrap();
+        assert_eq!(info.uid(), 17);
+        assert_eq!(info.user().unwrap(), "testuser");
+        assert_eq!(info.milli_timestamp(), 1049522829000);
+
+        let ways: Vec<_> = block.groups().flat_map(|g| g.ways()).collect();
+        assert_eq!(ways.len(), 1);
+        assert_eq!(ways[0].refs().collect::<Vec<_>>(), [105, 106, 108, 105]);
+        let tags: Vec<_> = ways[0].tags().collect();
+        assert!(tags.contains(&("building", "yes")));
+        assert!(tags.contains(&("name", "triangle")));
+
+        let relations: Vec<_> = block.groups().flat_map(|g| g.relations()).collect();
+        assert_eq!(relations.len(), 1);
+        let members: Vec<_> = relations[0].members().collect();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].member_type, RelMemberType::Way);
+        assert_eq!(members[0].member_id, 107);
+        assert_eq!(members[0].role().unwrap(), "test_role");
+    }
+}
+
+#[test]
+fn write_header() {
+    let mut builder = HeaderBuilder::new();
+    builder
+        .set_bbox(&HeaderBBox {
+            left: 11.5,
+            right: 11.7,
+            top: 52.2,
+            bottom: 52.1,
+        })
+        .add_optional_feature("Sort.Type_then_ID")
+        .set_osmosis_replication_sequence_number(42);
+    let writer = PbfWriter::new(vec![], &builder.build()).unwrap();
+    let bytes = writer.into_inner().unwrap();
+
+    let mut reader = BlobReader::new(bytes.as_slice());
+    let header = reader.next().unwrap().unwrap().to_headerblock().unwrap();
+    assert!(reader.next().is_none());
+
+    let bbox = header.bbox().unwrap();
+    assert_eq!(bbox.left, 11.5);
+    assert_eq!(bbox.top, 52.2);
+    assert_eq!(header.optional_features(), ["Sort.Type_then_ID"]);
+    assert_eq!(header.osmosis_replication_sequence_number(), Some(42));
+}
+
+/// Collect all dense nodes in a file.
+fn dense_node_values(bytes: &[u8]) -> Vec<OwnedNode> {
+    let mut values = vec![];
+    for blob in BlobReader::new(bytes) {
+        if let BlobDecode::OsmData(block) = blob.unwrap().decode().unwrap() {
+            for node in block.groups().flat_map(|g| g.dense_nodes()) {
+                values.push(node.to_owned().unwrap());
+            }
+        }
+    }
+    values
+}
+
+#[test]
+fn write_dense_nodes_roundtrip() {
+    for path in [TEST_FILE_PATH, HISTORY_FILE_PATH] {
+        let original = std::fs::read(path).unwrap();
+        let copy = write_copy(path, Compression::default());
+
+        let original_values = dense_node_values(&original);
+        assert!(!original_values.is_empty());
+        assert_eq!(original_values, dense_node_values(&copy));
+    }
+}
+
+#[test]
+fn write_dense_nodes_tags() {
+    let mut builder = BlockBuilder::new();
+    builder.add_dense_node(1, 0, 0, [("a", "b"), ("c", "d")], None);
+    builder.add_dense_node(2, 100, -100, Vec::<(&str, &str)>::new(), None);
+    builder.add_dense_node(3, -200, 200, [("a", "e")], None);
+    let block = builder.build();
+
+    let nodes: Vec<_> = block.groups().flat_map(|g| g.dense_nodes()).collect();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(
+        nodes[0].tags().collect::<Vec<_>>(),
+        [("a", "b"), ("c", "d")]
+    );
+    assert_eq!(nodes[1].tags().count(), 0);
+    assert_eq!(nodes[2].tags().collect::<Vec<_>>(), [("a", "e")]);
+    assert_eq!(nodes[2].nano_lat(), -200);
+    assert_eq!(nodes[2].nano_lon(), 200);
+    assert!(nodes.iter().all(|node| node.info().is_none()));
+}
+
+#[test]
+fn write_changesets() {
+    let mut builder = BlockBuilder::new();
+    builder.add_dense_node(1, 0, 0, Vec::<(&str, &str)>::new(), None);
+    builder.add_changeset(42);
+    builder.add_changeset(43);
+    assert_eq!(builder.len(), 3);
+    let block = builder.build();
+
+    // Changesets are not elements
+    assert_eq!(block.elements().count(), 1);
+    let ids: Vec<_> = block
+        .groups()
+        .flat_map(|g| g.changesets())
+        .map(|c| c.id())
+        .collect();
+    assert_eq!(ids, [42, 43]);
+
+    let mut writer = PbfWriter::new(vec![], &HeaderBuilder::new().build()).unwrap();
+    writer.write_block(&block).unwrap();
+    let data = writer.into_inner().unwrap();
+
+    let mut elements = 0;
+    let mut changesets = vec![];
+    ElementReader::new(data.as_slice())
+        .for_each_with_changesets(|_| elements += 1, |c| changesets.push(c.id()))
+        .unwrap();
+    assert_eq!(elements, 1);
+    assert_eq!(changesets, [42, 43]);
+}
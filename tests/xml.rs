@@ -0,0 +1,105 @@
+#![cfg(feature = "xml")]
+
+use osmpbf::*;
+use std::io::Write;
+
+static TEST_FILE_PATH: &str = "tests/test.osm";
+static TEST_PBF_FILE_PATH: &str = "tests/test.osm.pbf";
+
+/// Truncates coordinates to the precision of the PBF test file (100 nanodegrees), sets the
+/// changeset ID that is missing in the XML file to zero and removes the visible flag that is
+/// missing in the PBF file.
+fn normalize(mut element: OwnedElement) -> OwnedElement {
+    let info = match &mut element {
+        OwnedElement::Node(node) => {
+            node.nano_lat = node.nano_lat / 100 * 100;
+            node.nano_lon = node.nano_lon / 100 * 100;
+            &mut node.info
+        }
+        OwnedElement::Way(way) => &mut way.info,
+        OwnedElement::Relation(relation) => &mut relation.info,
+    };
+    info.changeset.get_or_insert(0);
+    info.visible = None;
+    element
+}
+
+#[test]
+fn read_xml_equals_pbf() {
+    let mut xml_elements = vec![];
+    XmlReader::from_path(TEST_FILE_PATH)
+        .unwrap()
+        .for_each(|element| xml_elements.push(normalize(element)))
+        .unwrap();
+
+    let mut pbf_elements = vec![];
+    ElementReader::from_path(TEST_PBF_FILE_PATH)
+        .unwrap()
+        .for_each(|element| pbf_elements.push(element.to_owned().unwrap()))
+        .unwrap();
+
+    assert_eq!(xml_elements.len(), 5);
+    assert_eq!(xml_elements, pbf_elements);
+}
+
+#[test]
+fn read_xml_gzip() {
+    let path = std::env::temp_dir().join("osmpbf-test.osm.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(&std::fs::read(TEST_FILE_PATH).unwrap())
+        .unwrap();
+    std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+    let elements: Vec<OwnedElement> = XmlReader::from_path(&path)
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let ids: Vec<i64> = elements.iter().map(|e| e.id()).collect();
+    assert_eq!(ids, [105, 106, 108, 107, 120]);
+}
+
+#[test]
+fn read_xml_skips_unknown_elements() {
+    let xml = r#"<osm version="0.6">
+      <bounds minlat="0" minlon="0" maxlat="1" maxlon="1"/>
+      <node id="-1" lat="-0.5" lon="0.25"><tag k="name" v="&lt;a&gt; &quot;b&quot;"/></node>
+      <note>text</note>
+      <way id="2"><nd ref="-1"/><extra><nd ref="3"/></extra><nd ref="4"/></way>
+    </osm>"#;
+    let elements: Vec<OwnedElement> = XmlReader::new(xml.as_bytes())
+        .collect::<Result<_>>()
+        .unwrap();
+
+    match &elements[..] {
+        [OwnedElement::Node(node), OwnedElement::Way(way)] => {
+            assert_eq!(node.id, -1);
+            assert_eq!(node.nano_lat, -500_000_000);
+            assert_eq!(node.nano_lon, 250_000_000);
+            assert_eq!(node.tags, [("name".to_string(), "<a> \"b\"".to_string())]);
+            assert_eq!(node.info, OwnedInfo::default());
+            assert_eq!(way.refs, [-1, 4]);
+        }
+        other => panic!("unexpected elements: {other:?}"),
+    }
+}
+
+#[test]
+fn read_invalid_xml() {
+    let errors = [
+        r#"<osmChange><create><node id="1"/></create></osmChange>"#,
+        r#"<osm><node lat="1" lon="2"/></osm>"#,
+        r#"<osm><node id="1" lat="north" lon="2"/></osm>"#,
+        r#"<osm><relation id="1"><member type="area" ref="1"/></relation></osm>"#,
+        r#"<osm><node id="1"></way></osm>"#,
+    ];
+    for xml in errors {
+        let result: Result<Vec<OwnedElement>> = XmlReader::new(xml.as_bytes()).collect();
+        match result.unwrap_err().kind() {
+            ErrorKind::InvalidXml { .. } => {}
+            other => panic!("expected InvalidXml error for {xml}, got {other:?}"),
+        }
+    }
+}